@@ -134,6 +134,126 @@ pub enum AuctionError {
     /// Gap tick size percentage must be between 0 and 100
     #[error("Gap tick size percentage must be between 0 and 100")]
     InvalidGapTickSizePercentage,
+
+    /// Dutch auctions need a valid price schedule, a capped winner count and an end time, and
+    /// cannot use tick sizes, gaps or instant sale prices
+    #[error("Dutch auction settings are invalid")]
+    InvalidDutchAuctionSettings,
+
+    /// Sealed-bid auctions need a reveal window, a capped winner count and an end time, and
+    /// cannot use gaps or instant sale prices
+    #[error("Sealed-bid auction settings are invalid")]
+    InvalidSealedBidSettings,
+
+    /// Bids on a sealed-bid auction must be committed and revealed
+    #[error("Bids on a sealed-bid auction must be committed and revealed")]
+    BidMustBeSealed,
+
+    /// Bids can only be revealed during the reveal window
+    #[error("Bids can only be revealed during the reveal window")]
+    RevealWindowClosed,
+
+    /// Sealed-bid auctions can only be ended once the reveal window has passed
+    #[error("Sealed-bid auctions can only be ended once the reveal window has passed")]
+    RevealWindowOpen,
+
+    /// Second-price settlement needs an English or sealed-bid auction without an instant sale
+    /// price
+    #[error("Settlement mode is not supported by this auction")]
+    InvalidSettlementMode,
+
+    /// The proxy bid and metadata of the current proxy bidder must be passed in
+    #[error("Proxy bid accounts of the current proxy bidder are missing")]
+    MissingProxyBid,
+
+    /// Raffle tickets and draws must pass the bid ledger pages they read and write
+    #[error("Bid ledger page is missing")]
+    MissingBidLedgerPage,
+
+    /// A bidder pot can only be closed once its bid was refunded or claimed
+    #[error("Bidder pot still holds a bid")]
+    BidderPotNotEmpty,
+
+    /// Rent is only returned to the account that paid it
+    #[error("Payer does not match the one that created the account")]
+    PayerMismatch,
+
+    /// An auction can only be closed once every bid was refunded or claimed, auctions created
+    /// before open bids were tracked cannot be closed
+    #[error("Auction still has open bids")]
+    AuctionHasOpenBids,
+
+    /// Gated auctions only take bids from holders of the gating token
+    #[error("Bidder does not hold the token required to bid on this auction")]
+    BidGateNotMet,
+
+    /// Raffles need a capped number of winners and a ticket price at least the price floor, and
+    /// take none of the English auction bid rules
+    #[error("Invalid raffle settings")]
+    InvalidRaffleSettings,
+
+    /// Raffle tickets stay in the draw until the winners have been drawn
+    #[error("Raffle winners have not been drawn yet")]
+    RaffleNotDrawn,
+
+    /// The slot a raffle is drawn from has to pass before its winners can be drawn
+    #[error("Raffle draw slot not reached")]
+    RaffleDrawSlotNotReached,
+
+    /// Bid books have to be allocated zeroed, owned by the auction program and sized for the
+    /// number of winners, and are only used by the auction they were created for
+    #[error("Invalid bid book")]
+    InvalidBidBook,
+
+    /// Bid-book auctions rank bids like an English auction, without gap tick sizes, instant sales
+    /// or second-price settlement
+    #[error("Invalid bid book settings")]
+    InvalidBidBookSettings,
+
+    /// Winners of a bid-book auction are only known once the bid book has been sorted
+    #[error("Bid book has not been sorted yet")]
+    BidBookNotSorted,
+
+    /// Auction account already uses the AuctionDataV2 layout
+    #[error("Auction already uses the latest account layout")]
+    AuctionAlreadyMigrated,
+
+    /// Payment mints are invalid, or not supported by this kind of auction
+    #[error("Invalid payment mint settings")]
+    InvalidPaymentMints,
+
+    /// The rate of a payment mint could not be read, or is zero
+    #[error("Invalid payment rate")]
+    InvalidPaymentRate,
+
+    /// Auction extended accounts of the legacy size have no room for the newer auction settings
+    #[error("Auction extended account is too small for these settings")]
+    LegacyAuctionDataExtended,
+
+    /// The auction does not take bids paid in lamports
+    #[error("Lamport bids not enabled")]
+    LamportBidsNotEnabled,
+
+    /// Cancellation rules are invalid, or not supported by this kind of auction
+    #[error("Invalid cancellation rules")]
+    InvalidCancellationRules,
+
+    /// Winning bids can no longer be cancelled this close to the end of the auction
+    #[error("Winning bids are locked up until the auction ends")]
+    BidLockedUp,
+
+    /// The account the cancellation penalty is paid to does not belong to the auction authority
+    #[error("Penalty account does not belong to the auction authority")]
+    InvalidPenaltyAccount,
+
+    /// Reserve prices are only supported by English and sealed-bid auctions, and cannot exceed
+    /// the instant sale price
+    #[error("Invalid reserve price")]
+    InvalidReservePrice,
+
+    /// The vault is not active, or does not match the accounts given for its buyout
+    #[error("Invalid vault buyout")]
+    InvalidVaultBuyout,
 }
 
 impl PrintProgramError for AuctionError {
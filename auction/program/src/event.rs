@@ -0,0 +1,93 @@
+//! Events logged by the auction program, so indexers can follow auctions without parsing its
+//! other log messages. Each event is logged as EVENT_PREFIX followed by its Borsh serialization in
+//! base64. New events and fields are only ever appended, so older logs keep decoding.
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{clock::UnixTimestamp, entrypoint::ProgramResult, msg, pubkey::Pubkey},
+};
+
+/// Marks an event among the program logs, followed by the encoded event.
+pub const EVENT_PREFIX: &str = "metaplex-auction event: ";
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub enum AuctionEvent {
+    /// An auction was created, and takes bids once started.
+    AuctionCreated {
+        auction: Pubkey,
+        authority: Pubkey,
+        resource: Pubkey,
+        token_mint: Pubkey,
+    },
+    /// An auction started taking bids, until ended_at when set.
+    AuctionStarted {
+        auction: Pubkey,
+        ended_at: Option<UnixTimestamp>,
+    },
+    /// A bid of amount, in the token_mint, paid as payment tokens of mint.
+    BidPlaced {
+        auction: Pubkey,
+        bidder: Pubkey,
+        amount: u64,
+        mint: Pubkey,
+        payment: u64,
+    },
+    /// A bid was raised in place to amount.
+    BidIncreased {
+        auction: Pubkey,
+        bidder: Pubkey,
+        amount: u64,
+    },
+    /// A bid was cancelled, refunding its pot less the cancellation penalty.
+    BidCancelled {
+        auction: Pubkey,
+        bidder: Pubkey,
+        refunded: u64,
+        penalty: u64,
+    },
+    /// An auction stopped taking bids.
+    AuctionEnded {
+        auction: Pubkey,
+        ended_at: Option<UnixTimestamp>,
+    },
+    /// An auction was cancelled by its authority, every bid is refunded.
+    AuctionCancelled { auction: Pubkey },
+    /// A winning bid was paid out to destination, refunding the bidder what they did not owe.
+    BidClaimed {
+        auction: Pubkey,
+        bidder: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+        refunded: u64,
+    },
+    /// A losing bid was refunded through RefundBid.
+    BidRefunded {
+        auction: Pubkey,
+        bidder: Pubkey,
+        amount: u64,
+    },
+    /// A sealed bid was revealed at amount, refunding what was deposited on top of it.
+    BidRevealed {
+        auction: Pubkey,
+        bidder: Pubkey,
+        amount: u64,
+        refunded: u64,
+    },
+    /// An auction ended without any bid meeting its reserve, every bid is refunded.
+    ReserveNotMet { auction: Pubkey, reserve: u64 },
+    /// A buyout auction for the whole of a vault was started at the given reserve.
+    VaultBuyoutStarted {
+        auction: Pubkey,
+        vault: Pubkey,
+        reserve: u64,
+    },
+}
+
+impl AuctionEvent {
+    /// Logs the event for indexers.
+    pub fn emit(&self) -> ProgramResult {
+        msg!("{}{}", EVENT_PREFIX, base64::encode(self.try_to_vec()?));
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{EXTENDED, PREFIX};
+use crate::{utils::get_associated_token_address, COMMITMENT, EXTENDED, LEDGER, PREFIX, PROXY};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     instruction::{AccountMeta, Instruction},
@@ -7,9 +7,16 @@ use solana_program::{
 };
 
 pub use crate::processor::{
-    cancel_bid::CancelBidArgs, claim_bid::ClaimBidArgs, create_auction::CreateAuctionArgs,
-    create_auction_v2::CreateAuctionArgsV2, end_auction::EndAuctionArgs, place_bid::PlaceBidArgs,
-    start_auction::StartAuctionArgs,
+    cancel_auction::CancelAuctionArgs, cancel_bid::CancelBidArgs, claim_bid::ClaimBidArgs,
+    close_auction::CloseAuctionArgs, close_bidder_pot::CloseBidderPotArgs,
+    commit_bid::CommitBidArgs, create_auction::CreateAuctionArgs,
+    create_auction_v2::CreateAuctionArgsV2, draw_winners::DrawWinnersArgs,
+    end_auction::EndAuctionArgs, increase_bid::IncreaseBidArgs,
+    migrate_auction::MigrateAuctionArgs, place_bid::PlaceBidArgs, refund_bid::RefundBidArgs,
+    reveal_bid::RevealBidArgs, set_proxy_bid::SetProxyBidArgs,
+    settle_vault_buyout::SettleVaultBuyoutArgs, sort_bid_book::SortBidBookArgs,
+    start_auction::StartAuctionArgs, start_scheduled_auction::StartScheduledAuctionArgs,
+    start_vault_buyout::StartVaultBuyoutArgs, update_auction::UpdateAuctionArgs,
 };
 
 #[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
@@ -21,14 +28,25 @@ pub enum AuctionInstruction {
     ///   3. `[writable]` The pot SPL account, where the tokens will be deposited.
     ///   4. `[writable]` The metadata account, storing information about the bidders actions.
     ///   5. `[writable]` Auction account, containing data about the auction and item being bid on.
-    ///   6. `[writable]` Token mint, for transfer instructions and verification.
-    ///   7. `[]` Clock sysvar
-    ///   8. `[]` Rent sysvar
-    ///   9. `[]` System program
-    ///   10. `[]` SPL Token Program
+    ///   6. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   7. `[writable]` Token mint, for transfer instructions and verification.
+    ///   8. `[]` Clock sysvar
+    ///   9. `[]` Rent sysvar
+    ///   10. `[]` System program
+    ///   11. `[]` SPL Token Program
+    ///   12. `[]` Bid commitment (pda of ['auction', program id, auction key, bidder key, 'commitment']), sealed-bid auctions only
+    ///
+    /// Bid-book auctions take their bid book in place of the bid commitment.
+    ///
+    /// Auctions with a cancellation penalty take the authority's token account for the mint the
+    /// bid was paid in, or their wallet for lamport bids, in place of the bid commitment.
+    ///
+    /// Lamport bids pass the pot in place of the pot SPL account and the bidders primary account
+    /// in place of their token account.
     CancelBid(CancelBidArgs),
 
-    /// Create a new auction account bound to a resource, initially in a pending state.
+    /// Create a new auction account bound to a resource, initially in a pending state. The auction
+    /// is written in the Borsh layout of AuctionData, see MigrateAuction for the fixed one.
     ///   0. `[signer]` The account creating the auction, which is authorised to make changes.
     ///   1. `[writable]` Uninitialized auction account.
     ///   2. `[writable]` Auction extended data account (pda relative to auction of ['auction', program id, vault key, 'extended']).
@@ -46,14 +64,24 @@ pub enum AuctionInstruction {
     ///   6. `[]` Token mint of the auction
     ///   7. `[]` Clock sysvar
     ///   8. `[]` Token program
-    ///   9. `[]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   9. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   10. `[writable]` The bidders token account, refunded whatever the pot holds beyond the settlement price
+    ///   11. `[]` Bid commitment (pda of ['auction', program id, auction key, bidder key, 'commitment']), to claim a forfeited sealed bid
+    ///   12. `[]` Bidder metadata (pda of ['auction', program id, auction key, bidder key, 'metadata']), bid-book auctions only
+    ///
+    /// Bid-book auctions take their bid book in place of the bid commitment.
+    ///
+    /// Lamport bids pass the pot in place of the pot token account, pay out to a wallet as the
+    /// destination and refund the bidder wallet in place of their token account.
     ClaimBid(ClaimBidArgs),
 
-    /// Ends an auction, regardless of end timing conditions
+    /// Ends an auction, regardless of end timing conditions. Bids below a reserve price are dropped
+    /// here, and if none meet it the auction ends with its price floor set to ReserveNotMet.
     ///
     ///   0. `[writable, signer]` Auction authority
     ///   1. `[writable]` Auction
-    ///   6. `[]` Clock sysvar
+    ///   2. `[]` Clock sysvar
+    ///   3. `[]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended']), required for sealed-bid auctions
     EndAuction(EndAuctionArgs),
 
     /// Start an inactive auction.
@@ -75,13 +103,36 @@ pub enum AuctionInstruction {
     ///   3. `[writable]` The pot SPL account, where the tokens will be deposited.
     ///   4. `[writable]` The metadata account, storing information about the bidders actions.
     ///   5. `[writable]` Auction account, containing data about the auction and item being bid on.
-    ///   6. `[writable]` Token mint, for transfer instructions and verification.
-    ///   7. `[signer]` Transfer authority, for moving tokens into the bid pot.
-    ///   8. `[signer]` Payer
-    ///   9. `[]` Clock sysvar
-    ///   10. `[]` Rent sysvar
-    ///   11. `[]` System program
-    ///   12. `[]` SPL Token Program
+    ///   6. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   7. `[writable]` Token mint, for transfer instructions and verification.
+    ///   8. `[signer]` Transfer authority, for moving tokens into the bid pot.
+    ///   9. `[signer]` Payer
+    ///   10. `[]` Clock sysvar
+    ///   11. `[]` Rent sysvar
+    ///   12. `[]` System program
+    ///   13. `[]` SPL Token Program
+    ///
+    /// The accounts from 14 depend on the type of auction, no auction takes more than one set.
+    ///
+    /// English, Dutch and sealed-bid auctions, while a proxy bid is active:
+    ///   14. `[]` Proxy bid of the current proxy bidder (pda of ['auction', program id, auction key, proxy bidder key, 'proxy'])
+    ///   15. `[writable]` Metadata account of the current proxy bidder
+    ///
+    /// Raffles, and open-edition auctions recording the bid in their ledger, which they may skip
+    /// so clients from before the ledger keep working:
+    ///   14. `[writable]` Bid ledger page the bid is recorded on (pda of ['auction', program id, auction key, 'ledger', page number as little endian u64])
+    ///
+    /// Bid-book auctions:
+    ///   14. `[writable]` Bid book of the auction
+    ///
+    /// Gated auctions take the bidder's proof of holding last, see bid_gate_proof_accounts.
+    ///
+    /// Bids paid in a payment mint, with the pot and token mint of that mint, take the price
+    /// account of the mint after the other accounts when its rate is read from one.
+    ///
+    /// Auctions in wrapped SOL created with lamport_bids take bids paid in lamports by passing the
+    /// pot in place of the pot SPL account and the bidders primary account in place of their token
+    /// account, with the native mint as token mint.
     PlaceBid(PlaceBidArgs),
 
     /// Create a new auction account bound to a resource, initially in a pending state.
@@ -91,7 +142,208 @@ pub enum AuctionInstruction {
     ///   2. `[writable]` Auction extended data account (pda relative to auction of ['auction', program id, vault key, 'extended']).
     ///   3. `[]` Rent sysvar
     ///   4. `[]` System account
+    ///   5. `[writable]` Bid book, allocated zeroed with BidBook::len_for(winners) bytes and owned by this program, bid-book auctions only
     CreateAuctionV2(CreateAuctionArgsV2),
+
+    /// Commit a sealed bid on a running sealed-bid auction.
+    ///   0. `[signer]` The bidders primary account, for PDA calculation/transit auth.
+    ///   1. `[writable]` The bidders token account they'll pay the deposit with
+    ///   2. `[writable]` The pot, containing a reference to the stored SPL token account.
+    ///   3. `[writable]` The pot SPL account, where the deposit will be held.
+    ///   4. `[writable]` The metadata account, storing information about the bidders actions.
+    ///   5. `[writable]` Bid commitment (pda of ['auction', program id, auction key, bidder key, 'commitment'])
+    ///   6. `[writable]` Auction account, containing data about the auction and item being bid on.
+    ///   7. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   8. `[]` Token mint, for transfer instructions and verification.
+    ///   9. `[signer]` Transfer authority, for moving tokens into the bid pot.
+    ///   10. `[signer]` Payer
+    ///   11. `[]` Clock sysvar
+    ///   12. `[]` Rent sysvar
+    ///   13. `[]` System program
+    ///   14. `[]` SPL Token Program
+    ///
+    /// Gated auctions take the bidder's proof of holding last, see bid_gate_proof_accounts.
+    CommitBid(CommitBidArgs),
+
+    /// Reveal a sealed bid, placing it into the auction and refunding the rest of the deposit.
+    ///   0. `[signer]` The bidders primary account, for PDA calculation/transit auth.
+    ///   1. `[writable]` The bidders token account they'll receive the refund with
+    ///   2. `[]` The pot, containing a reference to the stored SPL token account.
+    ///   3. `[writable]` The pot SPL account, holding the deposit.
+    ///   4. `[writable]` The metadata account, storing information about the bidders actions.
+    ///   5. `[writable]` Bid commitment (pda of ['auction', program id, auction key, bidder key, 'commitment'])
+    ///   6. `[writable]` Auction account, containing data about the auction and item being bid on.
+    ///   7. `[]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   8. `[]` Clock sysvar
+    ///   9. `[]` SPL Token Program
+    RevealBid(RevealBidArgs),
+
+    /// Raise an existing bid on a running English auction, moving only the difference into the pot.
+    ///   0. `[signer]` The bidders primary account, for PDA calculation/transit auth.
+    ///   1. `[writable]` The bidders token account they'll pay the difference with
+    ///   2. `[]` The pot, containing a reference to the stored SPL token account.
+    ///   3. `[writable]` The pot SPL account, where the tokens will be deposited.
+    ///   4. `[writable]` The metadata account, storing information about the bidders actions.
+    ///   5. `[writable]` Auction account, containing data about the auction and item being bid on.
+    ///   6. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   7. `[signer]` Transfer authority, for moving tokens into the bid pot.
+    ///   8. `[]` Clock sysvar
+    ///   9. `[]` SPL Token Program
+    ///   10. `[]` Proxy bid of the current proxy bidder (pda of ['auction', program id, auction key, proxy bidder key, 'proxy']), required while one is active
+    ///   11. `[writable]` Metadata account of the current proxy bidder, required while a proxy bid is active
+    IncreaseBid(IncreaseBidArgs),
+
+    /// Register a proxy bid on a running English auction, escrowing a maximum the program
+    /// counter-bids from whenever the bidder is outbid. The bidder must already have a live bid.
+    ///   0. `[signer]` The bidders primary account, for PDA calculation/transit auth.
+    ///   1. `[writable]` The bidders token account they'll pay the maximum with
+    ///   2. `[]` The pot, containing a reference to the stored SPL token account.
+    ///   3. `[writable]` The pot SPL account, where the maximum will be held.
+    ///   4. `[writable]` The metadata account, storing information about the bidders actions.
+    ///   5. `[writable]` Proxy bid (pda of ['auction', program id, auction key, bidder key, 'proxy'])
+    ///   6. `[writable]` Auction account, containing data about the auction and item being bid on.
+    ///   7. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   8. `[signer]` Transfer authority, for moving tokens into the bid pot.
+    ///   9. `[signer]` Payer
+    ///   10. `[]` Clock sysvar
+    ///   11. `[]` Rent sysvar
+    ///   12. `[]` System program
+    ///   13. `[]` SPL Token Program
+    ///   14. `[]` Proxy bid of the current proxy bidder, required while one is active
+    ///   15. `[writable]` Metadata account of the current proxy bidder, required while a proxy bid is active
+    SetProxyBid(SetProxyBidArgs),
+
+    /// Cancel an auction that has not ended, dropping all bids so that every bidder can withdraw
+    /// their full pot through CancelBid.
+    ///   0. `[signer]` The creator/authorised account.
+    ///   1. `[writable]` Auction
+    ///   2. `[]` Clock sysvar
+    CancelAuction(CancelAuctionArgs),
+
+    /// Refund losing bids once an auction has ended or was cancelled. Anyone can send this, every
+    /// pot is returned to its bidder's associated token account. Accounts 4 to 9 repeat for each
+    /// bidder refunded.
+    ///   0. `[]` Auction
+    ///   1. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   2. `[]` Clock sysvar
+    ///   3. `[]` SPL Token Program
+    ///   4. `[]` The bidders primary account
+    ///   5. `[writable]` The bidders associated token account for the mint the bid was paid in
+    ///   6. `[writable]` The pot, containing a reference to the stored SPL token account.
+    ///   7. `[writable]` The pot SPL account, where the bid tokens are held.
+    ///   8. `[writable]` The metadata account, storing information about the bidders actions.
+    ///   9. `[]` Bid commitment (pda of ['auction', program id, auction key, bidder key, 'commitment']), or the bid book on bid-book auctions
+    ///
+    /// Lamport bids pass the pot in place of the pot SPL account and the bidders primary account
+    /// in place of their associated token account.
+    RefundBid(RefundBidArgs),
+
+    /// Close the pot, pot token account and metadata of a bidder once the auction is over and
+    /// their bid was refunded or claimed, returning the rent to whoever paid it. The auction
+    /// authority has to sign for a claimed bid, whose metadata may still be needed to redeem the
+    /// prize.
+    ///   0. `[signer]` The bidders primary account
+    ///   1. `[writable]` The pot, containing a reference to the stored SPL token account.
+    ///   2. `[writable]` The pot SPL account, must be empty.
+    ///   3. `[writable]` The metadata account, storing information about the bidders actions.
+    ///   4. `[]` Auction
+    ///   5. `[writable]` Payer recorded on the pot when it was created
+    ///   6. `[]` Clock sysvar
+    ///   7. `[]` SPL Token Program
+    ///   8. `[signer]` Auction authority, only needed when the bid was claimed
+    ///
+    /// Lamport bids pass the pot in place of the pot SPL account.
+    CloseBidderPot(CloseBidderPotArgs),
+
+    /// Close an ended or cancelled auction once every bid on it was refunded or claimed, returning
+    /// the rent to whoever created it.
+    ///   0. `[signer]` The creator/authorised account.
+    ///   1. `[writable]` Auction
+    ///   2. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   3. `[writable]` Payer recorded on the auction when it was created
+    CloseAuction(CloseAuctionArgs),
+
+    /// Start a created auction once its scheduled start time has passed. Anyone can send this.
+    ///   0. `[writable]` Auction
+    ///   1. `[]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   2. `[]` Clock sysvar
+    StartScheduledAuction(StartScheduledAuctionArgs),
+
+    /// Update the end time, gap, price floor, ticks, instant sale price and name of an auction
+    /// that has not started yet.
+    ///   0. `[signer]` The creator/authorised account.
+    ///   1. `[writable]` Auction
+    ///   2. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    UpdateAuction(UpdateAuctionArgs),
+
+    /// Draw the winners of a raffle once ticket sales have closed, permissionless. The first call
+    /// commits to a slot RAFFLE_DRAW_DELAY slots ahead, the winners are drawn from its hash by the
+    /// calls made after it has passed.
+    ///   0. `[writable]` Auction
+    ///   1. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   2. `[]` Clock sysvar
+    ///   3. `[]` SlotHashes sysvar
+    ///   Followed by the bid ledger pages holding the winning tickets, see RaffleConfig::draw_index.
+    DrawWinners(DrawWinnersArgs),
+
+    /// Sort the bid book of a bid-book auction once bidding has closed, permissionless. Repeat
+    /// until every bid is ranked, winners can only claim after.
+    ///   0. `[]` Auction
+    ///   1. `[]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   2. `[]` Clock sysvar
+    ///   3. `[writable]` Bid book of the auction
+    SortBidBook(SortBidBookArgs),
+
+    /// Move an auction from the Borsh layout of AuctionData onto AuctionDataV2, at the request of
+    /// its authority. Afterwards the account no longer deserializes as AuctionData through Borsh: a fixed-size
+    /// header with the layout key AUCTION_DATA_V2_KEY at byte 64, in place of the last_bid Option
+    /// tag, is followed by the bids as 32-byte bidder keys and little-endian u64 amounts. Every
+    /// instruction reads either layout.
+    ///   0. `[writable]` Auction
+    ///   1. `[signer]` Authority of the auction
+    MigrateAuction(MigrateAuctionArgs),
+
+    /// Start an English auction for the whole of an active vault, permissionless. Bids are paid in
+    /// the mint of the redeem treasury and must meet the reserve, the circulating shares at the
+    /// share-weighted median price share holders voted for, once enough of the circulating shares
+    /// are locked in votes. A buyout that ended without bids can be started again.
+    ///   0. `[signer]` The account paying for the auction accounts
+    ///   1. `[writable]` Auction (pda of ['auction', program id, buyout authority])
+    ///   2. `[writable]` Auction extended (pda of ['auction', program id, buyout authority, 'extended'])
+    ///   3. `[]` Buyout authority (pda of ['auction', program id, vault, 'buyout'])
+    ///   4. `[]` Vault
+    ///   5. `[]` Fraction mint of the vault
+    ///   6. `[]` Fraction treasury of the vault
+    ///   7. `[]` Redeem treasury of the vault
+    ///   8. `[]` External pricing account of the vault
+    ///   9. `[]` Clock sysvar
+    ///   10. `[]` Rent sysvar
+    ///   11. `[]` System program
+    ///   12. `[]` Reserve votes of the vault (pda of the vault program ['vault', vault program id, vault, 'votes'])
+    StartVaultBuyout(StartVaultBuyoutArgs),
+
+    /// Settle a buyout auction once it is over, permissionless. The winning bid is paid into the
+    /// redeem treasury and the vault combined with the winner as its authority, shareholders then
+    /// redeem their shares for their part of the bid. Outbid buyers cancel their bids as usual.
+    /// If the vault is no longer active the buyout is cancelled and every bid refunded instead.
+    ///   0. `[writable]` Auction (pda of ['auction', program id, buyout authority])
+    ///   1. `[writable]` Auction extended (pda of ['auction', program id, buyout authority, 'extended'])
+    ///   2. `[]` Buyout authority (pda of ['auction', program id, vault, 'buyout'])
+    ///   3. `[writable]` Vault
+    ///   4. `[writable]` Fraction mint of the vault
+    ///   5. `[writable]` Fraction treasury of the vault
+    ///   6. `[writable]` Redeem treasury of the vault
+    ///   7. `[]` Burn authority of the vault (pda of ['vault', vault program id, vault])
+    ///   8. `[writable]` Winning bidder pot SPL account
+    ///   9. `[writable]` Winning bidder pot
+    ///   10. `[]` Winning bidder, the new authority of the vault
+    ///   11. `[writable]` Winning bidder token account, refunded whatever the bid did not owe
+    ///   12. `[]` Token mint of the auction
+    ///   13. `[]` Clock sysvar
+    ///   14. `[]` Token program
+    ///   15. `[]` Token vault program
+    ///   16. `[]` Auction program
+    SettleVaultBuyout(SettleVaultBuyoutArgs),
 }
 
 /// Creates an CreateAuction instruction.
@@ -151,15 +403,21 @@ pub fn create_auction_instruction_v2(
     ];
     let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
 
+    let mut accounts = vec![
+        AccountMeta::new(creator_pubkey, true),
+        AccountMeta::new(auction_pubkey, false),
+        AccountMeta::new(auction_extended_pubkey, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+
+    if let Some(bid_book) = args.bid_book {
+        accounts.push(AccountMeta::new(bid_book, false));
+    }
+
     Instruction {
         program_id,
-        accounts: vec![
-            AccountMeta::new(creator_pubkey, true),
-            AccountMeta::new(auction_pubkey, false),
-            AccountMeta::new(auction_extended_pubkey, false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(solana_program::system_program::id(), false),
-        ],
+        accounts,
         data: AuctionInstruction::CreateAuctionV2(args)
             .try_to_vec()
             .unwrap(),
@@ -211,7 +469,8 @@ pub fn start_auction_instruction(
     }
 }
 
-/// Creates an PlaceBid instruction.
+/// Creates an PlaceBid instruction. The proxy bidder, bid ledger page and bid book all go in the
+/// accounts from 14, pass only the one the type of auction takes.
 pub fn place_bid_instruction(
     program_id: Pubkey,
     bidder_pubkey: Pubkey,
@@ -220,6 +479,9 @@ pub fn place_bid_instruction(
     token_mint_pubkey: Pubkey,
     transfer_authority: Pubkey,
     payer: Pubkey,
+    proxy_bidder_pubkey: Option<Pubkey>,
+    bid_ledger_page: Option<u64>,
+    bid_book_pubkey: Option<Pubkey>,
     args: PlaceBidArgs,
 ) -> Instruction {
     // Derive Auction Key
@@ -257,24 +519,47 @@ pub fn place_bid_instruction(
     ];
     let (bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
 
+    let mut accounts = vec![
+        AccountMeta::new(bidder_pubkey, true),
+        AccountMeta::new(bidder_token_pubkey, false),
+        AccountMeta::new(bidder_pot_pubkey, false),
+        AccountMeta::new(bidder_pot_token_pubkey, false),
+        AccountMeta::new(bidder_meta_pubkey, false),
+        AccountMeta::new(auction_pubkey, false),
+        AccountMeta::new(auction_extended_pubkey, false),
+        AccountMeta::new(token_mint_pubkey, false),
+        AccountMeta::new_readonly(transfer_authority, true),
+        AccountMeta::new_readonly(payer, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some(proxy_bidder_pubkey) = proxy_bidder_pubkey {
+        accounts.extend(proxy_bid_accounts(
+            &program_id,
+            &auction_pubkey,
+            &proxy_bidder_pubkey,
+        ));
+    }
+    if let Some(page) = bid_ledger_page {
+        let seeds = &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            auction_pubkey.as_ref(),
+            LEDGER.as_bytes(),
+            &page.to_le_bytes(),
+        ];
+        let (bid_ledger_page_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+        accounts.push(AccountMeta::new(bid_ledger_page_pubkey, false));
+    }
+    if let Some(bid_book) = bid_book_pubkey {
+        accounts.push(AccountMeta::new(bid_book, false));
+    }
+
     Instruction {
         program_id,
-        accounts: vec![
-            AccountMeta::new(bidder_pubkey, true),
-            AccountMeta::new(bidder_token_pubkey, false),
-            AccountMeta::new(bidder_pot_pubkey, false),
-            AccountMeta::new(bidder_pot_token_pubkey, false),
-            AccountMeta::new(bidder_meta_pubkey, false),
-            AccountMeta::new(auction_pubkey, false),
-            AccountMeta::new(auction_extended_pubkey, false),
-            AccountMeta::new(token_mint_pubkey, false),
-            AccountMeta::new_readonly(transfer_authority, true),
-            AccountMeta::new_readonly(payer, true),
-            AccountMeta::new_readonly(sysvar::clock::id(), false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(solana_program::system_program::id(), false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts,
         data: AuctionInstruction::PlaceBid(args).try_to_vec().unwrap(),
     }
 }
@@ -286,6 +571,7 @@ pub fn cancel_bid_instruction(
     bidder_token_pubkey: Pubkey,
     bidder_pot_token_pubkey: Pubkey,
     token_mint_pubkey: Pubkey,
+    bid_book_pubkey: Option<Pubkey>,
     args: CancelBidArgs,
 ) -> Instruction {
     // Derive Auction Key
@@ -323,6 +609,16 @@ pub fn cancel_bid_instruction(
     ];
     let (bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
 
+    // Derive Bid Commitment
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+        COMMITMENT.as_bytes(),
+    ];
+    let (bid_commitment_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
     Instruction {
         program_id,
         accounts: vec![
@@ -338,6 +634,8 @@ pub fn cancel_bid_instruction(
             AccountMeta::new_readonly(sysvar::rent::id(), false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             AccountMeta::new_readonly(spl_token::id(), false),
+            // Also where the cancellation penalty is paid to, on auctions with one.
+            AccountMeta::new(bid_book_pubkey.unwrap_or(bid_commitment_pubkey), false),
         ],
         data: AuctionInstruction::CancelBid(args).try_to_vec().unwrap(),
     }
@@ -346,6 +644,7 @@ pub fn cancel_bid_instruction(
 pub fn end_auction_instruction(
     program_id: Pubkey,
     authority_pubkey: Pubkey,
+    auction_extended_pubkey: Option<Pubkey>,
     args: EndAuctionArgs,
 ) -> Instruction {
     // Derive Auction Key
@@ -356,13 +655,19 @@ pub fn end_auction_instruction(
     ];
     let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
 
+    let mut accounts = vec![
+        AccountMeta::new(authority_pubkey, true),
+        AccountMeta::new(auction_pubkey, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+
+    if let Some(auction_extended) = auction_extended_pubkey {
+        accounts.push(AccountMeta::new_readonly(auction_extended, false));
+    }
+
     Instruction {
         program_id,
-        accounts: vec![
-            AccountMeta::new(authority_pubkey, true),
-            AccountMeta::new(auction_pubkey, false),
-            AccountMeta::new_readonly(sysvar::clock::id(), false),
-        ],
+        accounts,
         data: AuctionInstruction::EndAuction(args).try_to_vec().unwrap(),
     }
 }
@@ -374,7 +679,8 @@ pub fn claim_bid_instruction(
     bidder_pubkey: Pubkey,
     bidder_pot_token_pubkey: Pubkey,
     token_mint_pubkey: Pubkey,
-    auction_extended_pubkey: Option<Pubkey>,
+    bidder_token_pubkey: Option<Pubkey>,
+    bid_commitment_pubkey: Option<Pubkey>,
     args: ClaimBidArgs,
 ) -> Instruction {
     // Derive Auction Key
@@ -385,6 +691,14 @@ pub fn claim_bid_instruction(
     ];
     let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
 
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
     // Derive Bidder Pot
     let seeds = &[
         PREFIX.as_bytes(),
@@ -403,11 +717,27 @@ pub fn claim_bid_instruction(
         AccountMeta::new_readonly(bidder_pubkey, false),
         AccountMeta::new_readonly(token_mint_pubkey, false),
         AccountMeta::new_readonly(sysvar::clock::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),        
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(auction_extended_pubkey, false),
     ];
 
-    if let Some(auction_extended) = auction_extended_pubkey {
-        accounts.push(AccountMeta::new_readonly(auction_extended, false));
+    if let Some(bidder_token) = bidder_token_pubkey {
+        accounts.push(AccountMeta::new(bidder_token, false));
+
+        if let Some(bid_commitment) = bid_commitment_pubkey {
+            accounts.push(AccountMeta::new_readonly(bid_commitment, false));
+
+            // Bid-book auctions look the bid up by the bidder metadata.
+            let seeds = &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                auction_pubkey.as_ref(),
+                bidder_pubkey.as_ref(),
+                "metadata".as_bytes(),
+            ];
+            let (bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+            accounts.push(AccountMeta::new_readonly(bidder_meta_pubkey, false));
+        }
     }
 
     Instruction {
@@ -416,3 +746,892 @@ pub fn claim_bid_instruction(
         data: AuctionInstruction::ClaimBid(args).try_to_vec().unwrap(),
     }
 }
+
+/// Creates an CommitBid instruction.
+pub fn commit_bid_instruction(
+    program_id: Pubkey,
+    bidder_pubkey: Pubkey,
+    bidder_token_pubkey: Pubkey,
+    bidder_pot_token_pubkey: Pubkey,
+    token_mint_pubkey: Pubkey,
+    transfer_authority: Pubkey,
+    payer: Pubkey,
+    args: CommitBidArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Pot
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+    ];
+    let (bidder_pot_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Meta
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+        "metadata".as_bytes(),
+    ];
+    let (bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bid Commitment
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+        COMMITMENT.as_bytes(),
+    ];
+    let (bid_commitment_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bidder_pubkey, true),
+            AccountMeta::new(bidder_token_pubkey, false),
+            AccountMeta::new(bidder_pot_pubkey, false),
+            AccountMeta::new(bidder_pot_token_pubkey, false),
+            AccountMeta::new(bidder_meta_pubkey, false),
+            AccountMeta::new(bid_commitment_pubkey, false),
+            AccountMeta::new(auction_pubkey, false),
+            AccountMeta::new(auction_extended_pubkey, false),
+            AccountMeta::new_readonly(token_mint_pubkey, false),
+            AccountMeta::new_readonly(transfer_authority, true),
+            AccountMeta::new_readonly(payer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: AuctionInstruction::CommitBid(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an RevealBid instruction.
+pub fn reveal_bid_instruction(
+    program_id: Pubkey,
+    bidder_pubkey: Pubkey,
+    bidder_token_pubkey: Pubkey,
+    bidder_pot_token_pubkey: Pubkey,
+    args: RevealBidArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Pot
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+    ];
+    let (bidder_pot_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Meta
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+        "metadata".as_bytes(),
+    ];
+    let (bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bid Commitment
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+        COMMITMENT.as_bytes(),
+    ];
+    let (bid_commitment_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(bidder_pubkey, true),
+            AccountMeta::new(bidder_token_pubkey, false),
+            AccountMeta::new_readonly(bidder_pot_pubkey, false),
+            AccountMeta::new(bidder_pot_token_pubkey, false),
+            AccountMeta::new(bidder_meta_pubkey, false),
+            AccountMeta::new(bid_commitment_pubkey, false),
+            AccountMeta::new(auction_pubkey, false),
+            AccountMeta::new_readonly(auction_extended_pubkey, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: AuctionInstruction::RevealBid(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an IncreaseBid instruction.
+pub fn increase_bid_instruction(
+    program_id: Pubkey,
+    bidder_pubkey: Pubkey,
+    bidder_token_pubkey: Pubkey,
+    bidder_pot_token_pubkey: Pubkey,
+    transfer_authority: Pubkey,
+    proxy_bidder_pubkey: Option<Pubkey>,
+    args: IncreaseBidArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Pot
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+    ];
+    let (bidder_pot_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Meta
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+        "metadata".as_bytes(),
+    ];
+    let (bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(bidder_pubkey, true),
+        AccountMeta::new(bidder_token_pubkey, false),
+        AccountMeta::new_readonly(bidder_pot_pubkey, false),
+        AccountMeta::new(bidder_pot_token_pubkey, false),
+        AccountMeta::new(bidder_meta_pubkey, false),
+        AccountMeta::new(auction_pubkey, false),
+        AccountMeta::new(auction_extended_pubkey, false),
+        AccountMeta::new_readonly(transfer_authority, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some(proxy_bidder_pubkey) = proxy_bidder_pubkey {
+        accounts.extend(proxy_bid_accounts(
+            &program_id,
+            &auction_pubkey,
+            &proxy_bidder_pubkey,
+        ));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: AuctionInstruction::IncreaseBid(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an SetProxyBid instruction. The current proxy bidder, if any, has to be passed so the
+/// two proxy bids can be settled against each other.
+pub fn set_proxy_bid_instruction(
+    program_id: Pubkey,
+    bidder_pubkey: Pubkey,
+    bidder_token_pubkey: Pubkey,
+    bidder_pot_token_pubkey: Pubkey,
+    transfer_authority: Pubkey,
+    payer: Pubkey,
+    proxy_bidder_pubkey: Option<Pubkey>,
+    args: SetProxyBidArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Pot
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+    ];
+    let (bidder_pot_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Meta
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+        "metadata".as_bytes(),
+    ];
+    let (bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Proxy Bid
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+        PROXY.as_bytes(),
+    ];
+    let (proxy_bid_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(bidder_pubkey, true),
+        AccountMeta::new(bidder_token_pubkey, false),
+        AccountMeta::new_readonly(bidder_pot_pubkey, false),
+        AccountMeta::new(bidder_pot_token_pubkey, false),
+        AccountMeta::new(bidder_meta_pubkey, false),
+        AccountMeta::new(proxy_bid_pubkey, false),
+        AccountMeta::new(auction_pubkey, false),
+        AccountMeta::new(auction_extended_pubkey, false),
+        AccountMeta::new_readonly(transfer_authority, true),
+        AccountMeta::new(payer, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some(proxy_bidder_pubkey) = proxy_bidder_pubkey {
+        accounts.extend(proxy_bid_accounts(
+            &program_id,
+            &auction_pubkey,
+            &proxy_bidder_pubkey,
+        ));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: AuctionInstruction::SetProxyBid(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Proof that a bidder holds the token a gated auction requires, appended after every other
+/// account of a PlaceBid or CommitBid instruction: a token account of the bidder holding the
+/// token, followed by the token metadata of its mint when the gate is on a creator.
+pub fn bid_gate_proof_accounts(
+    proof_token_pubkey: Pubkey,
+    creator_gated_mint: Option<Pubkey>,
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![AccountMeta::new_readonly(proof_token_pubkey, false)];
+    if let Some(mint) = creator_gated_mint {
+        let metadata_program = metaplex_token_metadata::id();
+        let seeds = &[
+            metaplex_token_metadata::state::PREFIX.as_bytes(),
+            metadata_program.as_ref(),
+            mint.as_ref(),
+        ];
+        let (metadata_pubkey, _) = Pubkey::find_program_address(seeds, &metadata_program);
+        accounts.push(AccountMeta::new_readonly(metadata_pubkey, false));
+    }
+    accounts
+}
+
+/// Proxy bid and bidder metadata of the current proxy bidder, trailing the bid instructions that
+/// can trigger a counter-bid.
+fn proxy_bid_accounts(
+    program_id: &Pubkey,
+    auction_pubkey: &Pubkey,
+    proxy_bidder_pubkey: &Pubkey,
+) -> Vec<AccountMeta> {
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        proxy_bidder_pubkey.as_ref(),
+        PROXY.as_bytes(),
+    ];
+    let (proxy_bid_pubkey, _) = Pubkey::find_program_address(seeds, program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        proxy_bidder_pubkey.as_ref(),
+        "metadata".as_bytes(),
+    ];
+    let (proxy_bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, program_id);
+
+    vec![
+        AccountMeta::new_readonly(proxy_bid_pubkey, false),
+        AccountMeta::new(proxy_bidder_meta_pubkey, false),
+    ]
+}
+
+/// Creates an CancelAuction instruction.
+pub fn cancel_auction_instruction(
+    program_id: Pubkey,
+    authority_pubkey: Pubkey,
+    args: CancelAuctionArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(authority_pubkey, true),
+            AccountMeta::new(auction_pubkey, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: AuctionInstruction::CancelAuction(args)
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates an RefundBid instruction, refunding each `(bidder, bidder pot token)` pair given. The
+/// bids must all have been paid in `token_mint_pubkey`.
+pub fn refund_bid_instruction(
+    program_id: Pubkey,
+    token_mint_pubkey: Pubkey,
+    bidders: &[(Pubkey, Pubkey)],
+    bid_book_pubkey: Option<Pubkey>,
+    args: RefundBidArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(auction_pubkey, false),
+        AccountMeta::new(auction_extended_pubkey, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    for (bidder_pubkey, bidder_pot_token_pubkey) in bidders {
+        let seeds = &[
+            PREFIX.as_bytes(),
+            &program_id.as_ref(),
+            auction_pubkey.as_ref(),
+            bidder_pubkey.as_ref(),
+        ];
+        let (bidder_pot_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            &program_id.as_ref(),
+            auction_pubkey.as_ref(),
+            bidder_pubkey.as_ref(),
+            "metadata".as_bytes(),
+        ];
+        let (bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            &program_id.as_ref(),
+            auction_pubkey.as_ref(),
+            bidder_pubkey.as_ref(),
+            COMMITMENT.as_bytes(),
+        ];
+        let (bid_commitment_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+        accounts.extend(vec![
+            AccountMeta::new_readonly(*bidder_pubkey, false),
+            AccountMeta::new(
+                get_associated_token_address(bidder_pubkey, &token_mint_pubkey),
+                false,
+            ),
+            AccountMeta::new(bidder_pot_pubkey, false),
+            AccountMeta::new(*bidder_pot_token_pubkey, false),
+            AccountMeta::new(bidder_meta_pubkey, false),
+            AccountMeta::new_readonly(bid_book_pubkey.unwrap_or(bid_commitment_pubkey), false),
+        ]);
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: AuctionInstruction::RefundBid(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an CloseBidderPot instruction.
+pub fn close_bidder_pot_instruction(
+    program_id: Pubkey,
+    bidder_pubkey: Pubkey,
+    bidder_pot_token_pubkey: Pubkey,
+    payer_pubkey: Pubkey,
+    authority_pubkey: Option<Pubkey>,
+    args: CloseBidderPotArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Pot
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+    ];
+    let (bidder_pot_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Meta
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+        "metadata".as_bytes(),
+    ];
+    let (bidder_meta_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(bidder_pubkey, true),
+        AccountMeta::new(bidder_pot_pubkey, false),
+        AccountMeta::new(bidder_pot_token_pubkey, false),
+        AccountMeta::new(bidder_meta_pubkey, false),
+        AccountMeta::new_readonly(auction_pubkey, false),
+        AccountMeta::new(payer_pubkey, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some(authority_pubkey) = authority_pubkey {
+        accounts.push(AccountMeta::new_readonly(authority_pubkey, true));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: AuctionInstruction::CloseBidderPot(args)
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates an CloseAuction instruction.
+pub fn close_auction_instruction(
+    program_id: Pubkey,
+    authority_pubkey: Pubkey,
+    payer_pubkey: Pubkey,
+    args: CloseAuctionArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(authority_pubkey, true),
+            AccountMeta::new(auction_pubkey, false),
+            AccountMeta::new(auction_extended_pubkey, false),
+            AccountMeta::new(payer_pubkey, false),
+        ],
+        data: AuctionInstruction::CloseAuction(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an StartScheduledAuction instruction.
+pub fn start_scheduled_auction_instruction(
+    program_id: Pubkey,
+    args: StartScheduledAuctionArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(auction_pubkey, false),
+            AccountMeta::new_readonly(auction_extended_pubkey, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: AuctionInstruction::StartScheduledAuction(args)
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates an UpdateAuction instruction.
+pub fn update_auction_instruction(
+    program_id: Pubkey,
+    authority_pubkey: Pubkey,
+    args: UpdateAuctionArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(authority_pubkey, true),
+            AccountMeta::new(auction_pubkey, false),
+            AccountMeta::new(auction_extended_pubkey, false),
+        ],
+        data: AuctionInstruction::UpdateAuction(args)
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates a DrawWinners instruction.
+pub fn draw_winners_instruction(
+    program_id: Pubkey,
+    bid_ledger_pages: &[u64],
+    args: DrawWinnersArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(auction_pubkey, false),
+        AccountMeta::new(auction_extended_pubkey, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+    ];
+
+    for page in bid_ledger_pages {
+        let page_bytes = page.to_le_bytes();
+        let seeds = &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            auction_pubkey.as_ref(),
+            LEDGER.as_bytes(),
+            &page_bytes,
+        ];
+        let (bid_ledger_page_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+        accounts.push(AccountMeta::new_readonly(bid_ledger_page_pubkey, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: AuctionInstruction::DrawWinners(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a SortBidBook instruction.
+pub fn sort_bid_book_instruction(
+    program_id: Pubkey,
+    bid_book_pubkey: Pubkey,
+    args: SortBidBookArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(auction_pubkey, false),
+            AccountMeta::new_readonly(auction_extended_pubkey, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new(bid_book_pubkey, false),
+        ],
+        data: AuctionInstruction::SortBidBook(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a MigrateAuction instruction.
+pub fn migrate_auction_instruction(
+    program_id: Pubkey,
+    authority_pubkey: Pubkey,
+    args: MigrateAuctionArgs,
+) -> Instruction {
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        args.resource.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(auction_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, true),
+        ],
+        data: AuctionInstruction::MigrateAuction(args)
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Derives the buyout authority of a vault, which both owns and is the resource of its buyout
+/// auction.
+pub fn find_buyout_authority(program_id: &Pubkey, vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault.as_ref(),
+            metaplex_token_vault::state::BUYOUT.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Creates a StartVaultBuyout instruction.
+pub fn start_vault_buyout_instruction(
+    program_id: Pubkey,
+    payer_pubkey: Pubkey,
+    fraction_mint_pubkey: Pubkey,
+    fraction_treasury_pubkey: Pubkey,
+    redeem_treasury_pubkey: Pubkey,
+    external_pricing_pubkey: Pubkey,
+    args: StartVaultBuyoutArgs,
+) -> Instruction {
+    let (buyout_authority_pubkey, _) = find_buyout_authority(&program_id, &args.vault);
+
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        buyout_authority_pubkey.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        buyout_authority_pubkey.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let vault_program_id = metaplex_token_vault::id();
+    let seeds = &[
+        metaplex_token_vault::state::PREFIX.as_bytes(),
+        vault_program_id.as_ref(),
+        args.vault.as_ref(),
+        metaplex_token_vault::state::VOTES.as_bytes(),
+    ];
+    let (reserve_votes_pubkey, _) = Pubkey::find_program_address(seeds, &vault_program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer_pubkey, true),
+            AccountMeta::new(auction_pubkey, false),
+            AccountMeta::new(auction_extended_pubkey, false),
+            AccountMeta::new_readonly(buyout_authority_pubkey, false),
+            AccountMeta::new_readonly(args.vault, false),
+            AccountMeta::new_readonly(fraction_mint_pubkey, false),
+            AccountMeta::new_readonly(fraction_treasury_pubkey, false),
+            AccountMeta::new_readonly(redeem_treasury_pubkey, false),
+            AccountMeta::new_readonly(external_pricing_pubkey, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(reserve_votes_pubkey, false),
+        ],
+        data: AuctionInstruction::StartVaultBuyout(args)
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates a SettleVaultBuyout instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn settle_vault_buyout_instruction(
+    program_id: Pubkey,
+    fraction_mint_pubkey: Pubkey,
+    fraction_treasury_pubkey: Pubkey,
+    redeem_treasury_pubkey: Pubkey,
+    bidder_pubkey: Pubkey,
+    bidder_pot_token_pubkey: Pubkey,
+    bidder_token_pubkey: Pubkey,
+    token_mint_pubkey: Pubkey,
+    args: SettleVaultBuyoutArgs,
+) -> Instruction {
+    let (buyout_authority_pubkey, _) = find_buyout_authority(&program_id, &args.vault);
+
+    // Derive Auction Key
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        buyout_authority_pubkey.as_ref(),
+    ];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        buyout_authority_pubkey.as_ref(),
+        EXTENDED.as_bytes(),
+    ];
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    // Derive Bidder Pot
+    let seeds = &[
+        PREFIX.as_bytes(),
+        &program_id.as_ref(),
+        auction_pubkey.as_ref(),
+        bidder_pubkey.as_ref(),
+    ];
+    let (bidder_pot_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let vault_program_id = metaplex_token_vault::id();
+    let seeds = &[
+        metaplex_token_vault::state::PREFIX.as_bytes(),
+        vault_program_id.as_ref(),
+        args.vault.as_ref(),
+    ];
+    let (burn_authority_pubkey, _) = Pubkey::find_program_address(seeds, &vault_program_id);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(auction_pubkey, false),
+            AccountMeta::new(auction_extended_pubkey, false),
+            AccountMeta::new_readonly(buyout_authority_pubkey, false),
+            AccountMeta::new(args.vault, false),
+            AccountMeta::new(fraction_mint_pubkey, false),
+            AccountMeta::new(fraction_treasury_pubkey, false),
+            AccountMeta::new(redeem_treasury_pubkey, false),
+            AccountMeta::new_readonly(burn_authority_pubkey, false),
+            AccountMeta::new(bidder_pot_token_pubkey, false),
+            AccountMeta::new(bidder_pot_pubkey, false),
+            AccountMeta::new_readonly(bidder_pubkey, false),
+            AccountMeta::new(bidder_token_pubkey, false),
+            AccountMeta::new_readonly(token_mint_pubkey, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(vault_program_id, false),
+            AccountMeta::new_readonly(program_id, false),
+        ],
+        data: AuctionInstruction::SettleVaultBuyout(args)
+            .try_to_vec()
+            .unwrap(),
+    }
+}
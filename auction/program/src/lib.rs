@@ -4,6 +4,7 @@ mod utils;
 
 pub mod entrypoint;
 pub mod errors;
+pub mod event;
 pub mod instruction;
 pub mod processor;
 
@@ -11,4 +12,13 @@ pub mod processor;
 pub const PREFIX: &str = "auction";
 
 pub const EXTENDED: &str = "extended";
+
+/// Suffix of the PDA holding a sealed bid commitment.
+pub const COMMITMENT: &str = "commitment";
+
+/// Suffix of the PDA holding a proxy bid maximum.
+pub const PROXY: &str = "proxy";
+
+/// Suffix of the PDAs holding pages of an open-edition bid ledger.
+pub const LEDGER: &str = "ledger";
 solana_program::declare_id!("auctxRXPeJoc4817jDhf4HbjnhEcr1cCXenosMhK5R8");
@@ -1,31 +1,62 @@
 use crate::errors::AuctionError;
 use arrayref::array_ref;
 use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
 use solana_program::{
     account_info::AccountInfo, borsh::try_from_slice_unchecked, clock::UnixTimestamp,
     entrypoint::ProgramResult, hash::Hash, msg, program_error::ProgramError, pubkey::Pubkey,
 };
-use std::{cell::Ref, cmp, mem};
+use std::{cell::Ref, cmp, convert::TryFrom, mem};
 
 // Declare submodules, each contains a single handler for each instruction variant in the program.
+pub mod cancel_auction;
 pub mod cancel_bid;
 pub mod claim_bid;
+pub mod close_auction;
+pub mod close_bidder_pot;
+pub mod commit_bid;
 pub mod create_auction;
 pub mod create_auction_v2;
+pub mod draw_winners;
 pub mod end_auction;
+pub mod increase_bid;
+pub mod migrate_auction;
 pub mod place_bid;
+pub mod refund_bid;
+pub mod reveal_bid;
 pub mod set_authority;
+pub mod set_proxy_bid;
+pub mod settle_vault_buyout;
+pub mod sort_bid_book;
 pub mod start_auction;
+pub mod start_scheduled_auction;
+pub mod start_vault_buyout;
+pub mod update_auction;
 
 // Re-export submodules handlers + associated types for other programs to consume.
+pub use cancel_auction::*;
 pub use cancel_bid::*;
 pub use claim_bid::*;
+pub use close_auction::*;
+pub use close_bidder_pot::*;
+pub use commit_bid::*;
 pub use create_auction::*;
 pub use create_auction_v2::*;
+pub use draw_winners::*;
 pub use end_auction::*;
+pub use increase_bid::*;
+pub use migrate_auction::*;
 pub use place_bid::*;
+pub use refund_bid::*;
+pub use reveal_bid::*;
 pub use set_authority::*;
+pub use set_proxy_bid::*;
+pub use settle_vault_buyout::*;
+pub use sort_bid_book::*;
 pub use start_auction::*;
+pub use start_scheduled_auction::*;
+pub use start_vault_buyout::*;
+pub use update_auction::*;
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -36,14 +67,38 @@ pub fn process_instruction(
     match AuctionInstruction::try_from_slice(input)? {
         AuctionInstruction::CancelBid(args) => cancel_bid(program_id, accounts, args),
         AuctionInstruction::ClaimBid(args) => claim_bid(program_id, accounts, args),
-        AuctionInstruction::CreateAuction(args) => {
-            create_auction(program_id, accounts, args, None, None)
-        }
+        AuctionInstruction::CreateAuction(args) => create_auction(
+            program_id,
+            accounts,
+            args,
+            AuctionExtendedSettings::default(),
+        ),
         AuctionInstruction::CreateAuctionV2(args) => create_auction_v2(program_id, accounts, args),
         AuctionInstruction::EndAuction(args) => end_auction(program_id, accounts, args),
         AuctionInstruction::PlaceBid(args) => place_bid(program_id, accounts, args),
         AuctionInstruction::SetAuthority => set_authority(program_id, accounts),
         AuctionInstruction::StartAuction(args) => start_auction(program_id, accounts, args),
+        AuctionInstruction::CommitBid(args) => commit_bid(program_id, accounts, args),
+        AuctionInstruction::RevealBid(args) => reveal_bid(program_id, accounts, args),
+        AuctionInstruction::IncreaseBid(args) => increase_bid(program_id, accounts, args),
+        AuctionInstruction::SetProxyBid(args) => set_proxy_bid(program_id, accounts, args),
+        AuctionInstruction::CancelAuction(args) => cancel_auction(program_id, accounts, args),
+        AuctionInstruction::RefundBid(args) => refund_bid(program_id, accounts, args),
+        AuctionInstruction::CloseBidderPot(args) => close_bidder_pot(program_id, accounts, args),
+        AuctionInstruction::CloseAuction(args) => close_auction(program_id, accounts, args),
+        AuctionInstruction::StartScheduledAuction(args) => {
+            start_scheduled_auction(program_id, accounts, args)
+        }
+        AuctionInstruction::UpdateAuction(args) => update_auction(program_id, accounts, args),
+        AuctionInstruction::DrawWinners(args) => draw_winners(program_id, accounts, args),
+        AuctionInstruction::SortBidBook(args) => sort_bid_book(program_id, accounts, args),
+        AuctionInstruction::MigrateAuction(args) => migrate_auction(program_id, accounts, args),
+        AuctionInstruction::StartVaultBuyout(args) => {
+            start_vault_buyout(program_id, accounts, args)
+        }
+        AuctionInstruction::SettleVaultBuyout(args) => {
+            settle_vault_buyout(program_id, accounts, args)
+        }
     }
 }
 
@@ -57,8 +112,227 @@ pub enum PriceFloor {
     None([u8; 32]),
     /// Explicit minimum price, any bid below this is rejected.
     MinimumPrice([u64; 4]),
-    /// Hidden minimum price, revealed at the end of the auction.
+    /// Hidden minimum price, revealed at the end of the auction. English and sealed-bid auctions
+    /// treat the revealed price as a reserve price.
     BlindedPrice(Hash),
+    /// Reserve price, bids below it are taken but cannot win. Once the auction ends they are
+    /// dropped, and if no bid meets the reserve the price floor becomes ReserveNotMet.
+    ReservePrice([u64; 4]),
+    /// Set when an auction ends with no bid meeting its reserve price. The auction has no winners
+    /// and every bid can be cancelled for a refund.
+    ReserveNotMet([u64; 4]),
+}
+
+impl PriceFloor {
+    /// Lowest bid that can win.
+    pub fn winning_minimum(&self) -> u64 {
+        match self {
+            PriceFloor::MinimumPrice(min) | PriceFloor::ReservePrice(min) => min[0],
+            _ => 0,
+        }
+    }
+}
+
+/// Descending price schedule for a dutch auction. The asking price falls in even steps, one per
+/// decay interval, from the start price when the auction starts to the floor price by its end.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct DutchAuctionSchedule {
+    /// Asking price when the auction starts.
+    pub start_price: u64,
+    /// Asking price the auction bottoms out at by its end time.
+    pub floor_price: u64,
+    /// How often, in seconds, the asking price steps down.
+    pub decay_interval: UnixTimestamp,
+}
+
+impl DutchAuctionSchedule {
+    /// Asking price at `now` for an auction that started at `started_at` and runs for `duration`.
+    pub fn price_at(
+        &self,
+        started_at: UnixTimestamp,
+        duration: UnixTimestamp,
+        now: UnixTimestamp,
+    ) -> Result<u64, ProgramError> {
+        let elapsed = now
+            .checked_sub(started_at)
+            .ok_or(AuctionError::NumericalOverflowError)?
+            .max(0)
+            .min(duration);
+
+        let total_steps = cmp::max(duration / self.decay_interval, 1) as u128;
+        let elapsed_steps = (elapsed / self.decay_interval) as u128;
+        let price_range = self.start_price.saturating_sub(self.floor_price) as u128;
+
+        // Use u128 as the range is multiplied before dividing back down.
+        let discount = price_range
+            .checked_mul(cmp::min(elapsed_steps, total_steps))
+            .ok_or(AuctionError::NumericalOverflowError)?
+            .checked_div(total_steps)
+            .ok_or(AuctionError::NumericalOverflowError)?;
+
+        Ok(self.start_price - discount as u64)
+    }
+}
+
+/// Settings for a sealed-bid auction. Bids are committed as hashes until the auction end time,
+/// then revealed during a window that follows it.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SealedBidConfig {
+    /// How long after the auction end time bidders have to reveal their bids.
+    pub reveal_window: UnixTimestamp,
+    /// Whether deposits behind commitments that were never revealed go to the auction authority
+    /// instead of back to the bidder.
+    pub forfeit_unrevealed: bool,
+}
+
+/// Settings for a raffle. Every entry is a ticket bought at a fixed price, and once ticket sales
+/// close the winners are drawn at random through DrawWinners.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct RaffleConfig {
+    /// Price of a ticket, every entry pays exactly this much.
+    pub ticket_price: u64,
+}
+
+impl RaffleConfig {
+    /// Ledger index of the ticket picked by the `draw`-th draw of a raffle with `entries` tickets.
+    /// Draws walk the ledger from a random offset with a random stride coprime with the number
+    /// of tickets, so every ticket has the same chance to win and none is picked twice.
+    pub fn draw_index(seed: &Hash, entries: u64, draw: u64) -> u64 {
+        let seed = seed.to_bytes();
+        let offset = u64::from_le_bytes(*array_ref![seed, 0, 8]) % entries;
+        let mut stride = u64::from_le_bytes(*array_ref![seed, 8, 8]) % entries;
+        while gcd(stride, entries) != 1 {
+            stride = (stride + 1) % entries;
+        }
+
+        ((offset as u128 + draw as u128 * stride as u128) % entries as u128) as u64
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// How winners of an English or sealed-bid auction are charged once it has ended.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub enum SettlementMode {
+    /// Each winner pays their own bid.
+    FirstPrice,
+    /// Each winner pays the bid ranked just below their own, the rest of their bid is refunded.
+    SecondPrice,
+}
+
+/// Restricts bidding to holders of a token, proven with a token account of the bidder holding
+/// one.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub enum BidGate {
+    /// Bidders must hold a token of this mint.
+    Mint(Pubkey),
+    /// Bidders must hold an NFT whose metadata lists this creator as verified.
+    Creator(Pubkey),
+}
+
+/// Most mints an auction takes bids in besides its own.
+pub const MAX_PAYMENT_MINTS: usize = 3;
+
+/// Another mint an auction takes bids in besides its token_mint. Bids are still ranked in units of
+/// the token_mint, and bidders paying in this mint pay what their bid is worth at its rate.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct PaymentMint {
+    /// Mint bids can be paid in.
+    pub mint: Pubkey,
+    /// What one token of the mint is worth in units of the auction's token_mint.
+    pub rate: PaymentRate,
+}
+
+/// Conversion ratio of a payment mint, a token being worth numerator / denominator units of the
+/// auction's token_mint.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub enum PaymentRate {
+    /// Ratio fixed when the auction is created.
+    Fixed { numerator: u64, denominator: u64 },
+    /// Ratio read from this account at the time of each bid, kept up to date by the authority. Its
+    /// data starts with the numerator and denominator as little endian u64s.
+    PriceAccount(Pubkey),
+}
+
+impl PaymentMint {
+    /// Tokens of this mint paid for a bid worth `amount` of the auction's token_mint, rounded up.
+    /// Rates read from a price account need that account among `accounts`.
+    pub fn payment_for(&self, amount: u64, accounts: &[AccountInfo]) -> Result<u64, ProgramError> {
+        let (numerator, denominator) = match &self.rate {
+            PaymentRate::Fixed {
+                numerator,
+                denominator,
+            } => (*numerator, *denominator),
+            PaymentRate::PriceAccount(key) => {
+                let price = accounts
+                    .iter()
+                    .find(|info| info.key == key)
+                    .ok_or(AuctionError::InvalidPaymentRate)?;
+                let data = price.data.borrow();
+                if data.len() < 16 {
+                    return Err(AuctionError::InvalidPaymentRate.into());
+                }
+                (
+                    u64::from_le_bytes(*array_ref![data, 0, 8]),
+                    u64::from_le_bytes(*array_ref![data, 8, 8]),
+                )
+            }
+        };
+
+        if numerator == 0 || denominator == 0 {
+            return Err(AuctionError::InvalidPaymentRate.into());
+        }
+
+        let payment =
+            (amount as u128 * denominator as u128 + numerator as u128 - 1) / numerator as u128;
+        if payment > u64::MAX as u128 {
+            return Err(AuctionError::NumericalOverflowError.into());
+        }
+        Ok(payment as u64)
+    }
+}
+
+/// Limits on cancelling winning bids, so bidders can't push the price up with bids they pull
+/// before the auction ends. English auctions only.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct CancellationRules {
+    /// Winning bids can't be cancelled from this many seconds before the auction ends.
+    pub lock_up: Option<UnixTimestamp>,
+    /// Share of a cancelled winning bid paid to the auction authority, in basis points.
+    pub penalty_basis_points: Option<u16>,
+}
+
+impl CancellationRules {
+    /// Whether winning bids can no longer be cancelled at `now`, for an auction ending at
+    /// `ended_at`. Auctions that have not started have no end time yet and lock nothing.
+    pub fn locked_up(&self, ended_at: Option<UnixTimestamp>, now: UnixTimestamp) -> bool {
+        match (self.lock_up, ended_at) {
+            (Some(lock_up), Some(end)) => now >= end.saturating_sub(lock_up),
+            _ => false,
+        }
+    }
+
+    /// Part of a cancelled winning bid of `amount` kept as the penalty.
+    pub fn penalty_for(&self, amount: u64) -> u64 {
+        match self.penalty_basis_points {
+            Some(basis_points) => (amount as u128 * basis_points as u128 / 10_000) as u64,
+            None => 0,
+        }
+    }
 }
 
 // The two extra 8's are present, one 8 is for the Vec's amount of elements and one is for the max
@@ -95,10 +369,208 @@ pub struct AuctionData {
     pub bid_state: BidState,
 }
 
+pub const AUCTION_DATA_V2_KEY: u8 = 2;
+pub const AUCTION_DATA_V2_HEADER_LEN: usize = mem::size_of::<AuctionDataV2>();
+/// Header of auction accounts laid out for reading in place, followed by the bids. Every field
+/// sits at a constant offset, so reading one never walks the Option tags ahead of it, and nothing
+/// has to be deserialized whole to reach the bids. Later versions can add fields behind a new key
+/// without touching what callers of the cheap accessors pay.
+///
+/// The first 64 bytes match the Borsh layout of AuctionData. The key takes the place of the
+/// last_bid Option tag, which that layout only ever sets to 0 or 1, so the two layouts are told
+/// apart by this byte alone. Enums are kept as their Borsh tags, and each optional timestamp has a
+/// bit in timestamps_set.
+///
+/// CreateAuction still writes the Borsh layout, so clients deserializing AuctionData keep working
+/// until they read this one. MigrateAuction moves an auction onto it, and AuctionData reads and
+/// saves either layout.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct AuctionDataV2 {
+    /// Pubkey of the authority with permission to modify this auction.
+    pub authority: [u8; 32],
+    /// Token mint for the SPL token being used to bid
+    pub token_mint: [u8; 32],
+    /// Layout version, AUCTION_DATA_V2_KEY.
+    pub key: u8,
+    /// Tag of the AuctionState.
+    pub state: u8,
+    /// Tag of the BidState variant.
+    pub bid_state: u8,
+    /// Tag of the PriceFloor variant, its data is in price_floor_data.
+    pub price_floor: u8,
+    /// Which of last_bid, ended_at, end_auction_at and end_auction_gap are set, one bit each from
+    /// the lowest.
+    pub timestamps_set: u8,
+    pub padding: [u8; 3],
+    pub last_bid: UnixTimestamp,
+    pub ended_at: UnixTimestamp,
+    pub end_auction_at: UnixTimestamp,
+    pub end_auction_gap: UnixTimestamp,
+    pub price_floor_data: [u8; 32],
+    /// Maximum of the bid state, the number of winners.
+    pub max: u64,
+    /// Number of bids following the header.
+    pub num_bids: u64,
+}
+
+impl AuctionDataV2 {
+    /// Space for an auction holding up to `bids` bids.
+    pub fn len_for(bids: usize) -> usize {
+        AUCTION_DATA_V2_HEADER_LEN + bids * BID_LENGTH
+    }
+
+    pub fn is_v2(data: &[u8]) -> bool {
+        data.len() >= AUCTION_DATA_V2_HEADER_LEN && data[64] == AUCTION_DATA_V2_KEY
+    }
+
+    /// Header of an auction laid out this way, None for one still in the Borsh layout.
+    pub fn header(data: &[u8]) -> Option<AuctionDataV2> {
+        if !AuctionDataV2::is_v2(data) {
+            return None;
+        }
+        Some(bytemuck::pod_read_unaligned(
+            &data[..AUCTION_DATA_V2_HEADER_LEN],
+        ))
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<AuctionData, ProgramError> {
+        let header = AuctionDataV2::header(data).ok_or(AuctionError::DataTypeMismatch)?;
+        let num_bids = header.num_bids as usize;
+        if data.len() < AuctionDataV2::len_for(num_bids) {
+            return Err(AuctionError::DataTypeMismatch.into());
+        }
+
+        let bids = (0..num_bids)
+            .map(|index| {
+                let offset = AUCTION_DATA_V2_HEADER_LEN + index * BID_LENGTH;
+                Bid(
+                    Pubkey::new_from_array(*array_ref![data, offset, 32]),
+                    u64::from_le_bytes(*array_ref![data, offset + 32, 8]),
+                )
+            })
+            .collect();
+        let max = header.max as usize;
+        let bid_state = match header.bid_state {
+            0 => BidState::EnglishAuction { bids, max },
+            1 => BidState::OpenEdition { bids, max },
+            2 => BidState::DutchAuction { bids, max },
+            3 => BidState::SealedBidAuction { bids, max },
+            4 => BidState::Raffle { bids, max },
+            5 => BidState::BidBook { bids, max },
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let mut price_floor = [header.price_floor; 33];
+        price_floor[1..].copy_from_slice(&header.price_floor_data);
+
+        Ok(AuctionData {
+            authority: Pubkey::new_from_array(header.authority),
+            token_mint: Pubkey::new_from_array(header.token_mint),
+            last_bid: header.timestamp(0, header.last_bid),
+            ended_at: header.timestamp(1, header.ended_at),
+            end_auction_at: header.timestamp(2, header.end_auction_at),
+            end_auction_gap: header.timestamp(3, header.end_auction_gap),
+            price_floor: PriceFloor::try_from_slice(&price_floor)?,
+            state: AuctionState::try_from_slice(&[header.state])?,
+            bid_state,
+        })
+    }
+
+    /// Writes the auction into `data` in this layout.
+    pub fn pack(auction: &AuctionData, data: &mut [u8]) -> ProgramResult {
+        let (bid_state, bids, max) = match &auction.bid_state {
+            BidState::EnglishAuction { bids, max } => (0, bids, max),
+            BidState::OpenEdition { bids, max } => (1, bids, max),
+            BidState::DutchAuction { bids, max } => (2, bids, max),
+            BidState::SealedBidAuction { bids, max } => (3, bids, max),
+            BidState::Raffle { bids, max } => (4, bids, max),
+            BidState::BidBook { bids, max } => (5, bids, max),
+        };
+        if data.len() < AuctionDataV2::len_for(bids.len()) {
+            return Err(AuctionError::DataTypeMismatch.into());
+        }
+
+        let price_floor = auction.price_floor.try_to_vec()?;
+        let timestamps = [
+            auction.last_bid,
+            auction.ended_at,
+            auction.end_auction_at,
+            auction.end_auction_gap,
+        ];
+        let mut timestamps_set = 0;
+        for (bit, timestamp) in timestamps.iter().enumerate() {
+            if timestamp.is_some() {
+                timestamps_set |= 1 << bit;
+            }
+        }
+
+        let header = AuctionDataV2 {
+            authority: auction.authority.to_bytes(),
+            token_mint: auction.token_mint.to_bytes(),
+            key: AUCTION_DATA_V2_KEY,
+            state: auction.state.try_to_vec()?[0],
+            bid_state,
+            price_floor: price_floor[0],
+            timestamps_set,
+            padding: [0; 3],
+            last_bid: auction.last_bid.unwrap_or(0),
+            ended_at: auction.ended_at.unwrap_or(0),
+            end_auction_at: auction.end_auction_at.unwrap_or(0),
+            end_auction_gap: auction.end_auction_gap.unwrap_or(0),
+            price_floor_data: *array_ref![price_floor, 1, 32],
+            max: *max as u64,
+            num_bids: bids.len() as u64,
+        };
+        data[..AUCTION_DATA_V2_HEADER_LEN].copy_from_slice(bytemuck::bytes_of(&header));
+
+        for (index, bid) in bids.iter().enumerate() {
+            let offset = AUCTION_DATA_V2_HEADER_LEN + index * BID_LENGTH;
+            data[offset..offset + 32].copy_from_slice(bid.0.as_ref());
+            data[offset + 32..offset + BID_LENGTH].copy_from_slice(&bid.1.to_le_bytes());
+        }
+
+        Ok(())
+    }
+
+    fn timestamp(&self, bit: u8, value: UnixTimestamp) -> Option<UnixTimestamp> {
+        if self.timestamps_set & (1 << bit) != 0 {
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
 // Alias for auction name.
 pub type AuctionName = [u8; 32];
 
-pub const MAX_AUCTION_DATA_EXTENDED_SIZE: usize = 8 + 9 + 2 + 9 + 33 + 158;
+pub const MAX_AUCTION_DATA_EXTENDED_SIZE: usize = 8
+    + 9
+    + 2
+    + 9
+    + 33
+    + 25
+    + 10
+    + 2
+    + 33
+    + 9
+    + 33
+    + 9
+    + 9
+    + 34
+    + 9
+    + 33
+    + 9
+    + 33
+    + 200
+    + 2
+    + 13
+    + 29;
+// Size auction extended accounts were created with when they ended at the name, followed by 158
+// bytes of padding. Fields added since read as None from that padding, which has room for the
+// ones an auction keeps up to date, but not for the settings newer auctions are created with.
+pub const LEGACY_AUCTION_DATA_EXTENDED_SIZE: usize = 8 + 9 + 2 + 9 + 33 + 158;
 // Further storage for more fields. Would like to store more on the main data but due
 // to a borsh issue that causes more added fields to inflict "Access violation" errors
 // during redemption in main Metaplex app for no reason, we had to add this nasty PDA.
@@ -116,11 +588,47 @@ pub struct AuctionDataExtended {
     pub instant_sale_price: Option<u64>,
     /// Auction name
     pub name: Option<AuctionName>,
+    /// Price schedule, only present on dutch auctions
+    pub dutch_auction_schedule: Option<DutchAuctionSchedule>,
+    /// Reveal settings, only present on sealed-bid auctions
+    pub sealed_bid_config: Option<SealedBidConfig>,
+    /// How winners are charged, first price when not set
+    pub settlement_mode: Option<SettlementMode>,
+    /// Bidder whose proxy bid currently answers competing bids, English auctions only
+    pub proxy_bidder: Option<Pubkey>,
+    /// Number of bids recorded in the bid ledger, open-edition auctions only
+    pub bid_ledger_entries: Option<u64>,
+    /// Account that paid rent for the auction accounts, refunded when they are closed
+    pub payer: Option<Pubkey>,
+    /// Bidder pots still holding a bid, the auction can only be closed once this drops to zero.
+    /// Not tracked on auctions created before the auction accounts could be closed.
+    pub open_bidder_pots: Option<u64>,
+    /// Time at which a created auction starts by itself, with the first bid or through
+    /// StartScheduledAuction
+    pub start_auction_at: Option<UnixTimestamp>,
+    /// Token bidders must hold to place a bid, anyone can bid when not set
+    pub bid_gate: Option<BidGate>,
+    /// Ticket settings, only present on raffles
+    pub raffle_config: Option<RaffleConfig>,
+    /// Randomness the raffle winners are drawn from, fixed by the first DrawWinners
+    pub raffle_seed: Option<Hash>,
+    /// Slot whose hash the raffle winners are drawn from, committed to by the first DrawWinners
+    pub raffle_draw_slot: Option<u64>,
+    /// Account the bids are ranked in, only present on bid-book auctions
+    pub bid_book: Option<Pubkey>,
+    /// Other mints bids can be paid in, only the token_mint when not set
+    pub payment_mints: Option<Vec<PaymentMint>>,
+    /// Whether bids can be paid in lamports, wrapped SOL auctions without a bid book only
+    pub lamport_bids: Option<bool>,
+    /// Limits on cancelling winning bids, winning bids can be cancelled freely when not set
+    pub cancellation_rules: Option<CancellationRules>,
 }
 
 impl AuctionDataExtended {
     pub fn from_account_info(a: &AccountInfo) -> Result<AuctionDataExtended, ProgramError> {
-        if a.data_len() != MAX_AUCTION_DATA_EXTENDED_SIZE {
+        if a.data_len() != MAX_AUCTION_DATA_EXTENDED_SIZE
+            && a.data_len() != LEGACY_AUCTION_DATA_EXTENDED_SIZE
+        {
             return Err(AuctionError::DataTypeMismatch.into());
         }
 
@@ -129,6 +637,68 @@ impl AuctionDataExtended {
         Ok(auction_extended)
     }
 
+    /// Writes the auction extended data back to its account. Accounts of the legacy size have no
+    /// room for the settings added since, so those can't be set on them.
+    pub fn save(&self, a: &AccountInfo) -> ProgramResult {
+        if a.data_len() == LEGACY_AUCTION_DATA_EXTENDED_SIZE
+            && self.settings()
+                != (AuctionExtendedSettings {
+                    instant_sale_price: self.instant_sale_price,
+                    name: self.name,
+                    ..Default::default()
+                })
+        {
+            return Err(AuctionError::LegacyAuctionDataExtended.into());
+        }
+        self.serialize(&mut *a.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Tracks a bidder pot that started holding a bid.
+    pub fn open_bidder_pot(&mut self) -> Result<(), ProgramError> {
+        if let Some(open) = self.open_bidder_pots {
+            self.open_bidder_pots = Some(
+                open.checked_add(1)
+                    .ok_or(AuctionError::NumericalOverflowError)?,
+            );
+        }
+        Ok(())
+    }
+
+    /// Tracks a bidder pot whose bid was refunded or claimed.
+    pub fn close_bidder_pot(&mut self) {
+        if let Some(open) = self.open_bidder_pots {
+            self.open_bidder_pots = Some(open.saturating_sub(1));
+        }
+    }
+
+    /// Settings of a mint bids can be paid in besides the token_mint.
+    pub fn payment_mint(&self, mint: &Pubkey) -> Result<&PaymentMint, ProgramError> {
+        self.payment_mints
+            .iter()
+            .flatten()
+            .find(|payment_mint| payment_mint.mint == *mint)
+            .ok_or_else(|| AuctionError::IncorrectMint.into())
+    }
+
+    /// Settings the auction was created with, as they stand now.
+    pub fn settings(&self) -> AuctionExtendedSettings {
+        AuctionExtendedSettings {
+            instant_sale_price: self.instant_sale_price,
+            name: self.name,
+            dutch_auction_schedule: self.dutch_auction_schedule.clone(),
+            sealed_bid_config: self.sealed_bid_config.clone(),
+            settlement_mode: self.settlement_mode.clone(),
+            start_auction_at: self.start_auction_at,
+            bid_gate: self.bid_gate.clone(),
+            raffle_config: self.raffle_config.clone(),
+            bid_book: self.bid_book,
+            payment_mints: self.payment_mints.clone(),
+            lamport_bids: self.lamport_bids,
+            cancellation_rules: self.cancellation_rules.clone(),
+        }
+    }
+
     pub fn get_instant_sale_price<'a>(data: &'a Ref<'a, &'a mut [u8]>) -> Option<u64> {
         if let Some(idx) = Self::find_instant_sale_beginning(data) {
             Some(u64::from_le_bytes(*array_ref![data, idx, 8]))
@@ -171,6 +741,10 @@ impl AuctionData {
     }
 
     pub fn get_state(a: &AccountInfo) -> Result<AuctionState, ProgramError> {
+        if let Some(header) = AuctionDataV2::header(&a.data.borrow()) {
+            return Ok(AuctionState::try_from_slice(&[header.state])?);
+        }
+
         // Remove the +1 to get rid of first byte of first bid, then -4 to subtract the u32 that is vec size of bids,
         // now we're back at the beginning of the u32, -1 again to get to state
         let bid_state_beginning = AuctionData::find_bid_state_beginning(a) - 1 - 4 - 1;
@@ -178,6 +752,7 @@ impl AuctionData {
             0 => Ok(AuctionState::Created),
             1 => Ok(AuctionState::Started),
             2 => Ok(AuctionState::Ended),
+            3 => Ok(AuctionState::Cancelled),
             _ => Err(ProgramError::InvalidAccountData),
         }
     }
@@ -207,6 +782,14 @@ impl AuctionData {
     }
 
     fn get_vec_info(a: &AccountInfo) -> (usize, usize, usize) {
+        if let Some(header) = AuctionDataV2::header(&a.data.borrow()) {
+            return (
+                AUCTION_DATA_V2_HEADER_LEN,
+                header.num_bids as usize,
+                header.max as usize,
+            );
+        }
+
         let bid_state_beginning = AuctionData::find_bid_state_beginning(a);
         let data = a.data.borrow();
 
@@ -219,8 +802,6 @@ impl AuctionData {
     }
 
     pub fn get_is_winner(a: &AccountInfo, key: &Pubkey) -> Option<usize> {
-        let bid_state_beginning = AuctionData::find_bid_state_beginning(a);
-        let data = a.data.borrow();
         let as_bytes = key.to_bytes();
         let (bid_state_beginning, num_elements, max) = AuctionData::get_vec_info(a);
         for idx in 0..std::cmp::min(num_elements, max) {
@@ -313,16 +894,84 @@ impl AuctionData {
         ]))
     }
 
+    /// Amount the winner at `idx` is charged under the given settlement mode, see
+    /// AuctionData::settlement_price.
+    pub fn get_winner_settlement_amount_at(
+        a: &AccountInfo,
+        idx: usize,
+        settlement_mode: Option<&SettlementMode>,
+    ) -> Option<u64> {
+        let bid_amount = AuctionData::get_winner_bid_amount_at(a, idx)?;
+        if settlement_mode != Some(&SettlementMode::SecondPrice) {
+            return Some(bid_amount);
+        }
+
+        let (bid_state_beginning, num_elements, max) = AuctionData::get_vec_info(a);
+        let data = a.data.borrow();
+
+        // The next bid down may be a losing bid, so only the number of bids bounds the lookup.
+        let next_bid_amount = AuctionData::get_winner_bid_amount_at_inner(
+            &data,
+            idx + 1,
+            bid_state_beginning,
+            num_elements,
+            num_elements,
+        )
+        .unwrap_or(0);
+
+        // Minimum and reserve prices both keep their price as the first word of the floor data.
+        let minimum = match AuctionDataV2::header(&data) {
+            Some(header) if header.price_floor == 1 || header.price_floor == 3 => {
+                u64::from_le_bytes(*array_ref![header.price_floor_data, 0, 8])
+            }
+            Some(_) => 0,
+            None => {
+                // The price floor enum and minimum price sit ahead of the state, bid state enum
+                // and u32.
+                let price_floor_beginning = bid_state_beginning - 1 - 4 - 1 - 32 - 1;
+                if data[price_floor_beginning] == 1 || data[price_floor_beginning] == 3 {
+                    u64::from_le_bytes(*array_ref![data, price_floor_beginning + 1, 8])
+                } else {
+                    0
+                }
+            }
+        };
+
+        Some(cmp::min(cmp::max(next_bid_amount, minimum), bid_amount))
+    }
+
     pub fn from_account_info(a: &AccountInfo) -> Result<AuctionData, ProgramError> {
-        if (a.data_len() - BASE_AUCTION_DATA_SIZE) % mem::size_of::<Bid>() != 0 {
+        AuctionData::from_bytes(&a.data.borrow())
+    }
+
+    /// Reads an auction in either account layout, see AuctionDataV2.
+    pub fn from_bytes(data: &[u8]) -> Result<AuctionData, ProgramError> {
+        if AuctionDataV2::is_v2(data) {
+            return AuctionDataV2::unpack(data);
+        }
+
+        if data.len() < BASE_AUCTION_DATA_SIZE
+            || (data.len() - BASE_AUCTION_DATA_SIZE) % mem::size_of::<Bid>() != 0
+        {
             return Err(AuctionError::DataTypeMismatch.into());
         }
 
-        let auction: AuctionData = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        let auction: AuctionData = try_from_slice_unchecked(data)?;
 
         Ok(auction)
     }
 
+    /// Writes the auction back in the layout its account already has.
+    pub fn save(&self, a: &AccountInfo) -> ProgramResult {
+        let mut data = a.data.borrow_mut();
+        if AuctionDataV2::is_v2(&data) {
+            AuctionDataV2::pack(self, &mut data)
+        } else {
+            self.serialize(&mut *data)?;
+            Ok(())
+        }
+    }
+
     pub fn ended(&self, now: UnixTimestamp) -> Result<bool, ProgramError> {
         // If there is an end time specified, handle conditions.
         return match (self.ended_at, self.end_auction_gap) {
@@ -350,12 +999,48 @@ impl AuctionData {
         };
     }
 
-    pub fn is_winner(&self, key: &Pubkey) -> Option<usize> {
-        let minimum = match self.price_floor {
-            PriceFloor::MinimumPrice(min) => min[0],
-            _ => 0,
+    /// Moves the auction to Started, with its end time counting from `started_at`.
+    pub fn start(&mut self, started_at: UnixTimestamp) -> ProgramResult {
+        self.ended_at = match self.end_auction_at {
+            Some(end_auction_at) => Some(
+                started_at
+                    .checked_add(end_auction_at)
+                    .ok_or(AuctionError::NumericalOverflowError)?,
+            ),
+            None => None,
         };
-        self.bid_state.is_winner(key, minimum)
+        self.state = self.state.clone().start()?;
+        Ok(())
+    }
+
+    /// Starts a created auction whose scheduled start time has passed. The end time counts from
+    /// the scheduled time rather than from whenever this happens to be called.
+    pub fn start_if_scheduled(
+        &mut self,
+        start_auction_at: Option<UnixTimestamp>,
+        now: UnixTimestamp,
+    ) -> Result<bool, ProgramError> {
+        match start_auction_at {
+            Some(start) if self.state == AuctionState::Created && now >= start => {
+                self.start(start)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Whether no more bids can be placed, because the auction has ended or was cancelled.
+    /// Raffles are only over once their winners have been drawn.
+    pub fn is_over(&self, now: UnixTimestamp) -> Result<bool, ProgramError> {
+        let is_raffle = matches!(self.bid_state, BidState::Raffle { .. });
+        Ok(self.state == AuctionState::Ended
+            || self.state == AuctionState::Cancelled
+            || (!is_raffle && self.ended(now)?))
+    }
+
+    pub fn is_winner(&self, key: &Pubkey) -> Option<usize> {
+        self.bid_state
+            .is_winner(key, self.price_floor.winning_minimum())
     }
 
     pub fn num_winners(&self) -> u64 {
@@ -370,6 +1055,62 @@ impl AuctionData {
         self.bid_state.winner_at(idx)
     }
 
+    /// Whether the auction ended without any bid meeting its reserve price.
+    pub fn reserve_not_met(&self) -> bool {
+        matches!(self.price_floor, PriceFloor::ReserveNotMet(_))
+    }
+
+    /// Settles the reserve price of an auction that has stopped taking bids. Bids below the
+    /// reserve are dropped so they no longer count as winners, and if none are left the auction
+    /// is marked ReserveNotMet.
+    pub fn settle_reserve(&mut self) {
+        if let PriceFloor::ReservePrice(reserve) = self.price_floor {
+            self.bid_state.drop_bids_below(reserve[0]);
+            if self.num_winners() == 0 {
+                self.price_floor = PriceFloor::ReserveNotMet(reserve);
+            }
+        }
+    }
+
+    /// Amount the winner at `index` is charged once the auction has ended.
+    pub fn settlement_price(&self, index: usize, settlement_mode: Option<&SettlementMode>) -> u64 {
+        match settlement_mode {
+            Some(SettlementMode::SecondPrice) => self
+                .bid_state
+                .second_price(index, self.price_floor.winning_minimum()),
+            _ => self.bid_state.amount(index),
+        }
+    }
+
+    /// Current asking price of a dutch auction. The schedule runs from the moment the auction was
+    /// started until its end time.
+    pub fn dutch_auction_price(
+        &self,
+        schedule: &DutchAuctionSchedule,
+        now: UnixTimestamp,
+    ) -> Result<u64, ProgramError> {
+        match (self.ended_at, self.end_auction_at) {
+            (Some(end), Some(duration)) => {
+                let started_at = end
+                    .checked_sub(duration)
+                    .ok_or(AuctionError::NumericalOverflowError)?;
+                schedule.price_at(started_at, duration, now)
+            }
+            _ => Err(AuctionError::InvalidState.into()),
+        }
+    }
+
+    /// Time after which sealed bids can no longer be revealed. The window opens at the auction
+    /// end time.
+    pub fn reveal_deadline(&self, config: &SealedBidConfig) -> Result<UnixTimestamp, ProgramError> {
+        match self.ended_at {
+            Some(end) => Ok(end
+                .checked_add(config.reveal_window)
+                .ok_or(AuctionError::NumericalOverflowError)?),
+            None => Err(AuctionError::InvalidState.into()),
+        }
+    }
+
     pub fn consider_instant_bid(&mut self, instant_sale_price: Option<u64>) {
         // Check if all the lots were sold with instant_sale_price
         if let Some(price) = instant_sale_price {
@@ -383,15 +1124,13 @@ impl AuctionData {
         }
     }
 
-    pub fn place_bid(
-        &mut self,
-        bid: Bid,
-        tick_size: Option<u64>,
+    /// Gap tick size percentage bids must respect at `now`, only set inside the gap window.
+    fn gap_tick_size_at(
+        &self,
         gap_tick_size_percentage: Option<u8>,
         now: UnixTimestamp,
-        instant_sale_price: Option<u64>,
-    ) -> Result<(), ProgramError> {
-        let gap_val = match self.ended_at {
+    ) -> Option<u8> {
+        match self.ended_at {
             Some(end) => {
                 // We use the actual gap tick size perc if we're in gap window,
                 // otherwise we pass in none so the logic isnt used
@@ -402,7 +1141,18 @@ impl AuctionData {
                 }
             }
             None => None,
-        };
+        }
+    }
+
+    pub fn place_bid(
+        &mut self,
+        bid: Bid,
+        tick_size: Option<u64>,
+        gap_tick_size_percentage: Option<u8>,
+        now: UnixTimestamp,
+        instant_sale_price: Option<u64>,
+    ) -> Result<(), ProgramError> {
+        let gap_val = self.gap_tick_size_at(gap_tick_size_percentage, now);
         let minimum = match self.price_floor {
             PriceFloor::MinimumPrice(min) => min[0],
             _ => 0,
@@ -421,6 +1171,61 @@ impl AuctionData {
 
         Ok(())
     }
+
+    /// Replaces the bidder's current bid with a larger one, ranking it as if it were a new bid.
+    pub fn increase_bid(
+        &mut self,
+        bid: Bid,
+        tick_size: Option<u64>,
+        gap_tick_size_percentage: Option<u8>,
+        now: UnixTimestamp,
+        instant_sale_price: Option<u64>,
+    ) -> Result<(), ProgramError> {
+        self.bid_state.cancel_bid(bid.0)?;
+        self.place_bid(
+            bid,
+            tick_size,
+            gap_tick_size_percentage,
+            now,
+            instant_sale_price,
+        )
+    }
+
+    /// Answers a bid that outranks a proxy bidder with a counter-bid, raising their bid by the
+    /// smallest valid step as long as it stays within `max_amount`. Returns the proxy bidder's
+    /// standing bid, or None once their maximum can no longer outbid the top bid.
+    pub fn counter_proxy_bid(
+        &mut self,
+        bidder: &Pubkey,
+        max_amount: u64,
+        tick_size: Option<u64>,
+        gap_tick_size_percentage: Option<u8>,
+        now: UnixTimestamp,
+        instant_sale_price: Option<u64>,
+    ) -> Result<Option<u64>, ProgramError> {
+        if self.winner_at(0) == Some(*bidder) {
+            return Ok(Some(self.bid_state.amount(0)));
+        }
+
+        let gap_val = self.gap_tick_size_at(gap_tick_size_percentage, now);
+        match self
+            .bid_state
+            .proxy_counter_bid(max_amount, tick_size, gap_val, instant_sale_price)
+        {
+            Some(counter) => {
+                msg!("Proxy bid of {:?} counters with {:?}", bidder, counter);
+                self.increase_bid(
+                    Bid(*bidder, counter),
+                    tick_size,
+                    gap_tick_size_percentage,
+                    now,
+                    instant_sale_price,
+                )?;
+                Ok(Some(counter))
+            }
+            None => Ok(None),
+        }
+    }
 }
 
 /// Define valid auction state transitions.
@@ -430,6 +1235,7 @@ pub enum AuctionState {
     Created,
     Started,
     Ended,
+    Cancelled,
 }
 
 impl AuctionState {
@@ -453,6 +1259,15 @@ impl AuctionState {
             _ => Err(AuctionError::AuctionTransitionInvalid.into()),
         }
     }
+
+    #[inline(always)]
+    pub fn cancel(self) -> Result<Self, ProgramError> {
+        match self {
+            AuctionState::Started => Ok(AuctionState::Cancelled),
+            AuctionState::Created => Ok(AuctionState::Cancelled),
+            _ => Err(AuctionError::AuctionTransitionInvalid.into()),
+        }
+    }
 }
 
 /// Bids associate a bidding key with an amount bid.
@@ -467,6 +1282,10 @@ pub struct Bid(pub Pubkey, pub u64);
 pub enum BidState {
     EnglishAuction { bids: Vec<Bid>, max: usize },
     OpenEdition { bids: Vec<Bid>, max: usize },
+    DutchAuction { bids: Vec<Bid>, max: usize },
+    SealedBidAuction { bids: Vec<Bid>, max: usize },
+    Raffle { bids: Vec<Bid>, max: usize },
+    BidBook { bids: Vec<Bid>, max: usize },
 }
 
 /// Bidding Implementations.
@@ -476,6 +1295,18 @@ pub enum BidState {
 ///
 /// Open Edition: All bids are accepted, cancellations return money to the bidder and always
 /// succeed.
+///
+/// Dutch Auction: bids are accepted at the current asking price until every lot is taken, the
+/// earliest bidder ranking first. The auction ends as soon as the last lot is bought.
+///
+/// Sealed Bid Auction: ranks bids like an English auction, but bids only enter the state once
+/// they are revealed after the auction end time.
+///
+/// Raffle: tickets are recorded in the bid ledger, and only the winners drawn from it once ticket
+/// sales close enter the state, in the order they were drawn.
+///
+/// Bid Book: an English auction ranking its bids in a bid book account of its own, for more
+/// winners than fit in the auction account. No bids are kept in the state, see BidBook.
 impl BidState {
     pub fn new_english(n: usize) -> Self {
         BidState::EnglishAuction {
@@ -491,6 +1322,34 @@ impl BidState {
         }
     }
 
+    pub fn new_dutch(n: usize) -> Self {
+        BidState::DutchAuction {
+            bids: vec![],
+            max: n,
+        }
+    }
+
+    pub fn new_sealed_bid(n: usize) -> Self {
+        BidState::SealedBidAuction {
+            bids: vec![],
+            max: n,
+        }
+    }
+
+    pub fn new_raffle(n: usize) -> Self {
+        BidState::Raffle {
+            bids: vec![],
+            max: n,
+        }
+    }
+
+    pub fn new_bid_book(n: usize) -> Self {
+        BidState::BidBook {
+            bids: vec![],
+            max: n,
+        }
+    }
+
     pub fn max_array_size_for(n: usize) -> usize {
         let mut real_max = n;
         if real_max < 8 {
@@ -559,7 +1418,8 @@ impl BidState {
 
         match self {
             // In a capped auction, track the limited number of winners.
-            BidState::EnglishAuction { ref mut bids, max } => {
+            BidState::EnglishAuction { ref mut bids, max }
+            | BidState::SealedBidAuction { ref mut bids, max } => {
                 match bids.last() {
                     Some(top) => {
                         msg!("Looking to go over the loop, but check tick size first");
@@ -625,6 +1485,28 @@ impl BidState {
 
             // In an open auction, bidding simply succeeds.
             BidState::OpenEdition { bids, max } => Ok(()),
+
+            // Raffle tickets are kept in the bid ledger until winners are drawn.
+            BidState::Raffle { bids, max } => Ok(()),
+
+            // Bid-book auctions rank their bids in the bid book instead.
+            BidState::BidBook { bids, max } => Ok(()),
+
+            // In a dutch auction the asking price only ever falls, so each new bid is placed
+            // below all earlier ones.
+            BidState::DutchAuction { ref mut bids, max } => {
+                if bids.len() >= *max {
+                    return Err(AuctionError::InvalidState.into());
+                }
+
+                bids.insert(0, bid);
+
+                if bids.len() >= *max {
+                    msg!("All the lots were sold, auction is ended");
+                    *auction_state = AuctionState::Ended;
+                }
+                Ok(())
+            }
         }
     }
 
@@ -632,7 +1514,10 @@ impl BidState {
     /// function simple no-ops.
     pub fn cancel_bid(&mut self, key: Pubkey) -> Result<(), ProgramError> {
         match self {
-            BidState::EnglishAuction { ref mut bids, max } => {
+            BidState::EnglishAuction { ref mut bids, max }
+            | BidState::DutchAuction { ref mut bids, max }
+            | BidState::SealedBidAuction { ref mut bids, max }
+            | BidState::Raffle { ref mut bids, max } => {
                 bids.retain(|b| b.0 != key);
                 Ok(())
             }
@@ -640,20 +1525,130 @@ impl BidState {
             // In an open auction, cancelling simply succeeds. It's up to the manager of an auction
             // to decide what to do with open edition bids.
             BidState::OpenEdition { bids, max } => Ok(()),
+
+            // Bids only leave a bid book by being outbid.
+            BidState::BidBook { bids, max } => Ok(()),
+        }
+    }
+
+    /// Drops every bid, leaving the auction without winners.
+    pub fn clear(&mut self) {
+        match self {
+            BidState::EnglishAuction { ref mut bids, max }
+            | BidState::DutchAuction { ref mut bids, max }
+            | BidState::SealedBidAuction { ref mut bids, max }
+            | BidState::OpenEdition { ref mut bids, max }
+            | BidState::Raffle { ref mut bids, max }
+            | BidState::BidBook { ref mut bids, max } => bids.clear(),
+        }
+    }
+
+    /// Drops the ranked bids below `min`, leaving them to be refunded like any losing bid.
+    pub fn drop_bids_below(&mut self, min: u64) {
+        match self {
+            BidState::EnglishAuction { ref mut bids, max }
+            | BidState::DutchAuction { ref mut bids, max }
+            | BidState::SealedBidAuction { ref mut bids, max }
+            | BidState::Raffle { ref mut bids, max } => bids.retain(|b| b.1 >= min),
+            BidState::OpenEdition { bids, max } | BidState::BidBook { bids, max } => {}
+        }
+    }
+
+    /// Records the next winner drawn in a raffle, ranked below every earlier draw.
+    pub fn draw_winner(&mut self, bid: Bid) -> ProgramResult {
+        match self {
+            BidState::Raffle { ref mut bids, max } => {
+                if bids.len() >= *max {
+                    return Err(AuctionError::InvalidState.into());
+                }
+                bids.insert(0, bid);
+                Ok(())
+            }
+            _ => Err(AuctionError::InvalidState.into()),
         }
     }
 
     pub fn amount(&self, index: usize) -> u64 {
         match self {
-            BidState::EnglishAuction { bids, max } => {
+            BidState::EnglishAuction { bids, max }
+            | BidState::DutchAuction { bids, max }
+            | BidState::SealedBidAuction { bids, max }
+            | BidState::Raffle { bids, max } => {
                 if index >= 0 as usize && index < bids.len() {
                     return bids[bids.len() - index - 1].1;
                 } else {
                     return 0;
                 }
             }
-            BidState::OpenEdition { bids, max } => 0,
+            BidState::OpenEdition { bids, max } | BidState::BidBook { bids, max } => 0,
+        }
+    }
+
+    /// Price paid by the winner at `index` in a second-price auction, the bid ranked just below
+    /// theirs or the minimum if there is none, but never more than their own bid.
+    pub fn second_price(&self, index: usize, minimum: u64) -> u64 {
+        match self {
+            BidState::EnglishAuction { bids, max } | BidState::SealedBidAuction { bids, max } => {
+                if index >= bids.len() {
+                    return 0;
+                }
+
+                let bid = bids[bids.len() - index - 1].1;
+                let next_bid = if index + 1 < bids.len() {
+                    bids[bids.len() - index - 2].1
+                } else {
+                    0
+                };
+                cmp::min(cmp::max(next_bid, minimum), bid)
+            }
+            _ => self.amount(index),
+        }
+    }
+
+    /// Smallest bid that beats the current top bid under the tick size and gap rules, capped at
+    /// `max_amount` and the instant sale price. None if no such bid exists or the top bid cannot
+    /// be outbid within the cap.
+    pub fn proxy_counter_bid(
+        &self,
+        max_amount: u64,
+        tick_size: Option<u64>,
+        gap_tick_size_percentage: Option<u8>,
+        instant_sale_price: Option<u64>,
+    ) -> Option<u64> {
+        let top = match self {
+            BidState::EnglishAuction { bids, max } => bids.last()?.1,
+            _ => return None,
+        };
+
+        let step = cmp::max(tick_size.unwrap_or(1), 1);
+        let mut lowest = top.checked_add(1)?;
+        let mut counter = top.checked_add(step)?;
+
+        // Inside the gap window the counter-bid has to clear the gap as well, rounded up to the
+        // next tick.
+        if let Some(gap_tick) = gap_tick_size_percentage {
+            let minimum_gap_bid = (top as u128)
+                .checked_mul((100 + gap_tick) as u128)?
+                .checked_div(100u128)?;
+            let minimum_gap_bid = u64::try_from(minimum_gap_bid).ok()?;
+            lowest = cmp::max(lowest, minimum_gap_bid);
+
+            let rounded_gap_bid = minimum_gap_bid
+                .checked_add(step - 1)?
+                .checked_div(step)?
+                .checked_mul(step)?;
+            counter = cmp::max(counter, rounded_gap_bid);
+        }
+
+        counter = cmp::min(counter, max_amount);
+        if let Some(price) = instant_sale_price {
+            counter = cmp::min(counter, price);
         }
+
+        if counter < lowest || counter % step != 0 {
+            return None;
+        }
+        Some(counter)
     }
 
     /// Check if a pubkey is currently a winner and return winner #1 as index 0 to outside world.
@@ -662,7 +1657,10 @@ impl BidState {
 
         match self {
             // Presense in the winner list is enough to check win state.
-            BidState::EnglishAuction { bids, max } => {
+            BidState::EnglishAuction { bids, max }
+            | BidState::DutchAuction { bids, max }
+            | BidState::SealedBidAuction { bids, max }
+            | BidState::Raffle { bids, max } => {
                 match bids.iter().position(|bid| &bid.0 == key && bid.1 >= min) {
                     Some(val) => {
                         let zero_based_index = bids.len() - val - 1;
@@ -678,19 +1676,28 @@ impl BidState {
             // There are no winners in an open edition, it is up to the auction manager to decide
             // what to do with open edition bids.
             BidState::OpenEdition { bids, max } => None,
+            // Winners of a bid-book auction are looked up in the bid book.
+            BidState::BidBook { bids, max } => None,
         }
     }
 
     pub fn num_winners(&self) -> u64 {
         match self {
-            BidState::EnglishAuction { bids, max } => cmp::min(bids.len(), *max) as u64,
-            BidState::OpenEdition { bids, max } => 0,
+            BidState::EnglishAuction { bids, max }
+            | BidState::DutchAuction { bids, max }
+            | BidState::SealedBidAuction { bids, max }
+            | BidState::Raffle { bids, max } => cmp::min(bids.len(), *max) as u64,
+            BidState::OpenEdition { bids, max } | BidState::BidBook { bids, max } => 0,
         }
     }
 
     pub fn num_possible_winners(&self) -> u64 {
         match self {
-            BidState::EnglishAuction { bids, max } => *max as u64,
+            BidState::EnglishAuction { bids, max }
+            | BidState::DutchAuction { bids, max }
+            | BidState::SealedBidAuction { bids, max }
+            | BidState::Raffle { bids, max }
+            | BidState::BidBook { bids, max } => *max as u64,
             BidState::OpenEdition { bids, max } => 0,
         }
     }
@@ -698,7 +1705,10 @@ impl BidState {
     /// Idea is to present #1 winner as index 0 to outside world with this method
     pub fn winner_at(&self, index: usize) -> Option<Pubkey> {
         match self {
-            BidState::EnglishAuction { bids, max } => {
+            BidState::EnglishAuction { bids, max }
+            | BidState::DutchAuction { bids, max }
+            | BidState::SealedBidAuction { bids, max }
+            | BidState::Raffle { bids, max } => {
                 if index < *max && index < bids.len() {
                     let bid = &bids[bids.len() - index - 1];
                     Some(bids[bids.len() - index - 1].0)
@@ -706,7 +1716,7 @@ impl BidState {
                     None
                 }
             }
-            BidState::OpenEdition { bids, max } => None,
+            BidState::OpenEdition { bids, max } | BidState::BidBook { bids, max } => None,
         }
     }
 
@@ -760,6 +1770,353 @@ impl BidderMetadata {
     }
 }
 
+pub const BID_COMMITMENT_LEN: usize = 32 + 32 + 32 + 8 + 1;
+/// A sealed bid, meant to be stored in a PDA next to the bidder metadata. The bid amount stays
+/// hidden behind the commitment hash until it is revealed.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct BidCommitment {
+    // Relationship with the bidder who made this commitment.
+    pub bidder_pubkey: Pubkey,
+    // Relationship with the auction this commitment was made on.
+    pub auction_pubkey: Pubkey,
+    // Hash of the bid amount, salt and bidder key, see bid_commitment_hash.
+    pub commitment: Hash,
+    // Amount deposited into the bidder pot, the largest bid that can be revealed.
+    pub deposit: u64,
+    // Whether the bid has been revealed and placed into the auction.
+    pub revealed: bool,
+}
+
+impl BidCommitment {
+    pub fn from_account_info(a: &AccountInfo) -> Result<BidCommitment, ProgramError> {
+        if a.data_len() != BID_COMMITMENT_LEN {
+            return Err(AuctionError::DataTypeMismatch.into());
+        }
+
+        let bid_commitment: BidCommitment = try_from_slice_unchecked(&a.data.borrow_mut())?;
+
+        Ok(bid_commitment)
+    }
+}
+
+pub const BID_LEDGER_PAGE_SIZE: usize = 64;
+pub const BID_LEDGER_ENTRY_LEN: usize = 32 + 8 + 8;
+pub const BID_LEDGER_PAGE_LEN: usize = 32 + 8 + 4 + BID_LEDGER_PAGE_SIZE * BID_LEDGER_ENTRY_LEN;
+
+/// A bid recorded in the bid ledger of an open-edition auction.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct BidLedgerEntry {
+    // The bidder who placed the bid.
+    pub bidder_pubkey: Pubkey,
+    // Amount that was bid.
+    pub amount: u64,
+    // When the bid was placed.
+    pub timestamp: UnixTimestamp,
+}
+
+/// A page of the append-only ledger of open-edition bids, meant to be stored in a PDA keyed by
+/// the auction and page number. Pages fill up in order, so the n-th bid lives on page
+/// n / BID_LEDGER_PAGE_SIZE. Cancelled bids are not removed.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct BidLedgerPage {
+    // Relationship with the auction these bids were placed on.
+    pub auction_pubkey: Pubkey,
+    // Position of this page in the ledger, starting from 0.
+    pub page: u64,
+    // Bids in the order they were placed, at most BID_LEDGER_PAGE_SIZE.
+    pub entries: Vec<BidLedgerEntry>,
+}
+
+impl BidLedgerPage {
+    pub fn from_account_info(a: &AccountInfo) -> Result<BidLedgerPage, ProgramError> {
+        if a.data_len() != BID_LEDGER_PAGE_LEN {
+            return Err(AuctionError::DataTypeMismatch.into());
+        }
+
+        let page: BidLedgerPage = try_from_slice_unchecked(&a.data.borrow_mut())?;
+
+        Ok(page)
+    }
+
+    /// Page the bid at `index` in the ledger is recorded on.
+    pub fn page_for(index: u64) -> u64 {
+        index / BID_LEDGER_PAGE_SIZE as u64
+    }
+
+    /// Entry at `index` on the page, where `index` counts from the start of this page, read
+    /// without deserializing every entry on it.
+    pub fn get_entry(a: &AccountInfo, index: usize) -> Result<BidLedgerEntry, ProgramError> {
+        let data = a.data.borrow();
+        let num_entries = u32::from_le_bytes(*array_ref![data, 40, 4]) as usize;
+        if index >= num_entries {
+            return Err(AuctionError::MissingBidLedgerPage.into());
+        }
+
+        let entry_beginning = 44 + index * BID_LEDGER_ENTRY_LEN;
+        let entry: BidLedgerEntry = try_from_slice_unchecked(
+            &data[entry_beginning..entry_beginning + BID_LEDGER_ENTRY_LEN],
+        )?;
+        Ok(entry)
+    }
+}
+
+pub const BID_BOOK_HEADER_LEN: usize = 32 + 8 + 8 + 8;
+pub const BID_BOOK_ENTRY_LEN: usize = 32 + 8;
+
+/// Bids of a bid-book auction, kept in an account of their own so an auction can have more
+/// winners than fit in the auction account. The auction creator allocates the account, zeroed and
+/// owned by this program, and CreateAuction writes its header: the auction it belongs to, the
+/// number of winners, how many bids it holds and how many of those have been sorted. A slot for
+/// every winner follows the header.
+///
+/// While bids are taken the slots form a min-heap on amount then bidder key, so ranking a bid
+/// costs O(log n) and the lowest winning bid is always in the first slot. Once the auction is
+/// over SortBidBook turns the heap into a list ranked from the highest bid down, a batch of slots
+/// at a time, and winners are then found by binary search.
+///
+/// The account is only ever read and written in place, it is too large to deserialize whole.
+pub struct BidBook;
+
+impl BidBook {
+    pub fn len_for(max: usize) -> usize {
+        BID_BOOK_HEADER_LEN + max * BID_BOOK_ENTRY_LEN
+    }
+
+    /// Writes the header of a freshly allocated bid book.
+    pub fn initialize(a: &AccountInfo, auction: &Pubkey, max: usize) -> ProgramResult {
+        if a.data_len() != BidBook::len_for(max) || BidBook::get_auction(a) != Pubkey::default() {
+            return Err(AuctionError::InvalidBidBook.into());
+        }
+
+        let mut data = a.data.borrow_mut();
+        data[0..32].copy_from_slice(auction.as_ref());
+        data[32..40].copy_from_slice(&(max as u64).to_le_bytes());
+        Ok(())
+    }
+
+    pub fn get_auction(a: &AccountInfo) -> Pubkey {
+        let data = a.data.borrow();
+        Pubkey::new_from_array(*array_ref![data, 0, 32])
+    }
+
+    pub fn get_max(a: &AccountInfo) -> u64 {
+        let data = a.data.borrow();
+        u64::from_le_bytes(*array_ref![data, 32, 8])
+    }
+
+    pub fn get_len(a: &AccountInfo) -> u64 {
+        let data = a.data.borrow();
+        u64::from_le_bytes(*array_ref![data, 40, 8])
+    }
+
+    pub fn get_sorted(a: &AccountInfo) -> u64 {
+        let data = a.data.borrow();
+        u64::from_le_bytes(*array_ref![data, 48, 8])
+    }
+
+    /// Whether SortBidBook has ranked every bid, after which winners can be looked up.
+    pub fn is_sorted(a: &AccountInfo) -> bool {
+        BidBook::get_sorted(a) >= BidBook::get_len(a)
+    }
+
+    /// Lowest bid in the book, the first to be outbid.
+    pub fn get_lowest_bid(a: &AccountInfo) -> Option<Bid> {
+        let data = a.data.borrow();
+        let len = BidBook::read_u64(&data, 40) as usize;
+        let sorted = BidBook::read_u64(&data, 48);
+        match len {
+            0 => None,
+            // Sorting moves the lowest bids to the end of the book.
+            _ if sorted > 0 => Some(BidBook::read_slot(&data, len - 1)),
+            _ => Some(BidBook::read_slot(&data, 0)),
+        }
+    }
+
+    /// Whether the bid is in the book. Bids only leave the book as its lowest one, and a full book
+    /// only takes bids above its lowest, so a bid is in the book exactly when it ranks at or above
+    /// the lowest one.
+    pub fn contains(a: &AccountInfo, bid: &Bid) -> bool {
+        match BidBook::get_lowest_bid(a) {
+            Some(lowest) => BidBook::rank(bid) >= BidBook::rank(&lowest),
+            None => false,
+        }
+    }
+
+    /// Ranks a bid in the book, outbidding the lowest bid once the book is full.
+    pub fn insert(a: &AccountInfo, bid: Bid) -> ProgramResult {
+        let mut data = a.data.borrow_mut();
+        let max = BidBook::read_u64(&data, 32) as usize;
+        let len = BidBook::read_u64(&data, 40) as usize;
+        if BidBook::read_u64(&data, 48) > 0 {
+            return Err(AuctionError::InvalidState.into());
+        }
+
+        if len < max {
+            BidBook::write_slot(&mut data, len, &bid);
+            BidBook::sift_up(&mut data, len);
+            data[40..48].copy_from_slice(&(len as u64 + 1).to_le_bytes());
+        } else {
+            // Equal bids do not outbid each other, the earlier one keeps its place.
+            if max == 0 || bid.1 <= BidBook::read_slot(&data, 0).1 {
+                return Err(AuctionError::BidTooSmall.into());
+            }
+            BidBook::write_slot(&mut data, 0, &bid);
+            BidBook::sift_down(&mut data, 0, len);
+        }
+
+        Ok(())
+    }
+
+    /// Moves up to `steps` more bids to their final rank, returning whether the book is sorted.
+    pub fn sort(a: &AccountInfo, steps: u64) -> Result<bool, ProgramError> {
+        let mut data = a.data.borrow_mut();
+        let len = BidBook::read_u64(&data, 40);
+        let mut sorted = BidBook::read_u64(&data, 48);
+
+        for _ in 0..steps {
+            if sorted >= len {
+                break;
+            }
+
+            // The root of what is left of the heap is its lowest bid, and goes right before the
+            // bids sorted so far.
+            let last = (len - sorted - 1) as usize;
+            let (lowest, last_bid) = (
+                BidBook::read_slot(&data, 0),
+                BidBook::read_slot(&data, last),
+            );
+            BidBook::write_slot(&mut data, 0, &last_bid);
+            BidBook::write_slot(&mut data, last, &lowest);
+            BidBook::sift_down(&mut data, 0, last);
+            sorted += 1;
+        }
+
+        data[48..56].copy_from_slice(&sorted.to_le_bytes());
+        Ok(sorted >= len)
+    }
+
+    /// Winner at `index`, with the highest bid at index 0, once the book is sorted.
+    pub fn get_winner_at(a: &AccountInfo, index: usize) -> Option<Pubkey> {
+        if !BidBook::is_sorted(a) || index as u64 >= BidBook::get_len(a) {
+            return None;
+        }
+
+        let data = a.data.borrow();
+        Some(BidBook::read_slot(&data, index).0)
+    }
+
+    /// Rank of the bid among the winners, with the highest bid at index 0, once the book is
+    /// sorted.
+    pub fn get_is_winner(a: &AccountInfo, bid: &Bid) -> Option<usize> {
+        if !BidBook::is_sorted(a) {
+            return None;
+        }
+
+        let data = a.data.borrow();
+        let wanted = BidBook::rank(bid);
+        let (mut low, mut high) = (0, BidBook::read_u64(&data, 40) as usize);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let found = BidBook::rank(&BidBook::read_slot(&data, middle));
+            match found.cmp(&wanted) {
+                cmp::Ordering::Equal => return Some(middle),
+                cmp::Ordering::Greater => low = middle + 1,
+                cmp::Ordering::Less => high = middle,
+            }
+        }
+        None
+    }
+
+    fn rank(bid: &Bid) -> (u64, Pubkey) {
+        (bid.1, bid.0)
+    }
+
+    fn read_u64(data: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(*array_ref![data, offset, 8])
+    }
+
+    fn read_slot(data: &[u8], index: usize) -> Bid {
+        let offset = BID_BOOK_HEADER_LEN + index * BID_BOOK_ENTRY_LEN;
+        Bid(
+            Pubkey::new_from_array(*array_ref![data, offset, 32]),
+            u64::from_le_bytes(*array_ref![data, offset + 32, 8]),
+        )
+    }
+
+    fn write_slot(data: &mut [u8], index: usize, bid: &Bid) {
+        let offset = BID_BOOK_HEADER_LEN + index * BID_BOOK_ENTRY_LEN;
+        data[offset..offset + 32].copy_from_slice(bid.0.as_ref());
+        data[offset + 32..offset + 40].copy_from_slice(&bid.1.to_le_bytes());
+    }
+
+    fn sift_up(data: &mut [u8], mut index: usize) {
+        let bid = BidBook::read_slot(data, index);
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            let parent_bid = BidBook::read_slot(data, parent);
+            if BidBook::rank(&parent_bid) <= BidBook::rank(&bid) {
+                break;
+            }
+            BidBook::write_slot(data, index, &parent_bid);
+            index = parent;
+        }
+        BidBook::write_slot(data, index, &bid);
+    }
+
+    fn sift_down(data: &mut [u8], mut index: usize, len: usize) {
+        let bid = BidBook::read_slot(data, index);
+        loop {
+            let mut child = 2 * index + 1;
+            if child >= len {
+                break;
+            }
+            let mut child_bid = BidBook::read_slot(data, child);
+            if child + 1 < len {
+                let right_bid = BidBook::read_slot(data, child + 1);
+                if BidBook::rank(&right_bid) < BidBook::rank(&child_bid) {
+                    child += 1;
+                    child_bid = right_bid;
+                }
+            }
+            if BidBook::rank(&bid) <= BidBook::rank(&child_bid) {
+                break;
+            }
+            BidBook::write_slot(data, index, &child_bid);
+            index = child;
+        }
+        BidBook::write_slot(data, index, &bid);
+    }
+}
+
+pub const PROXY_BID_LEN: usize = 32 + 32 + 8;
+/// A proxy bid, meant to be stored in a PDA next to the bidder metadata. The maximum is held in
+/// the bidder pot and stays out of the bid list, which only ever shows the standing bid.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct ProxyBid {
+    // Relationship with the bidder who registered this proxy bid.
+    pub bidder_pubkey: Pubkey,
+    // Relationship with the auction this proxy bid was registered on.
+    pub auction_pubkey: Pubkey,
+    // Largest bid the program may place on the bidder's behalf.
+    pub max_amount: u64,
+}
+
+impl ProxyBid {
+    pub fn from_account_info(a: &AccountInfo) -> Result<ProxyBid, ProgramError> {
+        if a.data_len() != PROXY_BID_LEN {
+            return Err(AuctionError::DataTypeMismatch.into());
+        }
+
+        let proxy_bid: ProxyBid = try_from_slice_unchecked(&a.data.borrow_mut())?;
+
+        Ok(proxy_bid)
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
 pub struct BidderPot {
@@ -771,16 +2128,41 @@ pub struct BidderPot {
     pub auction_act: Pubkey,
     /// emptied or not
     pub emptied: bool,
+    /// Account that paid rent for the pot and the bidder metadata, refunded when they are closed
+    pub payer: Pubkey,
+    /// Mint the pot holds, not recorded on legacy pots
+    pub mint: Option<Pubkey>,
 }
 
+pub const BIDDER_POT_LEN: usize = 32 + 32 + 32 + 1 + 32 + 33;
+/// Pots created before the payer and mint were recorded.
+pub const LEGACY_BIDDER_POT_LEN: usize = 32 + 32 + 32 + 1;
+
 impl BidderPot {
     pub fn from_account_info(a: &AccountInfo) -> Result<BidderPot, ProgramError> {
-        if a.data_len() != mem::size_of::<BidderPot>() {
-            return Err(AuctionError::DataTypeMismatch.into());
-        }
-
-        let bidder_pot: BidderPot = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        let data = a.data.borrow();
+        let bidder_pot: BidderPot = match data.len() {
+            BIDDER_POT_LEN => try_from_slice_unchecked(&data)?,
+            // The bidder stands in as the payer of legacy pots.
+            LEGACY_BIDDER_POT_LEN => {
+                try_from_slice_unchecked(&[&data[..], &data[32..64], &[0]].concat())?
+            }
+            _ => return Err(AuctionError::DataTypeMismatch.into()),
+        };
 
         Ok(bidder_pot)
     }
+
+    /// Mint the pot holds, pots that did not record it hold the auction's token_mint.
+    pub fn mint(&self, auction: &AuctionData) -> Pubkey {
+        self.mint.unwrap_or(auction.token_mint)
+    }
+
+    /// Writes the pot back, leaving out the fields legacy pots have no room for.
+    pub fn save(&self, a: &AccountInfo) -> ProgramResult {
+        let data = self.try_to_vec()?;
+        let len = a.data_len();
+        a.data.borrow_mut().copy_from_slice(&data[..len]);
+        Ok(())
+    }
 }
@@ -1,18 +1,36 @@
-//! Cancels an existing bid. This only works in two cases:
+//! Cancels an existing bid. This only works in three cases:
 //!
 //! 1) The auction is still going on, in which case it is possible to cancel a bid at any time.
 //! 2) The auction has finished, but the bid did not win. This allows users to claim back their
 //!    funds from bid accounts.
+//! 3) The auction was cancelled by its authority, in which case every bid is refunded in full.
+//!
+//! Sealed bids are locked from the auction end time until the reveal window closes, and sealed
+//! bids that were never revealed stay locked if the auction forfeits them.
+//!
+//! Bids held in the bid book of a bid-book auction are firm, only outbid ones can be cancelled.
+//! Such auctions take their bid book in place of the bid commitment.
+//!
+//! Auctions with cancellation rules lock winning bids in from a while before the end time, and
+//! keep a penalty out of winning bids cancelled before then. The penalty goes to the authority's
+//! account, passed in place of the bid commitment.
+//!
+//! Lamport bids pass the bidder pot in place of the pot token account, and are refunded in
+//! lamports to the account given in place of the bidder's token account.
 
 use crate::{
     errors::AuctionError,
-    processor::{AuctionData, AuctionDataExtended, BidderMetadata, BidderPot},
+    event::AuctionEvent,
+    processor::{
+        AuctionData, AuctionDataExtended, Bid, BidBook, BidCommitment, BidState, BidderMetadata,
+        BidderPot, SettlementMode,
+    },
     utils::{
-        assert_derivation, assert_initialized, assert_owned_by, assert_signer,
-        assert_token_program_matches_package, create_or_allocate_account_raw, spl_token_transfer,
-        TokenTransferParams,
+        assert_derivation, assert_initialized, assert_owned_by, assert_pot_balance, assert_signer,
+        assert_token_program_matches_package, create_or_allocate_account_raw, is_lamport_pot,
+        pot_transfer, spl_token_transfer, TokenTransferParams,
     },
-    EXTENDED, PREFIX,
+    COMMITMENT, EXTENDED, PREFIX,
 };
 
 use super::AuctionState;
@@ -21,6 +39,7 @@ use {
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         account_info::{next_account_info, AccountInfo},
+        clock::UnixTimestamp,
         entrypoint::ProgramResult,
         msg,
         program::invoke_signed,
@@ -42,6 +61,7 @@ pub struct CancelBidArgs {
 struct Accounts<'a, 'b: 'a> {
     auction: &'a AccountInfo<'b>,
     auction_extended: &'a AccountInfo<'b>,
+    bid_commitment: Option<&'a AccountInfo<'b>>,
     bidder_meta: &'a AccountInfo<'b>,
     bidder_pot: &'a AccountInfo<'b>,
     bidder_pot_token: &'a AccountInfo<'b>,
@@ -72,6 +92,7 @@ fn parse_accounts<'a, 'b: 'a>(
         rent: next_account_info(account_iter)?,
         system: next_account_info(account_iter)?,
         token_program: next_account_info(account_iter)?,
+        bid_commitment: next_account_info(account_iter).ok(),
     };
 
     assert_owned_by(accounts.auction, program_id)?;
@@ -79,7 +100,6 @@ fn parse_accounts<'a, 'b: 'a>(
     assert_owned_by(accounts.bidder_meta, program_id)?;
     assert_owned_by(accounts.mint, &spl_token::id())?;
     assert_owned_by(accounts.bidder_pot, program_id)?;
-    assert_owned_by(accounts.bidder_pot_token, &spl_token::id())?;
     assert_signer(accounts.bidder)?;
     assert_token_program_matches_package(accounts.token_program)?;
 
@@ -99,10 +119,11 @@ pub fn cancel_bid(
     let accounts = parse_accounts(program_id, accounts)?;
 
     // The account within the pot must be owned by us.
-    let actual_account: Account = assert_initialized(accounts.bidder_pot_token)?;
-    if actual_account.owner != *accounts.auction.key {
-        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
-    }
+    let pot_amount = assert_pot_balance(
+        accounts.auction.key,
+        accounts.bidder_pot,
+        accounts.bidder_pot_token,
+    )?;
 
     // Derive and load Auction.
     let auction_bump = assert_derivation(
@@ -124,13 +145,19 @@ pub fn cancel_bid(
 
     // Load the auction and verify this bid is valid.
     let mut auction = AuctionData::from_account_info(accounts.auction)?;
-    // The mint provided in this bid must match the one the auction was initialized with.
-    if auction.token_mint != *accounts.mint.key {
-        return Err(AuctionError::IncorrectMint.into());
-    }
 
-    // Load auction extended account to check instant_sale_price
+    // Load auction extended account to check instant_sale_price and sealed bid settings,
     // and update cancelled bids if auction still active
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
     let mut auction_extended = AuctionDataExtended::from_account_info(accounts.auction_extended)?;
 
     // Load the clock, used for various auction timing.
@@ -154,6 +181,7 @@ pub fn cancel_bid(
     if accounts.bidder_meta.owner != program_id {
         return Err(AuctionError::MetadataInvalid.into());
     }
+    let metadata = BidderMetadata::from_account_info(accounts.bidder_meta)?;
 
     // Derive Pot address, this account wraps/holds an SPL account to transfer tokens out of.
     let pot_seeds = [
@@ -184,6 +212,40 @@ pub fn cancel_bid(
         return Err(AuctionError::InvalidState.into());
     }
 
+    // Refuse to cancel raffle tickets until the winners are drawn, losing tickets are refunded
+    // after.
+    if let BidState::Raffle { .. } = auction.bid_state {
+        if !auction.is_over(clock.unix_timestamp)? {
+            return Err(AuctionError::RaffleNotDrawn.into());
+        }
+    }
+
+    // Refuse to cancel sealed bids while they are being revealed, or unrevealed ones that the
+    // auction keeps.
+    assert_sealed_bid_refundable(
+        program_id,
+        accounts.auction,
+        &auction,
+        &auction_extended,
+        accounts.bidder.key,
+        accounts.bid_commitment,
+        clock.unix_timestamp,
+    )?;
+
+    // Refuse to cancel a bid still held in the bid book. The bid is the one last placed, anyone
+    // can add to what the pot holds.
+    assert_bid_book_refundable(
+        &auction,
+        &auction_extended,
+        accounts.bid_commitment,
+        &Bid(*accounts.bidder.key, metadata.last_bid),
+    )?;
+
+    // Refuse to cancel a dutch auction bid, it was taken at the asking price and is final.
+    if let (Some(_), BidState::DutchAuction { .. }) = (winner_bid_index, &auction.bid_state) {
+        return Err(AuctionError::InvalidState.into());
+    }
+
     // Refuse to cancel if bidder set price above or equal instant_sale_price
     if let Some(bid_index) = winner_bid_index {
         if let Some(instant_sale_price) = auction_extended.instant_sale_price {
@@ -193,25 +255,69 @@ pub fn cancel_bid(
         }
     }
 
+    // Winning bids can't be cancelled once the lock-up before the end time starts, and pay the
+    // cancellation penalty until then. A cancelled auction refunds every bid in full.
+    let mut penalty = 0;
+    if let (Some(_), Some(rules)) = (winner_bid_index, &auction_extended.cancellation_rules) {
+        if auction.state != AuctionState::Cancelled {
+            if rules.locked_up(auction.ended_at, clock.unix_timestamp) {
+                return Err(AuctionError::BidLockedUp.into());
+            }
+            penalty = rules.penalty_for(pot_amount);
+        }
+    }
+
     // Confirm we're looking at the real SPL account for this bidder.
     let bidder_pot = BidderPot::from_account_info(accounts.bidder_pot)?;
     if bidder_pot.bidder_pot != *accounts.bidder_pot_token.key {
         return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
     }
 
-    // Transfer SPL bid balance back to the user.
-    let account: Account = Account::unpack_from_slice(&accounts.bidder_pot_token.data.borrow())?;
-    spl_token_transfer(TokenTransferParams {
-        source: accounts.bidder_pot_token.clone(),
-        destination: accounts.bidder_token.clone(),
-        authority: accounts.auction.clone(),
-        authority_signer_seeds: auction_seeds,
-        token_program: accounts.token_program.clone(),
-        amount: account.amount,
-    })?;
+    // The mint provided in this bid must match the one the bid was paid in.
+    if bidder_pot.mint(&auction) != *accounts.mint.key {
+        return Err(AuctionError::IncorrectMint.into());
+    }
+
+    // Pay the penalty to the authority, through the account other auctions take their bid
+    // commitment or bid book in.
+    if penalty > 0 {
+        let penalty_account = accounts
+            .bid_commitment
+            .ok_or(AuctionError::InvalidPenaltyAccount)?;
+        assert_penalty_account(
+            &auction,
+            &bidder_pot.mint(&auction),
+            accounts.bidder_pot,
+            accounts.bidder_pot_token,
+            penalty_account,
+        )?;
+        pot_transfer(
+            accounts.bidder_pot,
+            TokenTransferParams {
+                source: accounts.bidder_pot_token.clone(),
+                destination: penalty_account.clone(),
+                authority: accounts.auction.clone(),
+                authority_signer_seeds: auction_seeds,
+                token_program: accounts.token_program.clone(),
+                amount: penalty,
+            },
+        )?;
+    }
+
+    // Transfer the bid balance back to the user.
+    pot_transfer(
+        accounts.bidder_pot,
+        TokenTransferParams {
+            source: accounts.bidder_pot_token.clone(),
+            destination: accounts.bidder_token.clone(),
+            authority: accounts.auction.clone(),
+            authority_signer_seeds: auction_seeds,
+            token_program: accounts.token_program.clone(),
+            amount: pot_amount - penalty,
+        },
+    )?;
 
     // Update Metadata
-    let metadata = BidderMetadata::from_account_info(accounts.bidder_meta)?;
     let already_cancelled = metadata.cancelled;
     BidderMetadata {
         cancelled: true,
@@ -219,34 +325,147 @@ pub fn cancel_bid(
     }
     .serialize(&mut *accounts.bidder_meta.data.borrow_mut())?;
 
+    // The pot no longer holds a bid.
+    if !already_cancelled {
+        auction_extended.close_bidder_pot();
+    }
+
     // Update Auction
 
     if auction.state != AuctionState::Ended {
         // Once ended we want uncancelled bids to retain it's pre-ending count
+        msg!("Already cancelled is {:?}", already_cancelled);
+
+        if !already_cancelled && auction_extended.total_uncancelled_bids > 0 {
+            auction_extended.total_uncancelled_bids = auction_extended
+                .total_uncancelled_bids
+                .checked_sub(1)
+                .ok_or(AuctionError::NumericalOverflowError)?;
+        }
+
+        // A cancelled bid has nothing left for its proxy bid to defend.
+        if auction_extended.proxy_bidder == Some(*accounts.bidder.key) {
+            auction_extended.proxy_bidder = None;
+        }
+
+        // Only cancel the bid if the auction has not ended yet. Losing bids on second-price
+        // auctions also stay past the end time, as they set the price the winners pay.
+        let is_second_price = auction_extended.settlement_mode == Some(SettlementMode::SecondPrice);
+        if !(is_second_price && auction.ended(clock.unix_timestamp)?) {
+            auction.bid_state.cancel_bid(*accounts.bidder.key);
+            auction.save(accounts.auction)?;
+        }
+    }
+
+    auction_extended.save(accounts.auction_extended)?;
+
+    AuctionEvent::BidCancelled {
+        auction: *accounts.auction.key,
+        bidder: *accounts.bidder.key,
+        refunded: pot_amount - penalty,
+        penalty,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// Cancellation penalties go to the auction authority, in the mint the bid was paid in or to
+/// their wallet for lamport bids.
+fn assert_penalty_account(
+    auction: &AuctionData,
+    mint: &Pubkey,
+    bidder_pot: &AccountInfo,
+    bidder_pot_token: &AccountInfo,
+    penalty_account: &AccountInfo,
+) -> ProgramResult {
+    if is_lamport_pot(bidder_pot, bidder_pot_token) {
+        if *penalty_account.key != auction.authority {
+            return Err(AuctionError::InvalidPenaltyAccount.into());
+        }
+        return Ok(());
+    }
+
+    assert_owned_by(penalty_account, &spl_token::id())?;
+    let account: Account = assert_initialized(penalty_account)?;
+    if account.owner != auction.authority || account.mint != *mint {
+        return Err(AuctionError::InvalidPenaltyAccount.into());
+    }
+
+    Ok(())
+}
+
+/// Sealed bids are locked from the auction end time until the reveal window closes, and bids that
+/// were never revealed stay locked if the auction forfeits them. A cancelled auction keeps nothing.
+pub(crate) fn assert_sealed_bid_refundable(
+    program_id: &Pubkey,
+    auction_info: &AccountInfo,
+    auction: &AuctionData,
+    auction_extended: &AuctionDataExtended,
+    bidder: &Pubkey,
+    bid_commitment: Option<&AccountInfo>,
+    now: UnixTimestamp,
+) -> ProgramResult {
+    let config = match &auction_extended.sealed_bid_config {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+
+    // Nothing is sold when the reserve is not met, so unrevealed bids are refunded as well.
+    if auction.state == AuctionState::Cancelled
+        || auction.reserve_not_met()
+        || !auction.ended(now)?
+    {
+        return Ok(());
+    }
+
+    if now <= auction.reveal_deadline(config)? {
+        return Err(AuctionError::RevealWindowOpen.into());
+    }
+
+    if config.forfeit_unrevealed {
+        let bid_commitment = bid_commitment.ok_or(AuctionError::InvalidBidAccount)?;
+        assert_owned_by(bid_commitment, program_id)?;
         assert_derivation(
             program_id,
-            accounts.auction_extended,
+            bid_commitment,
             &[
                 PREFIX.as_bytes(),
                 program_id.as_ref(),
-                args.resource.as_ref(),
-                EXTENDED.as_bytes(),
+                auction_info.key.as_ref(),
+                bidder.as_ref(),
+                COMMITMENT.as_bytes(),
             ],
         )?;
+        if !BidCommitment::from_account_info(bid_commitment)?.revealed {
+            return Err(AuctionError::InvalidState.into());
+        }
+    }
 
-        msg!("Already cancelled is {:?}", already_cancelled);
+    Ok(())
+}
 
-        if !already_cancelled && auction_extended.total_uncancelled_bids > 0 {
-            auction_extended.total_uncancelled_bids = auction_extended
-                .total_uncancelled_bids
-                .checked_sub(1)
-                .ok_or(AuctionError::NumericalOverflowError)?;
-        }
-        auction_extended.serialize(&mut *accounts.auction_extended.data.borrow_mut())?;
+/// Bids held in the bid book are firm, only those that were outbid can be refunded. A cancelled
+/// auction keeps nothing.
+pub(crate) fn assert_bid_book_refundable(
+    auction: &AuctionData,
+    auction_extended: &AuctionDataExtended,
+    bid_book: Option<&AccountInfo>,
+    bid: &Bid,
+) -> ProgramResult {
+    if !matches!(auction.bid_state, BidState::BidBook { .. })
+        || auction.state == AuctionState::Cancelled
+    {
+        return Ok(());
+    }
 
-        // Only cancel the bid if the auction has not ended yet
-        auction.bid_state.cancel_bid(*accounts.bidder.key);
-        auction.serialize(&mut *accounts.auction.data.borrow_mut())?;
+    let bid_book = bid_book.ok_or(AuctionError::InvalidBidBook)?;
+    if auction_extended.bid_book != Some(*bid_book.key) {
+        return Err(AuctionError::InvalidBidBook.into());
+    }
+
+    if BidBook::contains(bid_book, bid) {
+        return Err(AuctionError::InvalidState.into());
     }
 
     Ok(())
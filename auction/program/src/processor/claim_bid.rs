@@ -1,15 +1,32 @@
 //! Claim bid winnings into a target SPL account, only the authorised key can do this, though the
 //! target can be any SPL account.
+//!
+//! On sealed-bid auctions that forfeit unrevealed bids, the deposit behind an unrevealed bid can
+//! be claimed the same way once the auction has ended.
+//!
+//! Only the settlement price is claimed, whatever else the pot holds, such as the rest of a
+//! second-price bid or an unused proxy bid maximum, is refunded to the bidder's token account.
+//!
+//! Bid-book auctions take their bid book in place of the bid commitment, followed by the bidder
+//! metadata, and can only be claimed once the book has been sorted. The winning bid is the one the
+//! metadata records, anything else in the pot is refunded.
+//!
+//! Bids paid in one of the auction's payment mints are claimed in that mint, and lamport bids in
+//! lamports, to a wallet rather than a token account.
 
 use crate::{
     errors::AuctionError,
-    processor::{AuctionData, AuctionDataExtended, BidderMetadata, BidderPot},
+    event::AuctionEvent,
+    processor::{
+        AuctionData, AuctionDataExtended, AuctionState, Bid, BidBook, BidCommitment, BidState,
+        BidderMetadata, BidderPot,
+    },
     utils::{
-        assert_derivation, assert_initialized, assert_owned_by, assert_signer,
-        assert_token_program_matches_package, create_or_allocate_account_raw, spl_token_transfer,
-        TokenTransferParams,
+        assert_derivation, assert_initialized, assert_owned_by, assert_pot_balance, assert_signer,
+        assert_token_program_matches_package, create_or_allocate_account_raw, is_lamport_pot,
+        pot_transfer, spl_token_transfer, TokenTransferParams,
     },
-    EXTENDED, PREFIX,
+    COMMITMENT, EXTENDED, PREFIX,
 };
 
 use {
@@ -22,10 +39,11 @@ use {
         program_error::ProgramError,
         program_pack::Pack,
         pubkey::Pubkey,
-        system_instruction,
+        system_instruction, system_program,
         sysvar::{clock::Clock, Sysvar},
     },
     spl_token::state::Account,
+    std::{cmp, convert::TryFrom},
 };
 
 #[repr(C)]
@@ -44,7 +62,10 @@ struct Accounts<'a, 'b: 'a> {
     mint: &'a AccountInfo<'b>,
     clock_sysvar: &'a AccountInfo<'b>,
     token_program: &'a AccountInfo<'b>,
-    auction_extended: Option<&'a AccountInfo<'b>>,
+    auction_extended: &'a AccountInfo<'b>,
+    bidder_token: Option<&'a AccountInfo<'b>>,
+    bid_commitment: Option<&'a AccountInfo<'b>>,
+    bidder_meta: Option<&'a AccountInfo<'b>>,
 }
 
 fn parse_accounts<'a, 'b: 'a>(
@@ -62,19 +83,27 @@ fn parse_accounts<'a, 'b: 'a>(
         mint: next_account_info(account_iter)?,
         clock_sysvar: next_account_info(account_iter)?,
         token_program: next_account_info(account_iter)?,
-        auction_extended: next_account_info(account_iter).ok(),
+        auction_extended: next_account_info(account_iter)?,
+        bidder_token: next_account_info(account_iter).ok(),
+        bid_commitment: next_account_info(account_iter).ok(),
+        bidder_meta: next_account_info(account_iter).ok(),
     };
 
     assert_owned_by(accounts.auction, program_id)?;
     assert_owned_by(accounts.mint, &spl_token::id())?;
-    assert_owned_by(accounts.destination, &spl_token::id())?;
-    assert_owned_by(accounts.bidder_pot_token, &spl_token::id())?;
     assert_owned_by(accounts.bidder_pot, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
     assert_signer(accounts.authority)?;
     assert_token_program_matches_package(accounts.token_program)?;
 
-    if let Some(auction_extended) = accounts.auction_extended {
-        assert_owned_by(auction_extended, program_id)?;
+    // Lamport bids are paid out in lamports, to accounts that are not token accounts.
+    if is_lamport_pot(accounts.bidder_pot, accounts.bidder_pot_token) {
+        assert_owned_by(accounts.destination, &system_program::id())?;
+    } else {
+        assert_owned_by(accounts.destination, &spl_token::id())?;
+        if let Some(bidder_token) = accounts.bidder_token {
+            assert_owned_by(bidder_token, &spl_token::id())?;
+        }
     }
 
     if *accounts.token_program.key != spl_token::id() {
@@ -94,10 +123,11 @@ pub fn claim_bid(
     let clock = Clock::from_account_info(accounts.clock_sysvar)?;
 
     // The account within the pot must be owned by us.
-    let actual_account: Account = assert_initialized(accounts.bidder_pot_token)?;
-    if actual_account.owner != *accounts.auction.key {
-        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
-    }
+    let pot_amount = assert_pot_balance(
+        accounts.auction.key,
+        accounts.bidder_pot,
+        accounts.bidder_pot_token,
+    )?;
 
     // Derive and load Auction.
     let auction_bump = assert_derivation(
@@ -124,35 +154,46 @@ pub fn claim_bid(
         return Err(AuctionError::InvalidAuthority.into());
     }
 
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+    let mut auction_extended = AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+
+    // Sealed bids are only settled once the auction has been ended after the reveal window.
+    let is_sealed = matches!(auction.bid_state, BidState::SealedBidAuction { .. });
+    if is_sealed && auction.state != AuctionState::Ended {
+        return Err(AuctionError::InvalidState.into());
+    }
+
     // User must have won the auction in order to claim their funds. Check early as the rest of the
     // checks will be for nothing otherwise.
-    let bid_index = auction.is_winner(accounts.bidder.key);
-    if bid_index.is_none() {
+    let is_bid_book = matches!(auction.bid_state, BidState::BidBook { .. });
+    let (bid_index, bid_book_amount) = if is_bid_book {
+        let bid = bid_book_bid(program_id, &accounts)?;
+        (bid_book_winner(&accounts, &auction_extended, &bid)?, bid.1)
+    } else {
+        (auction.is_winner(accounts.bidder.key), 0)
+    };
+    // Nothing is forfeited on an auction whose reserve was not met.
+    if bid_index.is_none()
+        && !(is_sealed
+            && !auction.reserve_not_met()
+            && is_forfeited(program_id, &accounts, &auction_extended)?)
+    {
         msg!("User {:?} is not winner", accounts.bidder.key);
         return Err(AuctionError::InvalidState.into());
     }
 
-    let instant_sale_price = accounts.auction_extended.and_then(|info| {
-        assert_derivation(
-            program_id,
-            info,
-            &[
-                PREFIX.as_bytes(),
-                program_id.as_ref(),
-                args.resource.as_ref(),
-                EXTENDED.as_bytes(),
-            ],
-        )
-        .ok()?;
-
-        AuctionDataExtended::from_account_info(info)
-            .ok()?
-            .instant_sale_price
-    });
-
     // Auction either must have ended or bidder pay instant_sale_price
-    if !auction.ended(clock.unix_timestamp)? {
-        match instant_sale_price {
+    if !auction.ended(clock.unix_timestamp)? && auction.state != AuctionState::Ended {
+        match auction_extended.instant_sale_price {
             Some(instant_sale_price)
                 if auction.bid_state.amount(bid_index.unwrap()) < instant_sale_price =>
             {
@@ -163,11 +204,6 @@ pub fn claim_bid(
         }
     }
 
-    // The mint provided in this claim must match the one the auction was initialized with.
-    if auction.token_mint != *accounts.mint.key {
-        return Err(AuctionError::IncorrectMint.into());
-    }
-
     // Derive Pot address, this account wraps/holds an SPL account to transfer tokens into.
     let pot_seeds = [
         PREFIX.as_bytes(),
@@ -197,18 +233,179 @@ pub fn claim_bid(
         return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
     }
 
-    // Transfer SPL bid balance back to the user.
-    spl_token_transfer(TokenTransferParams {
-        source: accounts.bidder_pot_token.clone(),
-        destination: accounts.destination.clone(),
-        authority: accounts.auction.clone(),
-        authority_signer_seeds: auction_seeds,
-        token_program: accounts.token_program.clone(),
-        amount: actual_account.amount,
-    })?;
+    // The mint provided in this claim must match the one the bid was paid in.
+    let mint = bidder_pot.mint(&auction);
+    if mint != *accounts.mint.key {
+        return Err(AuctionError::IncorrectMint.into());
+    }
+
+    // Winners only pay the settlement price, anything else in the pot, such as an unused proxy
+    // bid maximum or the excess over a second price, is theirs to keep.
+    let amount = match bid_index {
+        // Bid-book winners pay their own bid.
+        Some(_) if is_bid_book => cmp::min(bid_book_amount, pot_amount),
+        // Bids paid in another mint hold what the bid was worth when placed, and pay the same
+        // share of it.
+        Some(index) if mint != auction.token_mint => pot_share(
+            pot_amount,
+            auction.settlement_price(index, auction_extended.settlement_mode.as_ref()),
+            auction.bid_state.amount(index),
+        )?,
+        Some(index) => cmp::min(
+            auction.settlement_price(index, auction_extended.settlement_mode.as_ref()),
+            pot_amount,
+        ),
+        None => pot_amount,
+    };
+
+    let refund = pot_amount - amount;
+    if refund > 0 {
+        let bidder_token = accounts
+            .bidder_token
+            .ok_or(AuctionError::InvalidBidAccount)?;
+        // Lamport refunds go back to the bidder's wallet.
+        if is_lamport_pot(accounts.bidder_pot, accounts.bidder_pot_token) {
+            if bidder_token.key != accounts.bidder.key {
+                return Err(AuctionError::InvalidBidAccount.into());
+            }
+        } else {
+            let bidder_token_account: Account = assert_initialized(bidder_token)?;
+            if bidder_token_account.owner != *accounts.bidder.key
+                || bidder_token_account.mint != mint
+            {
+                return Err(AuctionError::InvalidBidAccount.into());
+            }
+        }
+
+        pot_transfer(
+            accounts.bidder_pot,
+            TokenTransferParams {
+                source: accounts.bidder_pot_token.clone(),
+                destination: bidder_token.clone(),
+                authority: accounts.auction.clone(),
+                authority_signer_seeds: auction_seeds,
+                token_program: accounts.token_program.clone(),
+                amount: refund,
+            },
+        )?;
+    }
+
+    // Transfer the bid balance to the destination.
+    pot_transfer(
+        accounts.bidder_pot,
+        TokenTransferParams {
+            source: accounts.bidder_pot_token.clone(),
+            destination: accounts.destination.clone(),
+            authority: accounts.auction.clone(),
+            authority_signer_seeds: auction_seeds,
+            token_program: accounts.token_program.clone(),
+            amount,
+        },
+    )?;
+
+    if !bidder_pot.emptied {
+        auction_extended.close_bidder_pot();
+        auction_extended.save(accounts.auction_extended)?;
+    }
 
     bidder_pot.emptied = true;
-    bidder_pot.serialize(&mut *accounts.bidder_pot.data.borrow_mut())?;
+    bidder_pot.save(accounts.bidder_pot)?;
+
+    AuctionEvent::BidClaimed {
+        auction: *accounts.auction.key,
+        bidder: *accounts.bidder.key,
+        destination: *accounts.destination.key,
+        amount,
+        refunded: refund,
+    }
+    .emit()?;
 
     Ok(())
 }
+
+/// Whether the bidder made a sealed bid that was never revealed, on an auction that forfeits such
+/// bids to the authority.
+fn is_forfeited(
+    program_id: &Pubkey,
+    accounts: &Accounts,
+    auction_extended: &AuctionDataExtended,
+) -> Result<bool, ProgramError> {
+    let bid_commitment = match accounts.bid_commitment {
+        Some(bid_commitment) => bid_commitment,
+        None => return Ok(false),
+    };
+
+    let forfeit_unrevealed = match &auction_extended.sealed_bid_config {
+        Some(config) => config.forfeit_unrevealed,
+        None => false,
+    };
+
+    assert_owned_by(bid_commitment, program_id)?;
+    assert_derivation(
+        program_id,
+        bid_commitment,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            COMMITMENT.as_bytes(),
+        ],
+    )?;
+
+    Ok(forfeit_unrevealed && !BidCommitment::from_account_info(bid_commitment)?.revealed)
+}
+
+/// The bid the bidder last placed, as their metadata records it. What the pot holds can't be
+/// trusted for this, anyone can add to it.
+fn bid_book_bid(program_id: &Pubkey, accounts: &Accounts) -> Result<Bid, ProgramError> {
+    let bidder_meta = accounts.bidder_meta.ok_or(AuctionError::MetadataInvalid)?;
+    assert_owned_by(bidder_meta, program_id)?;
+    assert_derivation(
+        program_id,
+        bidder_meta,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            "metadata".as_bytes(),
+        ],
+    )?;
+
+    let metadata = BidderMetadata::from_account_info(bidder_meta)?;
+    if metadata.cancelled {
+        return Err(AuctionError::InvalidState.into());
+    }
+    Ok(Bid(*accounts.bidder.key, metadata.last_bid))
+}
+
+/// Where the bid ranks in the bid book, if it won. The book must be sorted.
+fn bid_book_winner(
+    accounts: &Accounts,
+    auction_extended: &AuctionDataExtended,
+    bid: &Bid,
+) -> Result<Option<usize>, ProgramError> {
+    let bid_book = accounts
+        .bid_commitment
+        .ok_or(AuctionError::InvalidBidBook)?;
+    if auction_extended.bid_book != Some(*bid_book.key) {
+        return Err(AuctionError::InvalidBidBook.into());
+    }
+
+    if !BidBook::is_sorted(bid_book) {
+        return Err(AuctionError::BidBookNotSorted.into());
+    }
+
+    Ok(BidBook::get_is_winner(bid_book, bid))
+}
+
+/// Part of a pot holding `bid` that pays `price`, rounded up in favour of the authority.
+fn pot_share(pot: u64, price: u64, bid: u64) -> Result<u64, ProgramError> {
+    if bid == 0 || price >= bid {
+        return Ok(pot);
+    }
+
+    let share = (pot as u128 * price as u128 + bid as u128 - 1) / bid as u128;
+    u64::try_from(share).map_err(|_| AuctionError::NumericalOverflowError.into())
+}
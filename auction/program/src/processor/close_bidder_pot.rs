@@ -0,0 +1,189 @@
+//! Closes the accounts a bidder created to bid, once the auction is over and their bid was
+//! refunded or claimed. The rent of the pot, the pot token account and the bidder metadata goes
+//! back to whoever paid for them.
+//!
+//! Metaplex reads the bidder metadata when prizes are redeemed, so the accounts of a winner whose
+//! bid was claimed can only be closed with the auction authority signing, once it no longer needs
+//! them.
+
+use crate::{
+    errors::AuctionError,
+    processor::{AuctionData, BidderMetadata, BidderPot},
+    utils::{
+        assert_derivation, assert_owned_by, assert_pot_balance, assert_signer,
+        assert_token_program_matches_package, close_program_account, is_lamport_pot,
+    },
+    PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program::invoke_signed,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::{clock::Clock, Sysvar},
+    },
+    spl_token::instruction::close_account,
+};
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct CloseBidderPotArgs {
+    /// The resource being auctioned. See AuctionData.
+    pub resource: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    bidder_meta: &'a AccountInfo<'b>,
+    bidder_pot: &'a AccountInfo<'b>,
+    bidder_pot_token: &'a AccountInfo<'b>,
+    bidder: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    payer: &'a AccountInfo<'b>,
+    token_program: &'a AccountInfo<'b>,
+    authority: Option<&'a AccountInfo<'b>>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        bidder: next_account_info(account_iter)?,
+        bidder_pot: next_account_info(account_iter)?,
+        bidder_pot_token: next_account_info(account_iter)?,
+        bidder_meta: next_account_info(account_iter)?,
+        auction: next_account_info(account_iter)?,
+        payer: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        token_program: next_account_info(account_iter)?,
+        authority: next_account_info(account_iter).ok(),
+    };
+
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.bidder_pot, program_id)?;
+    assert_owned_by(accounts.bidder_meta, program_id)?;
+    assert_signer(accounts.bidder)?;
+    assert_token_program_matches_package(accounts.token_program)?;
+
+    Ok(accounts)
+}
+
+pub fn close_bidder_pot<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    args: CloseBidderPotArgs,
+) -> ProgramResult {
+    msg!("+ Processing CloseBidderPot");
+    let accounts = parse_accounts(program_id, accounts)?;
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    let auction_bump = assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+
+    let auction_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        &[auction_bump],
+    ];
+
+    assert_derivation(
+        program_id,
+        accounts.bidder_pot,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+        ],
+    )?;
+
+    assert_derivation(
+        program_id,
+        accounts.bidder_meta,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            "metadata".as_bytes(),
+        ],
+    )?;
+
+    // Bids can still be placed, and would need these accounts, until the auction is over.
+    let auction = AuctionData::from_account_info(accounts.auction)?;
+    if !auction.is_over(clock.unix_timestamp)? {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    let bidder_pot = BidderPot::from_account_info(accounts.bidder_pot)?;
+    if bidder_pot.bidder_pot != *accounts.bidder_pot_token.key {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    if bidder_pot.payer != *accounts.payer.key {
+        return Err(AuctionError::PayerMismatch.into());
+    }
+
+    let pot_amount = assert_pot_balance(
+        accounts.auction.key,
+        accounts.bidder_pot,
+        accounts.bidder_pot_token,
+    )?;
+
+    // The bid must have been refunded or claimed, not just be worth nothing.
+    let metadata = BidderMetadata::from_account_info(accounts.bidder_meta)?;
+    if pot_amount > 0 || !(metadata.cancelled || bidder_pot.emptied) {
+        return Err(AuctionError::BidderPotNotEmpty.into());
+    }
+
+    // Refunds cancel the bid, so an emptied pot of an uncancelled bid was claimed by the winner.
+    if !metadata.cancelled {
+        let authority = accounts.authority.ok_or(AuctionError::InvalidAuthority)?;
+        if auction.authority != *authority.key {
+            return Err(AuctionError::InvalidAuthority.into());
+        }
+        if !authority.is_signer {
+            return Err(AuctionError::AuthorityNotSigner.into());
+        }
+    }
+
+    // Lamport bids were escrowed in the pot itself, so there is no token account to close.
+    if !is_lamport_pot(accounts.bidder_pot, accounts.bidder_pot_token) {
+        invoke_signed(
+            &close_account(
+                accounts.token_program.key,
+                accounts.bidder_pot_token.key,
+                accounts.payer.key,
+                accounts.auction.key,
+                &[],
+            )?,
+            &[
+                accounts.bidder_pot_token.clone(),
+                accounts.payer.clone(),
+                accounts.auction.clone(),
+                accounts.token_program.clone(),
+            ],
+            &[auction_seeds],
+        )?;
+    }
+
+    close_program_account(accounts.bidder_pot, accounts.payer)?;
+    close_program_account(accounts.bidder_meta, accounts.payer)?;
+
+    Ok(())
+}
@@ -0,0 +1,375 @@
+//! Commits a sealed bid on a running sealed-bid auction. Only a hash of the bid is stored, along
+//! with a deposit that must cover the bid once it is revealed. Depositing more than the bid keeps
+//! the real amount hidden, the excess is refunded on reveal.
+
+use crate::{
+    errors::AuctionError,
+    event::AuctionEvent,
+    processor::{
+        start_if_scheduled, AuctionData, AuctionDataExtended, AuctionState, BidCommitment,
+        BidState, BidderMetadata, BidderPot, BIDDER_METADATA_LEN, BIDDER_POT_LEN,
+        BID_COMMITMENT_LEN,
+    },
+    utils::{
+        assert_derivation, assert_initialized, assert_owned_by, assert_passes_bid_gate,
+        assert_signer, assert_token_program_matches_package, create_or_allocate_account_raw,
+        spl_token_transfer, TokenTransferParams,
+    },
+    COMMITMENT, EXTENDED, PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        hash::{hashv, Hash},
+        msg,
+        program_error::ProgramError,
+        program_option::COption,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        sysvar::{clock::Clock, Sysvar},
+    },
+    spl_token::state::Account,
+};
+
+/// Hash a bidder commits to, binding the bid amount and salt to the bidder so a commitment cannot
+/// be copied by someone else.
+pub fn bid_commitment_hash(amount: u64, salt: u64, bidder: &Pubkey) -> Hash {
+    hashv(&[&amount.to_be_bytes(), &salt.to_be_bytes(), bidder.as_ref()])
+}
+
+/// Arguments for the CommitBid instruction discriminant .
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct CommitBidArgs {
+    /// Hash of the sealed bid, see bid_commitment_hash.
+    pub commitment: Hash,
+    /// Amount moved into the bidder pot, must be at least the sealed bid.
+    pub deposit: u64,
+    /// Resource being bid on.
+    pub resource: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    bid_commitment: &'a AccountInfo<'b>,
+    bidder_meta: &'a AccountInfo<'b>,
+    bidder_pot: &'a AccountInfo<'b>,
+    bidder_pot_token: &'a AccountInfo<'b>,
+    bidder: &'a AccountInfo<'b>,
+    bidder_token: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    mint: &'a AccountInfo<'b>,
+    payer: &'a AccountInfo<'b>,
+    rent: &'a AccountInfo<'b>,
+    system: &'a AccountInfo<'b>,
+    token_program: &'a AccountInfo<'b>,
+    transfer_authority: &'a AccountInfo<'b>,
+    trailing_accounts: &'a [AccountInfo<'b>],
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        bidder: next_account_info(account_iter)?,
+        bidder_token: next_account_info(account_iter)?,
+        bidder_pot: next_account_info(account_iter)?,
+        bidder_pot_token: next_account_info(account_iter)?,
+        bidder_meta: next_account_info(account_iter)?,
+        bid_commitment: next_account_info(account_iter)?,
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        mint: next_account_info(account_iter)?,
+        transfer_authority: next_account_info(account_iter)?,
+        payer: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        rent: next_account_info(account_iter)?,
+        system: next_account_info(account_iter)?,
+        token_program: next_account_info(account_iter)?,
+        trailing_accounts: account_iter.as_slice(),
+    };
+
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
+    assert_owned_by(accounts.bidder_token, &spl_token::id())?;
+
+    if !accounts.bidder_pot.data_is_empty() {
+        assert_owned_by(accounts.bidder_pot, program_id)?;
+    }
+    if !accounts.bidder_meta.data_is_empty() {
+        assert_owned_by(accounts.bidder_meta, program_id)?;
+    }
+    if !accounts.bid_commitment.data_is_empty() {
+        assert_owned_by(accounts.bid_commitment, program_id)?;
+    }
+
+    assert_owned_by(accounts.mint, &spl_token::id())?;
+    assert_owned_by(accounts.bidder_pot_token, &spl_token::id())?;
+    assert_signer(accounts.bidder)?;
+    assert_signer(accounts.payer)?;
+    assert_signer(accounts.transfer_authority)?;
+    assert_token_program_matches_package(accounts.token_program)?;
+
+    Ok(accounts)
+}
+
+pub fn commit_bid<'r, 'b: 'r>(
+    program_id: &Pubkey,
+    accounts: &'r [AccountInfo<'b>],
+    args: CommitBidArgs,
+) -> ProgramResult {
+    msg!("+ Processing CommitBid");
+    let accounts = parse_accounts(program_id, accounts)?;
+
+    // Derive and load Auction.
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+    let mut auction = AuctionData::from_account_info(accounts.auction)?;
+
+    // Only sealed-bid auctions take commitments.
+    if !matches!(auction.bid_state, BidState::SealedBidAuction { .. }) {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    // The mint provided in this bid must match the one the auction was initialized with.
+    if auction.token_mint != *accounts.mint.key {
+        return Err(AuctionError::IncorrectMint.into());
+    }
+
+    // Commitments are only taken while the auction is running, up to its end time. Scheduled
+    // auctions start with the first commitment made after their start time.
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+    if start_if_scheduled(
+        program_id,
+        &args.resource,
+        &mut auction,
+        accounts.auction_extended,
+        clock.unix_timestamp,
+    )? {
+        auction.save(accounts.auction)?;
+        AuctionEvent::AuctionStarted {
+            auction: *accounts.auction.key,
+            ended_at: auction.ended_at,
+        }
+        .emit()?;
+    }
+    if auction.state != AuctionState::Started || auction.ended(clock.unix_timestamp)? {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    // Derive Metadata key and load it.
+    let metadata_bump = assert_derivation(
+        program_id,
+        accounts.bidder_meta,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            "metadata".as_bytes(),
+        ],
+    )?;
+
+    // If metadata doesn't exist, create it.
+    if accounts.bidder_meta.owner != program_id {
+        create_or_allocate_account_raw(
+            *program_id,
+            accounts.bidder_meta,
+            accounts.rent,
+            accounts.system,
+            accounts.payer,
+            BIDDER_METADATA_LEN,
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                accounts.auction.key.as_ref(),
+                accounts.bidder.key.as_ref(),
+                "metadata".as_bytes(),
+                &[metadata_bump],
+            ],
+        )?;
+    } else {
+        // Verify the last commitment was cancelled before continuing.
+        let bidder_metadata: BidderMetadata =
+            BidderMetadata::from_account_info(accounts.bidder_meta)?;
+        if bidder_metadata.cancelled == false {
+            return Err(AuctionError::BidAlreadyActive.into());
+        }
+    };
+
+    // Derive the commitment key, creating the account on the first commitment.
+    let commitment_bump = assert_derivation(
+        program_id,
+        accounts.bid_commitment,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            COMMITMENT.as_bytes(),
+        ],
+    )?;
+
+    if accounts.bid_commitment.owner != program_id {
+        create_or_allocate_account_raw(
+            *program_id,
+            accounts.bid_commitment,
+            accounts.rent,
+            accounts.system,
+            accounts.payer,
+            BID_COMMITMENT_LEN,
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                accounts.auction.key.as_ref(),
+                accounts.bidder.key.as_ref(),
+                COMMITMENT.as_bytes(),
+                &[commitment_bump],
+            ],
+        )?;
+    }
+
+    // Derive Pot address, this account wraps/holds an SPL account to transfer tokens into and is
+    // also used as the authoriser of the SPL pot.
+    let pot_bump = assert_derivation(
+        program_id,
+        accounts.bidder_pot,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+        ],
+    )?;
+
+    // The account within the pot must be owned by us.
+    let actual_account: Account = assert_initialized(accounts.bidder_pot_token)?;
+    if actual_account.owner != *accounts.auction.key {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    if actual_account.delegate != COption::None {
+        return Err(AuctionError::DelegateShouldBeNone.into());
+    }
+
+    if actual_account.close_authority != COption::None {
+        return Err(AuctionError::CloseAuthorityShouldBeNone.into());
+    }
+
+    let bump_authority_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        accounts.auction.key.as_ref(),
+        accounts.bidder.key.as_ref(),
+        &[pot_bump],
+    ];
+
+    // If the bidder pot account is empty, we need to generate one.
+    if accounts.bidder_pot.data_is_empty() {
+        create_or_allocate_account_raw(
+            *program_id,
+            accounts.bidder_pot,
+            accounts.rent,
+            accounts.system,
+            accounts.payer,
+            BIDDER_POT_LEN,
+            bump_authority_seeds,
+        )?;
+
+        // Attach SPL token address to pot account.
+        let mut pot = BidderPot::from_account_info(accounts.bidder_pot)?;
+        pot.bidder_pot = *accounts.bidder_pot_token.key;
+        pot.bidder_act = *accounts.bidder.key;
+        pot.auction_act = *accounts.auction.key;
+        pot.payer = *accounts.payer.key;
+        pot.mint = Some(auction.token_mint);
+        pot.save(accounts.bidder_pot)?;
+    } else {
+        // Already exists, verify that the pot contains the specified SPL address.
+        let bidder_pot = BidderPot::from_account_info(accounts.bidder_pot)?;
+        if bidder_pot.bidder_pot != *accounts.bidder_pot_token.key {
+            return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+        }
+    }
+
+    // Update now we have new bid.
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+    let mut auction_extended: AuctionDataExtended =
+        AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+
+    // Gated auctions take the bidder's proof of holding as the last accounts.
+    if let Some(gate) = &auction_extended.bid_gate {
+        assert_passes_bid_gate(gate, accounts.bidder.key, accounts.trailing_accounts)?;
+    }
+
+    auction_extended.total_uncancelled_bids = auction_extended
+        .total_uncancelled_bids
+        .checked_add(1)
+        .ok_or(AuctionError::NumericalOverflowError)?;
+    auction_extended.open_bidder_pot()?;
+    auction_extended.save(accounts.auction_extended)?;
+
+    // Confirm payers SPL token balance is enough to pay the deposit.
+    let account: Account = Account::unpack_from_slice(&accounts.bidder_token.data.borrow())?;
+    if account.amount < args.deposit {
+        msg!(
+            "Deposit is too large: {:?}, compared to account amount of {:?}",
+            args.deposit,
+            account.amount
+        );
+        return Err(AuctionError::BalanceTooLow.into());
+    }
+
+    // Transfer deposit of SPL token to bid account.
+    spl_token_transfer(TokenTransferParams {
+        source: accounts.bidder_token.clone(),
+        destination: accounts.bidder_pot_token.clone(),
+        authority: accounts.transfer_authority.clone(),
+        authority_signer_seeds: bump_authority_seeds,
+        token_program: accounts.token_program.clone(),
+        amount: args.deposit,
+    })?;
+
+    BidCommitment {
+        bidder_pubkey: *accounts.bidder.key,
+        auction_pubkey: *accounts.auction.key,
+        commitment: args.commitment,
+        deposit: args.deposit,
+        revealed: false,
+    }
+    .serialize(&mut *accounts.bid_commitment.data.borrow_mut())?;
+
+    // The bid amount stays unknown until revealed.
+    BidderMetadata {
+        bidder_pubkey: *accounts.bidder.key,
+        auction_pubkey: *accounts.auction.key,
+        last_bid: 0,
+        last_bid_timestamp: clock.unix_timestamp,
+        cancelled: false,
+    }
+    .serialize(&mut *accounts.bidder_meta.data.borrow_mut())?;
+
+    Ok(())
+}
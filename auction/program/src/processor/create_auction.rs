@@ -1,10 +1,11 @@
-use mem::size_of;
-
 use crate::{
     errors::AuctionError,
+    event::AuctionEvent,
     processor::{
-        AuctionData, AuctionDataExtended, AuctionName, AuctionState, Bid, BidState, PriceFloor,
-        WinnerLimit, BASE_AUCTION_DATA_SIZE, MAX_AUCTION_DATA_EXTENDED_SIZE,
+        AuctionData, AuctionDataExtended, AuctionName, AuctionState, Bid, BidBook, BidGate,
+        BidState, CancellationRules, DutchAuctionSchedule, PaymentMint, PaymentRate, PriceFloor,
+        RaffleConfig, SealedBidConfig, SettlementMode, WinnerLimit, BASE_AUCTION_DATA_SIZE,
+        MAX_AUCTION_DATA_EXTENDED_SIZE, MAX_PAYMENT_MINTS,
     },
     utils::{assert_derivation, assert_owned_by, create_or_allocate_account_raw},
     EXTENDED, PREFIX,
@@ -46,12 +47,43 @@ pub struct CreateAuctionArgs {
     pub gap_tick_size_percentage: Option<u8>,
 }
 
+/// Settings of an auction beyond CreateAuctionArgs, kept in its AuctionDataExtended. Everything
+/// left at its default is simply not used by the auction.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct AuctionExtendedSettings {
+    /// Price at which a bid wins outright.
+    pub instant_sale_price: Option<u64>,
+    /// Auction name
+    pub name: Option<AuctionName>,
+    /// Run a dutch auction on this price schedule instead of an English auction.
+    pub dutch_auction_schedule: Option<DutchAuctionSchedule>,
+    /// Take sealed bids through CommitBid and RevealBid instead of open bids.
+    pub sealed_bid_config: Option<SealedBidConfig>,
+    /// Charge winners the next bid down instead of their own bid.
+    pub settlement_mode: Option<SettlementMode>,
+    /// Start the auction by itself at this time, instead of waiting for StartAuction.
+    pub start_auction_at: Option<UnixTimestamp>,
+    /// Only take bids from holders of this token.
+    pub bid_gate: Option<BidGate>,
+    /// Sell fixed-price tickets and draw the winners through DrawWinners.
+    pub raffle_config: Option<RaffleConfig>,
+    /// Rank bids in this bid book account instead of the auction account.
+    pub bid_book: Option<Pubkey>,
+    /// Also take bids paid in these mints, valued in the token_mint at their rates.
+    pub payment_mints: Option<Vec<PaymentMint>>,
+    /// Also take bids paid in lamports, wrapped SOL auctions only.
+    pub lamport_bids: Option<bool>,
+    /// Lock winning bids in before the end time, or charge a penalty for cancelling them.
+    pub cancellation_rules: Option<CancellationRules>,
+}
+
 struct Accounts<'a, 'b: 'a> {
     auction: &'a AccountInfo<'b>,
     auction_extended: &'a AccountInfo<'b>,
     payer: &'a AccountInfo<'b>,
     rent: &'a AccountInfo<'b>,
     system: &'a AccountInfo<'b>,
+    bid_book: Option<&'a AccountInfo<'b>>,
 }
 
 fn parse_accounts<'a, 'b: 'a>(
@@ -65,6 +97,7 @@ fn parse_accounts<'a, 'b: 'a>(
         auction_extended: next_account_info(account_iter)?,
         rent: next_account_info(account_iter)?,
         system: next_account_info(account_iter)?,
+        bid_book: next_account_info(account_iter).ok(),
     };
     Ok(accounts)
 }
@@ -73,8 +106,7 @@ pub fn create_auction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     args: CreateAuctionArgs,
-    instant_sale_price: Option<u64>,
-    name: Option<AuctionName>,
+    settings: AuctionExtendedSettings,
 ) -> ProgramResult {
     msg!("+ Processing CreateAuction");
     let accounts = parse_accounts(program_id, accounts)?;
@@ -91,23 +123,49 @@ pub fn create_auction(
     if auction_key != *accounts.auction.key {
         return Err(AuctionError::InvalidAuctionAccount.into());
     }
-    // The data must be large enough to hold at least the number of winners.
-    let auction_size = match args.winners {
-        WinnerLimit::Capped(n) => {
-            mem::size_of::<Bid>() * BidState::max_array_size_for(n) + BASE_AUCTION_DATA_SIZE
+    let bid_state = match (
+        &args.winners,
+        &settings.dutch_auction_schedule,
+        &settings.sealed_bid_config,
+        &settings.raffle_config,
+    ) {
+        (WinnerLimit::Capped(n), Some(_), None, None) => BidState::new_dutch(*n),
+        (WinnerLimit::Capped(n), None, Some(_), None) => BidState::new_sealed_bid(*n),
+        (WinnerLimit::Capped(n), None, None, Some(_)) => BidState::new_raffle(*n),
+        (WinnerLimit::Capped(n), None, None, None) if settings.bid_book.is_some() => {
+            BidState::new_bid_book(*n)
         }
-        WinnerLimit::Unlimited(_) => BASE_AUCTION_DATA_SIZE,
+        (WinnerLimit::Capped(n), None, None, None) => BidState::new_english(*n),
+        (WinnerLimit::Capped(_), _, _, Some(_)) => {
+            return Err(AuctionError::InvalidRaffleSettings.into())
+        }
+        (WinnerLimit::Capped(_), Some(_), Some(_), None) => {
+            return Err(AuctionError::InvalidSealedBidSettings.into())
+        }
+        (WinnerLimit::Unlimited(_), _, _, Some(_)) => {
+            return Err(AuctionError::InvalidRaffleSettings.into())
+        }
+        (WinnerLimit::Unlimited(_), _, _, None) => BidState::new_open_edition(),
     };
 
-    let bid_state = match args.winners {
-        WinnerLimit::Capped(n) => BidState::new_english(n),
-        WinnerLimit::Unlimited(_) => BidState::new_open_edition(),
+    assert_valid_auction_settings(&args, &bid_state, &settings)?;
+
+    // The data must be large enough to hold at least the number of winners, unless they are kept
+    // in a bid book.
+    let auction_size = match (&args.winners, &bid_state) {
+        (_, BidState::BidBook { .. }) | (WinnerLimit::Unlimited(_), _) => BASE_AUCTION_DATA_SIZE,
+        (WinnerLimit::Capped(n), _) => {
+            mem::size_of::<Bid>() * BidState::max_array_size_for(*n) + BASE_AUCTION_DATA_SIZE
+        }
     };
 
-    if let Some(gap_tick) = args.gap_tick_size_percentage {
-        if gap_tick > 100 {
-            return Err(AuctionError::InvalidGapTickSizePercentage.into());
+    if let (Some(key), BidState::BidBook { max, .. }) = (&settings.bid_book, &bid_state) {
+        let book = accounts.bid_book.ok_or(AuctionError::InvalidBidBook)?;
+        if book.key != key {
+            return Err(AuctionError::InvalidBidBook.into());
         }
+        assert_owned_by(book, program_id)?;
+        BidBook::initialize(book, &auction_key, *max)?;
     }
 
     // Create auction account with enough space for a winner tracking.
@@ -158,13 +216,30 @@ pub fn create_auction(
         total_uncancelled_bids: 0,
         tick_size: args.tick_size,
         gap_tick_size_percentage: args.gap_tick_size_percentage,
-        instant_sale_price,
-        name,
+        instant_sale_price: settings.instant_sale_price,
+        name: settings.name,
+        dutch_auction_schedule: settings.dutch_auction_schedule,
+        sealed_bid_config: settings.sealed_bid_config,
+        settlement_mode: settings.settlement_mode,
+        proxy_bidder: None,
+        bid_ledger_entries: None,
+        payer: Some(*accounts.payer.key),
+        open_bidder_pots: Some(0),
+        start_auction_at: settings.start_auction_at,
+        bid_gate: settings.bid_gate,
+        raffle_config: settings.raffle_config,
+        raffle_seed: None,
+        raffle_draw_slot: None,
+        bid_book: settings.bid_book,
+        payment_mints: settings.payment_mints,
+        lamport_bids: settings.lamport_bids,
+        cancellation_rules: settings.cancellation_rules,
     }
     .serialize(&mut *accounts.auction_extended.data.borrow_mut())?;
 
-    // Configure Auction.
-    AuctionData {
+    // Configure Auction, still in the Borsh layout clients deserialize. MigrateAuction moves it
+    // onto AuctionDataV2.
+    let auction = AuctionData {
         authority: args.authority,
         bid_state: bid_state,
         end_auction_at: args.end_auction_at,
@@ -174,8 +249,290 @@ pub fn create_auction(
         price_floor: args.price_floor,
         state: AuctionState::create(),
         token_mint: args.token_mint,
+    };
+    auction.serialize(&mut *accounts.auction.data.borrow_mut())?;
+
+    AuctionEvent::AuctionCreated {
+        auction: auction_key,
+        authority: auction.authority,
+        resource: args.resource,
+        token_mint: auction.token_mint,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// Checks the bidding rules of an auction fit together, shared by CreateAuction and UpdateAuction.
+pub(crate) fn assert_valid_auction_settings(
+    args: &CreateAuctionArgs,
+    bid_state: &BidState,
+    settings: &AuctionExtendedSettings,
+) -> ProgramResult {
+    let instant_sale_price = settings.instant_sale_price;
+
+    if let Some(gap_tick) = args.gap_tick_size_percentage {
+        if gap_tick > 100 {
+            return Err(AuctionError::InvalidGapTickSizePercentage.into());
+        }
+    }
+
+    assert_valid_price_floor(args, bid_state, instant_sale_price)?;
+
+    if let Some(schedule) = &settings.dutch_auction_schedule {
+        assert_valid_dutch_auction(args, instant_sale_price, schedule)?;
+    }
+
+    if let Some(config) = &settings.sealed_bid_config {
+        assert_valid_sealed_bid_auction(args, instant_sale_price, config)?;
+    }
+
+    if let Some(config) = &settings.raffle_config {
+        assert_valid_raffle(args, instant_sale_price, config)?;
+    }
+
+    if settings.bid_book.is_some() {
+        assert_valid_bid_book(args, bid_state, instant_sale_price)?;
+    }
+
+    if let Some(payment_mints) = &settings.payment_mints {
+        assert_valid_payment_mints(args, bid_state, payment_mints)?;
+    }
+
+    // Lamports stand in for wrapped SOL, so only auctions in it can take them.
+    if settings.lamport_bids == Some(true) && args.token_mint != spl_token::native_mint::id() {
+        return Err(AuctionError::IncorrectMint.into());
+    }
+
+    // Bid books are only paid in token accounts, as with payment mints, a lamport pot takes
+    // transfers from anyone.
+    if settings.lamport_bids == Some(true) && settings.bid_book.is_some() {
+        return Err(AuctionError::InvalidBidBookSettings.into());
+    }
+
+    if let Some(rules) = &settings.cancellation_rules {
+        assert_valid_cancellation_rules(args, bid_state, rules)?;
+    }
+
+    if let Some(SettlementMode::SecondPrice) = settings.settlement_mode {
+        // Only ranked bids have a next bid down to price against, and an instant sale has a fixed
+        // price of its own.
+        if !matches!(
+            bid_state,
+            BidState::EnglishAuction { .. } | BidState::SealedBidAuction { .. }
+        ) || instant_sale_price.is_some()
+        {
+            return Err(AuctionError::InvalidSettlementMode.into());
+        }
+    }
+
+    Ok(())
+}
+
+fn assert_valid_price_floor(
+    args: &CreateAuctionArgs,
+    bid_state: &BidState,
+    instant_sale_price: Option<u64>,
+) -> ProgramResult {
+    match args.price_floor {
+        // Only EndAuction marks an auction as having missed its reserve.
+        PriceFloor::ReserveNotMet(_) => Err(AuctionError::InvalidReservePrice.into()),
+        PriceFloor::ReservePrice(reserve) => {
+            // Only ranked bids can fall short of a reserve, and buying at the instant sale price
+            // has to meet it.
+            if !matches!(
+                bid_state,
+                BidState::EnglishAuction { .. } | BidState::SealedBidAuction { .. }
+            ) || instant_sale_price.map_or(false, |price| price < reserve[0])
+            {
+                return Err(AuctionError::InvalidReservePrice.into());
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn assert_valid_dutch_auction(
+    args: &CreateAuctionArgs,
+    instant_sale_price: Option<u64>,
+    schedule: &DutchAuctionSchedule,
+) -> ProgramResult {
+    // The price falls from start to floor over the lifetime of the auction, so it needs an end.
+    let duration = match args.end_auction_at {
+        Some(duration) => duration,
+        None => return Err(AuctionError::InvalidDutchAuctionSettings.into()),
+    };
+
+    if schedule.decay_interval <= 0
+        || schedule.decay_interval > duration
+        || schedule.start_price < schedule.floor_price
+    {
+        return Err(AuctionError::InvalidDutchAuctionSettings.into());
+    }
+
+    // Bids are taken at the asking price, none of the English auction bid rules apply.
+    if let WinnerLimit::Unlimited(_) = args.winners {
+        return Err(AuctionError::InvalidDutchAuctionSettings.into());
+    }
+
+    if args.end_auction_gap.is_some()
+        || args.tick_size.is_some()
+        || args.gap_tick_size_percentage.is_some()
+        || instant_sale_price.is_some()
+    {
+        return Err(AuctionError::InvalidDutchAuctionSettings.into());
+    }
+
+    Ok(())
+}
+
+fn assert_valid_sealed_bid_auction(
+    args: &CreateAuctionArgs,
+    instant_sale_price: Option<u64>,
+    config: &SealedBidConfig,
+) -> ProgramResult {
+    // Commitments are taken until the end time, reveals during the window after it.
+    if args.end_auction_at.is_none() || config.reveal_window <= 0 {
+        return Err(AuctionError::InvalidSealedBidSettings.into());
+    }
+
+    if let WinnerLimit::Unlimited(_) = args.winners {
+        return Err(AuctionError::InvalidSealedBidSettings.into());
+    }
+
+    // Nothing is visible while bidding, so there is no gap to extend, previous bid to outbid by a
+    // tick or instant price to meet.
+    if args.end_auction_gap.is_some()
+        || args.tick_size.is_some()
+        || args.gap_tick_size_percentage.is_some()
+        || instant_sale_price.is_some()
+    {
+        return Err(AuctionError::InvalidSealedBidSettings.into());
+    }
+
+    Ok(())
+}
+
+fn assert_valid_raffle(
+    args: &CreateAuctionArgs,
+    instant_sale_price: Option<u64>,
+    config: &RaffleConfig,
+) -> ProgramResult {
+    // Winners are drawn once ticket sales close, so there must be a fixed number of them and a
+    // time the sales close at.
+    if args.end_auction_at.is_none() {
+        return Err(AuctionError::InvalidRaffleSettings.into());
+    }
+
+    if let WinnerLimit::Unlimited(_) = args.winners {
+        return Err(AuctionError::InvalidRaffleSettings.into());
+    }
+
+    // Every ticket costs the same, none of the English auction bid rules apply.
+    if args.end_auction_gap.is_some()
+        || args.tick_size.is_some()
+        || args.gap_tick_size_percentage.is_some()
+        || instant_sale_price.is_some()
+    {
+        return Err(AuctionError::InvalidRaffleSettings.into());
+    }
+
+    if let PriceFloor::MinimumPrice(floor) = args.price_floor {
+        if config.ticket_price < floor[0] {
+            return Err(AuctionError::InvalidRaffleSettings.into());
+        }
+    }
+
+    Ok(())
+}
+
+fn assert_valid_bid_book(
+    args: &CreateAuctionArgs,
+    bid_state: &BidState,
+    instant_sale_price: Option<u64>,
+) -> ProgramResult {
+    // Only English auctions are ranked in a bid book.
+    if !matches!(bid_state, BidState::BidBook { .. }) {
+        return Err(AuctionError::InvalidBidBookSettings.into());
+    }
+
+    // The book only knows its lowest bid, not the bids either side of a new one.
+    if args.gap_tick_size_percentage.is_some() || instant_sale_price.is_some() {
+        return Err(AuctionError::InvalidBidBookSettings.into());
+    }
+
+    Ok(())
+}
+
+fn assert_valid_payment_mints(
+    args: &CreateAuctionArgs,
+    bid_state: &BidState,
+    payment_mints: &[PaymentMint],
+) -> ProgramResult {
+    if payment_mints.is_empty() || payment_mints.len() > MAX_PAYMENT_MINTS {
+        return Err(AuctionError::InvalidPaymentMints.into());
+    }
+
+    // Sealed bids are deposits rather than bids, and bid-book winners pay the bid they placed out
+    // of their pot, so both are only paid in the token_mint.
+    if matches!(
+        bid_state,
+        BidState::SealedBidAuction { .. } | BidState::BidBook { .. }
+    ) {
+        return Err(AuctionError::InvalidPaymentMints.into());
+    }
+
+    for (index, payment_mint) in payment_mints.iter().enumerate() {
+        if payment_mint.mint == args.token_mint
+            || payment_mints[..index]
+                .iter()
+                .any(|other| other.mint == payment_mint.mint)
+        {
+            return Err(AuctionError::InvalidPaymentMints.into());
+        }
+
+        if let PaymentRate::Fixed {
+            numerator,
+            denominator,
+        } = payment_mint.rate
+        {
+            if numerator == 0 || denominator == 0 {
+                return Err(AuctionError::InvalidPaymentRate.into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn assert_valid_cancellation_rules(
+    args: &CreateAuctionArgs,
+    bid_state: &BidState,
+    rules: &CancellationRules,
+) -> ProgramResult {
+    // Only English auctions let winning bids be cancelled while the auction runs, the penalty
+    // is paid through the account other auctions take their bid commitment or bid book in.
+    if !matches!(bid_state, BidState::EnglishAuction { .. }) {
+        return Err(AuctionError::InvalidCancellationRules.into());
+    }
+
+    if rules.lock_up.is_none() && rules.penalty_basis_points.is_none() {
+        return Err(AuctionError::InvalidCancellationRules.into());
+    }
+
+    // The lock-up counts back from the end time.
+    if let Some(lock_up) = rules.lock_up {
+        if lock_up <= 0 || args.end_auction_at.is_none() {
+            return Err(AuctionError::InvalidCancellationRules.into());
+        }
+    }
+
+    if let Some(basis_points) = rules.penalty_basis_points {
+        if basis_points == 0 || basis_points > 10_000 {
+            return Err(AuctionError::InvalidCancellationRules.into());
+        }
     }
-    .serialize(&mut *accounts.auction.data.borrow_mut())?;
 
     Ok(())
 }
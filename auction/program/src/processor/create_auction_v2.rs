@@ -4,8 +4,10 @@ use crate::{
     errors::AuctionError,
     processor::create_auction::*,
     processor::{
-        AuctionData, AuctionDataExtended, AuctionName, AuctionState, Bid, BidState, PriceFloor,
-        WinnerLimit, BASE_AUCTION_DATA_SIZE, MAX_AUCTION_DATA_EXTENDED_SIZE,
+        AuctionData, AuctionDataExtended, AuctionName, AuctionState, Bid, BidGate, BidState,
+        CancellationRules, DutchAuctionSchedule, PaymentMint, PriceFloor, RaffleConfig,
+        SealedBidConfig, SettlementMode, WinnerLimit, BASE_AUCTION_DATA_SIZE,
+        MAX_AUCTION_DATA_EXTENDED_SIZE,
     },
     utils::{assert_derivation, assert_owned_by, create_or_allocate_account_raw},
     EXTENDED, PREFIX,
@@ -49,6 +51,27 @@ pub struct CreateAuctionArgsV2 {
     pub instant_sale_price: Option<u64>,
     /// Auction name
     pub name: Option<AuctionName>,
+    /// Run a dutch auction on this price schedule instead of an English auction.
+    pub dutch_auction_schedule: Option<DutchAuctionSchedule>,
+    /// Take sealed bids through CommitBid and RevealBid instead of open bids.
+    pub sealed_bid_config: Option<SealedBidConfig>,
+    /// Charge winners the next bid down instead of their own bid.
+    pub settlement_mode: Option<SettlementMode>,
+    /// Start the auction by itself at this time, instead of waiting for StartAuction.
+    pub start_auction_at: Option<UnixTimestamp>,
+    /// Only take bids from holders of this token.
+    pub bid_gate: Option<BidGate>,
+    /// Sell fixed-price tickets and draw the winners through DrawWinners.
+    pub raffle_config: Option<RaffleConfig>,
+    /// Rank bids in this bid book account instead of the auction account, for more winners than
+    /// fit in it. See BidBook.
+    pub bid_book: Option<Pubkey>,
+    /// Also take bids paid in these mints, valued in the token_mint at their rates.
+    pub payment_mints: Option<Vec<PaymentMint>>,
+    /// Also take bids paid in lamports, wrapped SOL auctions only.
+    pub lamport_bids: Option<bool>,
+    /// Lock winning bids in before the end time, or charge a penalty for cancelling them.
+    pub cancellation_rules: Option<CancellationRules>,
 }
 
 struct Accounts<'a, 'b: 'a> {
@@ -93,7 +116,19 @@ pub fn create_auction_v2(
             tick_size: args.tick_size,
             gap_tick_size_percentage: args.gap_tick_size_percentage,
         },
-        args.instant_sale_price,
-        args.name,
+        AuctionExtendedSettings {
+            instant_sale_price: args.instant_sale_price,
+            name: args.name,
+            dutch_auction_schedule: args.dutch_auction_schedule,
+            sealed_bid_config: args.sealed_bid_config,
+            settlement_mode: args.settlement_mode,
+            start_auction_at: args.start_auction_at,
+            bid_gate: args.bid_gate,
+            raffle_config: args.raffle_config,
+            bid_book: args.bid_book,
+            payment_mints: args.payment_mints,
+            lamport_bids: args.lamport_bids,
+            cancellation_rules: args.cancellation_rules,
+        },
     )
 }
@@ -0,0 +1,275 @@
+//! Permissionless crank drawing the winners of a raffle once ticket sales have closed. The first
+//! call only commits to a slot RAFFLE_DRAW_DELAY slots ahead, whose hash nobody knows yet. Once
+//! that slot has passed, the next call fixes the seed the draw is made from, taking the hash of the
+//! committed slot (or of the first slot produced after it, if it was skipped) from the SlotHashes
+//! sysvar, and every call after that only walks the draw order it defines.
+//!
+//! Drawing from the most recent slot hash in the same call would let anyone game the draw: a
+//! program calling DrawWinners through CPI can read the winners it drew and fail the transaction
+//! until it likes them. With the seed committed to a slot in the future, the outcome is settled
+//! before anyone can compute it and retrying changes nothing. Should the committed slot leave
+//! SlotHashes before the seed is fixed, a new slot is committed to the same way.
+//!
+//! Winning tickets are read from the bid ledger, so the pages holding them are passed after the
+//! fixed accounts. RaffleConfig::draw_index gives the ledger index of every draw, letting clients
+//! work out which pages to pass, and draws continue over as many calls as needed to pass them all.
+//! The raffle ends once every winner is drawn.
+//!
+//! The leader producing the committed slot can still influence its hash, so this is fair enough
+//! for prizes not worth a validator's attention but no replacement for a verifiable randomness
+//! source.
+
+use arrayref::array_ref;
+
+use crate::{
+    errors::AuctionError,
+    event::AuctionEvent,
+    processor::{
+        AuctionData, AuctionDataExtended, AuctionState, Bid, BidLedgerPage, BidState, RaffleConfig,
+        BID_LEDGER_PAGE_SIZE,
+    },
+    utils::{assert_derivation, assert_owned_by},
+    EXTENDED, LEDGER, PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Slot,
+        entrypoint::ProgramResult,
+        hash::{hashv, Hash},
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::{clock::Clock, slot_hashes, Sysvar},
+    },
+    std::cmp,
+};
+
+/// Slots between the first DrawWinners call and the slot whose hash the winners are drawn from.
+pub const RAFFLE_DRAW_DELAY: Slot = 32;
+
+/// Most entries the SlotHashes sysvar holds, older slots are dropped from it.
+const MAX_SLOT_HASH_ENTRIES: usize = 512;
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DrawWinnersArgs {
+    /// The resource being auctioned. See AuctionData.
+    pub resource: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    slot_hashes_sysvar: &'a AccountInfo<'b>,
+    bid_ledger_pages: &'a [AccountInfo<'b>],
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        slot_hashes_sysvar: next_account_info(account_iter)?,
+        bid_ledger_pages: account_iter.as_slice(),
+    };
+
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
+
+    if *accounts.slot_hashes_sysvar.key != slot_hashes::id() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(accounts)
+}
+
+pub fn draw_winners<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    args: DrawWinnersArgs,
+) -> ProgramResult {
+    msg!("+ Processing DrawWinners");
+    let accounts = parse_accounts(program_id, accounts)?;
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+
+    let mut auction = AuctionData::from_account_info(accounts.auction)?;
+    let mut auction_extended = AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+
+    let max = match auction.bid_state {
+        BidState::Raffle { max, .. } => max,
+        _ => return Err(AuctionError::InvalidState.into()),
+    };
+
+    // Winners are drawn once, after ticket sales have closed.
+    if auction.state != AuctionState::Started || !auction.ended(clock.unix_timestamp)? {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    let seed_was_drawn = auction_extended.raffle_seed.is_some();
+    if !seed_was_drawn {
+        match auction_extended.raffle_draw_slot {
+            Some(draw_slot) if clock.slot <= draw_slot => {
+                return Err(AuctionError::RaffleDrawSlotNotReached.into());
+            }
+            Some(draw_slot) => {
+                auction_extended.raffle_seed =
+                    slot_hash_since(accounts.slot_hashes_sysvar, draw_slot, accounts.auction.key)?;
+            }
+            None => {}
+        }
+
+        // Commit to a slot whose hash is not known yet, on the first call or once the committed
+        // slot has left SlotHashes.
+        if auction_extended.raffle_seed.is_none() {
+            let draw_slot = clock
+                .slot
+                .checked_add(RAFFLE_DRAW_DELAY)
+                .ok_or(AuctionError::NumericalOverflowError)?;
+            msg!("Raffle will be drawn from slot {}", draw_slot);
+            auction_extended.raffle_draw_slot = Some(draw_slot);
+            auction_extended.save(accounts.auction_extended)?;
+            return Ok(());
+        }
+    }
+    let seed = auction_extended
+        .raffle_seed
+        .ok_or(AuctionError::InvalidState)?;
+
+    let entries = auction_extended.bid_ledger_entries.unwrap_or(0);
+    let winners = cmp::min(max as u64, entries);
+    let mut drawn = auction.num_winners();
+    let already_drawn = drawn;
+
+    while drawn < winners {
+        let index = RaffleConfig::draw_index(&seed, entries, drawn);
+        let page = match find_ledger_page(
+            program_id,
+            accounts.auction.key,
+            accounts.bid_ledger_pages,
+            BidLedgerPage::page_for(index),
+        ) {
+            Some(page) => page,
+            None => break,
+        };
+
+        let entry = BidLedgerPage::get_entry(page, (index % BID_LEDGER_PAGE_SIZE as u64) as usize)?;
+        auction
+            .bid_state
+            .draw_winner(Bid(entry.bidder_pubkey, entry.amount))?;
+        drawn += 1;
+    }
+
+    // The call fixing the seed may only do that, later ones have to make progress.
+    if seed_was_drawn && drawn == already_drawn && drawn < winners {
+        return Err(AuctionError::MissingBidLedgerPage.into());
+    }
+
+    if drawn == winners {
+        msg!("Raffle drawn!");
+        auction.state = auction.state.end()?;
+    }
+
+    auction.save(accounts.auction)?;
+    auction_extended.save(accounts.auction_extended)?;
+
+    if drawn == winners {
+        AuctionEvent::AuctionEnded {
+            auction: *accounts.auction.key,
+            ended_at: auction.ended_at,
+        }
+        .emit()?;
+    }
+
+    Ok(())
+}
+
+/// Mixes the hash of `draw_slot`, or of the first slot produced after it when it was skipped, with
+/// the auction key, so raffles drawn from the same slot differ. None when that hash is not known
+/// any more, because `draw_slot` may have left SlotHashes.
+fn slot_hash_since(
+    slot_hashes: &AccountInfo,
+    draw_slot: Slot,
+    auction: &Pubkey,
+) -> Result<Option<Hash>, ProgramError> {
+    let data = slot_hashes.data.borrow();
+    // A vector length followed by (slot, hash) pairs, most recent first.
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let len = u64::from_le_bytes(*array_ref![data, 0, 8]) as usize;
+    if data.len() < 8 + len * 40 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut found = None;
+    let mut reaches_draw_slot = len < MAX_SLOT_HASH_ENTRIES;
+    for i in 0..len {
+        let entry = 8 + i * 40;
+        let slot = u64::from_le_bytes(*array_ref![data, entry, 8]);
+        if slot < draw_slot {
+            reaches_draw_slot = true;
+            break;
+        }
+        found = Some(&data[entry + 8..entry + 40]);
+        if slot == draw_slot {
+            reaches_draw_slot = true;
+            break;
+        }
+    }
+
+    Ok(match found {
+        Some(hash) if reaches_draw_slot => Some(hashv(&[hash, auction.as_ref()])),
+        _ => None,
+    })
+}
+
+fn find_ledger_page<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    auction: &Pubkey,
+    pages: &'a [AccountInfo<'b>],
+    page: u64,
+) -> Option<&'a AccountInfo<'b>> {
+    let (key, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            auction.as_ref(),
+            LEDGER.as_bytes(),
+            &page.to_le_bytes(),
+        ],
+        program_id,
+    );
+
+    pages
+        .iter()
+        .find(|info| *info.key == key && info.owner == program_id && !info.data_is_empty())
+}
@@ -1,8 +1,11 @@
 use crate::{
     errors::AuctionError,
-    processor::{AuctionData, AuctionState, Bid, BidState, PriceFloor, WinnerLimit},
+    event::AuctionEvent,
+    processor::{
+        AuctionData, AuctionDataExtended, AuctionState, Bid, BidState, PriceFloor, WinnerLimit,
+    },
     utils::{assert_derivation, assert_owned_by, assert_signer, create_or_allocate_account_raw},
-    PREFIX,
+    EXTENDED, PREFIX,
 };
 
 use {
@@ -37,6 +40,7 @@ struct Accounts<'a, 'b: 'a> {
     authority: &'a AccountInfo<'b>,
     auction: &'a AccountInfo<'b>,
     clock_sysvar: &'a AccountInfo<'b>,
+    auction_extended: Option<&'a AccountInfo<'b>>,
 }
 
 fn parse_accounts<'a, 'b: 'a>(
@@ -48,13 +52,21 @@ fn parse_accounts<'a, 'b: 'a>(
         authority: next_account_info(account_iter)?,
         auction: next_account_info(account_iter)?,
         clock_sysvar: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter).ok(),
     };
     assert_owned_by(accounts.auction, program_id)?;
+    if let Some(auction_extended) = accounts.auction_extended {
+        assert_owned_by(auction_extended, program_id)?;
+    }
     assert_signer(accounts.authority)?;
     Ok(accounts)
 }
 
-fn reveal(price_floor: PriceFloor, revealer: Option<Revealer>) -> Result<PriceFloor, ProgramError> {
+fn reveal(
+    price_floor: PriceFloor,
+    revealer: Option<Revealer>,
+    bid_state: &BidState,
+) -> Result<PriceFloor, ProgramError> {
     // If the price floor was blinded, we update it.
     if let PriceFloor::BlindedPrice(blinded) = price_floor {
         // If the hash matches, update the price to the actual minimum.
@@ -63,7 +75,16 @@ fn reveal(price_floor: PriceFloor, revealer: Option<Revealer>) -> Result<PriceFl
             if reveal_hash != blinded {
                 return Err(AuctionError::InvalidReveal.into());
             }
-            Ok(PriceFloor::MinimumPrice([reveal.0, 0, 0, 0]))
+            // Bids were never checked against the hidden price, so where bids are ranked it is
+            // settled as a reserve.
+            if matches!(
+                bid_state,
+                BidState::EnglishAuction { .. } | BidState::SealedBidAuction { .. }
+            ) {
+                Ok(PriceFloor::ReservePrice([reveal.0, 0, 0, 0]))
+            } else {
+                Ok(PriceFloor::MinimumPrice([reveal.0, 0, 0, 0]))
+            }
         } else {
             return Err(AuctionError::MustReveal.into());
         }
@@ -100,18 +121,81 @@ pub fn end_auction<'a, 'b: 'a>(
         return Err(AuctionError::InvalidAuthority.into());
     }
 
-    // As long as it hasn't already ended.
-    if auction.ended_at.is_some() {
+    // As long as it hasn't already ended. Dutch auctions and raffles always carry an end time, but
+    // the authority may still close them out early, for example when lots are left unsold.
+    let is_dutch = matches!(auction.bid_state, BidState::DutchAuction { .. });
+    let is_sealed = matches!(auction.bid_state, BidState::SealedBidAuction { .. });
+    let is_raffle = matches!(auction.bid_state, BidState::Raffle { .. });
+    if auction.ended_at.is_some() && !is_dutch && !is_sealed && !is_raffle {
         return Err(AuctionError::AuctionTransitionInvalid.into());
     }
 
-    AuctionData {
-        ended_at: Some(clock.unix_timestamp),
-        state: auction.state.end()?,
-        price_floor: reveal(auction.price_floor, args.reveal)?,
+    // Sealed-bid auctions end once every bidder has had the chance to reveal. Their end time is
+    // kept as is, as the reveal window hangs off it.
+    let ended_at = if is_sealed && auction.ended_at.is_some() {
+        let auction_extended = accounts
+            .auction_extended
+            .ok_or(AuctionError::InvalidAuctionAccount)?;
+        assert_derivation(
+            program_id,
+            auction_extended,
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                &args.resource.as_ref(),
+                EXTENDED.as_bytes(),
+            ],
+        )?;
+        let config = AuctionDataExtended::from_account_info(auction_extended)?
+            .sealed_bid_config
+            .ok_or(AuctionError::InvalidAuctionAccount)?;
+
+        if clock.unix_timestamp <= auction.reveal_deadline(&config)? {
+            return Err(AuctionError::RevealWindowOpen.into());
+        }
+        auction.ended_at
+    } else if is_raffle {
+        // Ticket sales can be closed early, but never reopened. Auctions only count as ended once
+        // their end time has passed, so the close is dated a second back to take effect at once.
+        let closed_at = clock.unix_timestamp - 1;
+        Some(auction.ended_at.map_or(closed_at, |end| end.min(closed_at)))
+    } else {
+        Some(clock.unix_timestamp)
+    };
+
+    // A running raffle only stops selling tickets here, it ends once DrawWinners has picked the
+    // winners.
+    let state = if is_raffle && auction.state == AuctionState::Started {
+        AuctionState::Started
+    } else {
+        auction.state.clone().end()?
+    };
+
+    let mut auction = AuctionData {
+        ended_at,
+        state,
+        price_floor: reveal(auction.price_floor, args.reveal, &auction.bid_state)?,
         ..auction
+    };
+
+    // Bids below the reserve lose, and without any winners left every bid is refunded.
+    auction.settle_reserve();
+    auction.save(accounts.auction)?;
+
+    AuctionEvent::AuctionEnded {
+        auction: *accounts.auction.key,
+        ended_at,
+    }
+    .emit()?;
+
+    if let PriceFloor::ReserveNotMet(reserve) = auction.price_floor {
+        msg!("Reserve price was not met");
+        AuctionEvent::ReserveNotMet {
+            auction: *accounts.auction.key,
+            reserve: reserve[0],
+        }
+        .emit()?;
     }
-    .serialize(&mut *accounts.auction.data.borrow_mut())?;
 
     Ok(())
 }
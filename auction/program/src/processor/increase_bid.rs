@@ -0,0 +1,285 @@
+//! Raises an existing bid on a running English auction in place. Only the difference is moved
+//! into the bidder pot, and the bid is re-ranked under the same tick size and gap rules as a new
+//! bid, so the bidder never has to cancel and lose their place to bid higher.
+
+use crate::{
+    errors::AuctionError,
+    event::AuctionEvent,
+    processor::{
+        defend_proxy_bid, AuctionData, AuctionDataExtended, AuctionState, Bid, BidState,
+        BidderMetadata, BidderPot,
+    },
+    utils::{
+        assert_derivation, assert_initialized, assert_owned_by, assert_signer,
+        assert_token_program_matches_package, spl_token_transfer, TokenTransferParams,
+    },
+    EXTENDED, PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        sysvar::{clock::Clock, Sysvar},
+    },
+    spl_token::state::Account,
+};
+
+/// Arguments for the IncreaseBid instruction discriminant .
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct IncreaseBidArgs {
+    /// New size of the bid, must be larger than the bid it replaces.
+    pub amount: u64,
+    /// Resource being bid on.
+    pub resource: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    bidder_meta: &'a AccountInfo<'b>,
+    bidder_pot: &'a AccountInfo<'b>,
+    bidder_pot_token: &'a AccountInfo<'b>,
+    bidder: &'a AccountInfo<'b>,
+    bidder_token: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    token_program: &'a AccountInfo<'b>,
+    transfer_authority: &'a AccountInfo<'b>,
+    proxy_bid: Option<&'a AccountInfo<'b>>,
+    proxy_bidder_meta: Option<&'a AccountInfo<'b>>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        bidder: next_account_info(account_iter)?,
+        bidder_token: next_account_info(account_iter)?,
+        bidder_pot: next_account_info(account_iter)?,
+        bidder_pot_token: next_account_info(account_iter)?,
+        bidder_meta: next_account_info(account_iter)?,
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        transfer_authority: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        token_program: next_account_info(account_iter)?,
+        proxy_bid: next_account_info(account_iter).ok(),
+        proxy_bidder_meta: next_account_info(account_iter).ok(),
+    };
+
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
+    assert_owned_by(accounts.bidder_meta, program_id)?;
+    assert_owned_by(accounts.bidder_pot, program_id)?;
+    assert_owned_by(accounts.bidder_pot_token, &spl_token::id())?;
+    assert_owned_by(accounts.bidder_token, &spl_token::id())?;
+    assert_signer(accounts.bidder)?;
+    assert_signer(accounts.transfer_authority)?;
+    assert_token_program_matches_package(accounts.token_program)?;
+
+    Ok(accounts)
+}
+
+pub fn increase_bid<'r, 'b: 'r>(
+    program_id: &Pubkey,
+    accounts: &'r [AccountInfo<'b>],
+    args: IncreaseBidArgs,
+) -> ProgramResult {
+    msg!("+ Processing IncreaseBid");
+    let accounts = parse_accounts(program_id, accounts)?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+    let mut auction = AuctionData::from_account_info(accounts.auction)?;
+
+    // Only English auction bids are ranked against each other and can be raised.
+    match auction.bid_state {
+        BidState::EnglishAuction { .. } => (),
+        BidState::SealedBidAuction { .. } => return Err(AuctionError::BidMustBeSealed.into()),
+        _ => return Err(AuctionError::InvalidState.into()),
+    }
+
+    // Load the clock, used for various auction timing.
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    // Verify auction has not ended.
+    if auction.ended(clock.unix_timestamp)? {
+        auction.state = auction.state.end()?;
+        auction.save(accounts.auction)?;
+        msg!("Auction ended!");
+        AuctionEvent::AuctionEnded {
+            auction: *accounts.auction.key,
+            ended_at: auction.ended_at,
+        }
+        .emit()?;
+        return Ok(());
+    }
+
+    // Can't bid on an auction that isn't running.
+    if auction.state != AuctionState::Started {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    // There must be a live bid to raise.
+    assert_derivation(
+        program_id,
+        accounts.bidder_meta,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            "metadata".as_bytes(),
+        ],
+    )?;
+    let metadata = BidderMetadata::from_account_info(accounts.bidder_meta)?;
+    if metadata.cancelled {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    // Confirm we're looking at the real SPL account for this bidder.
+    let pot_bump = assert_derivation(
+        program_id,
+        accounts.bidder_pot,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+        ],
+    )?;
+    let bidder_pot = BidderPot::from_account_info(accounts.bidder_pot)?;
+    if bidder_pot.bidder_pot != *accounts.bidder_pot_token.key {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    let actual_account: Account = assert_initialized(accounts.bidder_pot_token)?;
+    if actual_account.owner != *accounts.auction.key {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    // The pot is topped up to the new bid as is, so only bids in the token_mint are increased.
+    if actual_account.mint != auction.token_mint {
+        return Err(AuctionError::IncorrectMint.into());
+    }
+
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+    let mut auction_extended = AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+
+    let mut bid_price = args.amount;
+
+    if let Some(instant_sale_price) = auction_extended.instant_sale_price {
+        if args.amount > instant_sale_price {
+            msg!("Received amount is more than instant_sale_price so it was reduced to instant_sale_price - {:?}", instant_sale_price);
+            bid_price = instant_sale_price;
+        }
+    }
+
+    if bid_price <= metadata.last_bid {
+        msg!(
+            "Bid of {:?} does not raise the current bid of {:?}",
+            bid_price,
+            metadata.last_bid
+        );
+        return Err(AuctionError::BidTooSmall.into());
+    }
+
+    // Only the difference is moved, the pot already holds the current bid and any proxy bid
+    // maximum on top of it.
+    let top_up = bid_price.saturating_sub(actual_account.amount);
+    let account: Account = Account::unpack_from_slice(&accounts.bidder_token.data.borrow())?;
+    if account.amount < top_up {
+        msg!(
+            "Amount is too small: {:?}, compared to account amount of {:?}",
+            top_up,
+            account.amount
+        );
+        return Err(AuctionError::BalanceTooLow.into());
+    }
+
+    spl_token_transfer(TokenTransferParams {
+        source: accounts.bidder_token.clone(),
+        destination: accounts.bidder_pot_token.clone(),
+        authority: accounts.transfer_authority.clone(),
+        authority_signer_seeds: &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            &[pot_bump],
+        ],
+        token_program: accounts.token_program.clone(),
+        amount: top_up,
+    })?;
+
+    // Serialize new Auction State
+    auction.last_bid = Some(clock.unix_timestamp);
+    auction.increase_bid(
+        Bid(*accounts.bidder.key, bid_price),
+        auction_extended.tick_size,
+        auction_extended.gap_tick_size_percentage,
+        clock.unix_timestamp,
+        auction_extended.instant_sale_price,
+    )?;
+
+    // Give the current proxy bidder the chance to counter this bid.
+    if let Some(proxy_bidder) = auction_extended
+        .proxy_bidder
+        .filter(|key| key != accounts.bidder.key)
+    {
+        if !defend_proxy_bid(
+            program_id,
+            accounts.auction.key,
+            &mut auction,
+            &auction_extended,
+            &proxy_bidder,
+            accounts.proxy_bid,
+            accounts.proxy_bidder_meta,
+            clock.unix_timestamp,
+        )? {
+            auction_extended.proxy_bidder = None;
+            auction_extended.save(accounts.auction_extended)?;
+        }
+    }
+    auction.save(accounts.auction)?;
+
+    BidderMetadata {
+        last_bid: bid_price,
+        last_bid_timestamp: clock.unix_timestamp,
+        ..metadata
+    }
+    .serialize(&mut *accounts.bidder_meta.data.borrow_mut())?;
+
+    AuctionEvent::BidIncreased {
+        auction: *accounts.auction.key,
+        bidder: *accounts.bidder.key,
+        amount: bid_price,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Moves an auction from the Borsh layout CreateAuction writes onto AuctionDataV2. The auction
+//! reads the same afterwards, but clients deserializing it through Borsh no longer can, so only its
+//! authority decides when that happens.
+//!
+//! The version 2 header is smaller than the fields ahead of the bids in the Borsh layout, so every
+//! auction fits in the account it already has.
+
+use crate::{
+    errors::AuctionError,
+    processor::{AuctionData, AuctionDataV2},
+    utils::{assert_derivation, assert_owned_by, assert_signer},
+    PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MigrateAuctionArgs {
+    /// The resource being auctioned. See AuctionData.
+    pub resource: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    authority: &'a AccountInfo<'b>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        auction: next_account_info(account_iter)?,
+        authority: next_account_info(account_iter)?,
+    };
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_signer(accounts.authority)?;
+    Ok(accounts)
+}
+
+pub fn migrate_auction<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    args: MigrateAuctionArgs,
+) -> ProgramResult {
+    msg!("+ Processing MigrateAuction");
+    let accounts = parse_accounts(program_id, accounts)?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+
+    if AuctionDataV2::is_v2(&accounts.auction.data.borrow()) {
+        return Err(AuctionError::AuctionAlreadyMigrated.into());
+    }
+
+    let auction = AuctionData::from_account_info(accounts.auction)?;
+
+    // Check authority is correct.
+    if auction.authority != *accounts.authority.key {
+        return Err(AuctionError::InvalidAuthority.into());
+    }
+
+    // Clear the Borsh layout first, so nothing of it is left behind the bids.
+    let mut data = accounts.auction.data.borrow_mut();
+    for byte in data.iter_mut() {
+        *byte = 0;
+    }
+    AuctionDataV2::pack(&auction, &mut data)?;
+
+    Ok(())
+}
@@ -9,20 +9,29 @@
 //!
 //! A few solutions come to mind: don't allow cancelling bids, and simply prune all bids that
 //! are not winning bids from the state.
+//!
+//! Auctions with payment mints also take bids paid in those, the bid amount is always given in
+//! the auction's token_mint.
+//!
+//! Auctions in wrapped SOL created with lamport_bids also take bids paid in lamports, escrowed in
+//! the bidder pot itself so bidders need no wrapped SOL account.
 
 use borsh::try_to_vec_with_schema;
 
 use crate::{
     errors::AuctionError,
+    event::AuctionEvent,
     processor::{
-        AuctionData, AuctionDataExtended, AuctionState, Bid, BidderMetadata, BidderPot, PriceFloor,
+        defend_proxy_bid, start_if_scheduled, AuctionData, AuctionDataExtended, AuctionState, Bid,
+        BidBook, BidLedgerEntry, BidLedgerPage, BidState, BidderMetadata, BidderPot, PriceFloor,
+        BIDDER_POT_LEN, BID_LEDGER_PAGE_LEN,
     },
     utils::{
-        assert_derivation, assert_initialized, assert_owned_by, assert_signer,
-        assert_token_program_matches_package, create_or_allocate_account_raw, spl_token_transfer,
-        TokenTransferParams,
+        assert_derivation, assert_initialized, assert_owned_by, assert_passes_bid_gate,
+        assert_pot_balance, assert_signer, assert_token_program_matches_package,
+        create_or_allocate_account_raw, is_lamport_pot, spl_token_transfer, TokenTransferParams,
     },
-    EXTENDED, PREFIX,
+    EXTENDED, LEDGER, PREFIX,
 };
 
 use super::BIDDER_METADATA_LEN;
@@ -31,6 +40,7 @@ use {
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         account_info::{next_account_info, AccountInfo},
+        clock::UnixTimestamp,
         entrypoint::ProgramResult,
         msg,
         program::{invoke, invoke_signed},
@@ -44,7 +54,6 @@ use {
         sysvar::{clock::Clock, Sysvar},
     },
     spl_token::state::Account,
-    std::mem,
 };
 
 /// Arguments for the PlaceBid instruction discriminant .
@@ -72,6 +81,11 @@ struct Accounts<'a, 'b: 'a> {
     system: &'a AccountInfo<'b>,
     token_program: &'a AccountInfo<'b>,
     transfer_authority: &'a AccountInfo<'b>,
+    proxy_bid: Option<&'a AccountInfo<'b>>,
+    proxy_bidder_meta: Option<&'a AccountInfo<'b>>,
+    bid_ledger_page: Option<&'a AccountInfo<'b>>,
+    bid_book: Option<&'a AccountInfo<'b>>,
+    trailing_accounts: &'a [AccountInfo<'b>],
 }
 
 fn parse_accounts<'a, 'b: 'a>(
@@ -94,11 +108,26 @@ fn parse_accounts<'a, 'b: 'a>(
         rent: next_account_info(account_iter)?,
         system: next_account_info(account_iter)?,
         token_program: next_account_info(account_iter)?,
+        trailing_accounts: account_iter.as_slice(),
+        proxy_bid: next_account_info(account_iter).ok(),
+        proxy_bidder_meta: next_account_info(account_iter).ok(),
+        bid_ledger_page: None,
+        bid_book: None,
     };
 
     assert_owned_by(accounts.auction, program_id)?;
     assert_owned_by(accounts.auction_extended, program_id)?;
-    assert_owned_by(accounts.bidder_token, &spl_token::id())?;
+
+    // Lamport bids pay from the bidder's wallet into the bidder pot, which stands in for both
+    // token accounts.
+    if is_lamport_pot(accounts.bidder_pot, accounts.bidder_pot_token) {
+        if accounts.bidder_token.key != accounts.bidder.key {
+            return Err(AuctionError::InvalidBidAccount.into());
+        }
+    } else {
+        assert_owned_by(accounts.bidder_token, &spl_token::id())?;
+        assert_owned_by(accounts.bidder_pot_token, &spl_token::id())?;
+    }
 
     if !accounts.bidder_pot.data_is_empty() {
         assert_owned_by(accounts.bidder_pot, program_id)?;
@@ -108,7 +137,6 @@ fn parse_accounts<'a, 'b: 'a>(
     }
 
     assert_owned_by(accounts.mint, &spl_token::id())?;
-    assert_owned_by(accounts.bidder_pot_token, &spl_token::id())?;
     assert_signer(accounts.bidder)?;
     assert_signer(accounts.payer)?;
     assert_signer(accounts.transfer_authority)?;
@@ -121,6 +149,25 @@ fn parse_accounts<'a, 'b: 'a>(
     Ok(accounts)
 }
 
+impl<'a, 'b: 'a> Accounts<'a, 'b> {
+    /// Open-edition auctions and raffles have no proxy bids and pass their bid ledger page in the
+    /// place of the proxy bid, bid-book auctions their bid book. See PlaceBid.
+    fn for_bid_state(mut self, bid_state: &BidState) -> Self {
+        match bid_state {
+            BidState::OpenEdition { .. } | BidState::Raffle { .. } => {
+                self.bid_ledger_page = self.proxy_bid.take();
+                self.proxy_bidder_meta = None;
+            }
+            BidState::BidBook { .. } => {
+                self.bid_book = self.proxy_bid.take();
+                self.proxy_bidder_meta = None;
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
 #[allow(clippy::absurd_extreme_comparisons)]
 pub fn place_bid<'r, 'b: 'r>(
     program_id: &Pubkey,
@@ -132,15 +179,48 @@ pub fn place_bid<'r, 'b: 'r>(
 
     // Load the auction and verify this bid is valid.
     let mut auction = AuctionData::from_account_info(accounts.auction)?;
+    let accounts = accounts.for_bid_state(&auction.bid_state);
+
+    // Sealed bids go through CommitBid and RevealBid instead.
+    if let BidState::SealedBidAuction { .. } = auction.bid_state {
+        return Err(AuctionError::BidMustBeSealed.into());
+    }
 
     // Load the clock, used for various auction timing.
     let clock = Clock::from_account_info(accounts.clock_sysvar)?;
 
+    // Scheduled auctions start with the first bid placed after their start time.
+    if start_if_scheduled(
+        program_id,
+        &args.resource,
+        &mut auction,
+        accounts.auction_extended,
+        clock.unix_timestamp,
+    )? {
+        msg!("Auction started!");
+        AuctionEvent::AuctionStarted {
+            auction: *accounts.auction.key,
+            ended_at: auction.ended_at,
+        }
+        .emit()?;
+    }
+
+    let is_raffle = matches!(auction.bid_state, BidState::Raffle { .. });
+
     // Verify auction has not ended.
     if auction.ended(clock.unix_timestamp)? {
+        // Raffles stay running until their winners are drawn, only ticket sales close.
+        if is_raffle {
+            return Err(AuctionError::InvalidState.into());
+        }
         auction.state = auction.state.end()?;
-        auction.serialize(&mut *accounts.auction.data.borrow_mut())?;
+        auction.save(accounts.auction)?;
         msg!("Auction ended!");
+        AuctionEvent::AuctionEnded {
+            auction: *accounts.auction.key,
+            ended_at: auction.ended_at,
+        }
+        .emit()?;
         return Ok(());
     }
     // Derive Metadata key and load it.
@@ -198,18 +278,41 @@ pub fn place_bid<'r, 'b: 'r>(
         ],
     )?;
 
-    // The account within the pot must be owned by us.
-    let actual_account: Account = assert_initialized(accounts.bidder_pot_token)?;
-    if actual_account.owner != *accounts.auction.key {
-        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
-    }
+    // Mint the bid is paid in and what the pot already holds.
+    let is_lamport_bid = is_lamport_pot(accounts.bidder_pot, accounts.bidder_pot_token);
+    let (pot_mint, pot_amount) = if is_lamport_bid {
+        // Lamports stand in for wrapped SOL, so only auctions in it take them.
+        if auction.token_mint != spl_token::native_mint::id() {
+            return Err(AuctionError::IncorrectMint.into());
+        }
+        let pot_amount = assert_pot_balance(
+            accounts.auction.key,
+            accounts.bidder_pot,
+            accounts.bidder_pot,
+        )?;
+        (auction.token_mint, pot_amount)
+    } else {
+        // The account within the pot must be owned by us.
+        let actual_account: Account = assert_initialized(accounts.bidder_pot_token)?;
+        if actual_account.owner != *accounts.auction.key {
+            return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+        }
 
-    if actual_account.delegate != COption::None {
-        return Err(AuctionError::DelegateShouldBeNone.into());
-    }
+        if actual_account.delegate != COption::None {
+            return Err(AuctionError::DelegateShouldBeNone.into());
+        }
+
+        if actual_account.close_authority != COption::None {
+            return Err(AuctionError::CloseAuthorityShouldBeNone.into());
+        }
 
-    if actual_account.close_authority != COption::None {
-        return Err(AuctionError::CloseAuthorityShouldBeNone.into());
+        (actual_account.mint, actual_account.amount)
+    };
+
+    // The bid is paid in the mint of the pot, checked against the mints the auction takes once
+    // the auction extended account is loaded.
+    if pot_mint != *accounts.mint.key {
+        return Err(AuctionError::IncorrectMint.into());
     }
 
     // Derive and load Auction.
@@ -244,7 +347,7 @@ pub fn place_bid<'r, 'b: 'r>(
             accounts.rent,
             accounts.system,
             accounts.payer,
-            mem::size_of::<BidderPot>(),
+            BIDDER_POT_LEN,
             bump_authority_seeds,
         )?;
 
@@ -253,7 +356,9 @@ pub fn place_bid<'r, 'b: 'r>(
         pot.bidder_pot = *accounts.bidder_pot_token.key;
         pot.bidder_act = *accounts.bidder.key;
         pot.auction_act = *accounts.auction.key;
-        pot.serialize(&mut *accounts.bidder_pot.data.borrow_mut())?;
+        pot.payer = *accounts.payer.key;
+        pot.mint = Some(pot_mint);
+        pot.save(accounts.bidder_pot)?;
     } else {
         // Already exists, verify that the pot contains the specified SPL address.
         let bidder_pot = BidderPot::from_account_info(accounts.bidder_pot)?;
@@ -275,11 +380,22 @@ pub fn place_bid<'r, 'b: 'r>(
     )?;
     let mut auction_extended: AuctionDataExtended =
         AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+
+    if is_lamport_bid && auction_extended.lamport_bids != Some(true) {
+        return Err(AuctionError::LamportBidsNotEnabled.into());
+    }
+
+    // Gated auctions take the bidder's proof of holding as the last accounts.
+    if let Some(gate) = &auction_extended.bid_gate {
+        assert_passes_bid_gate(gate, accounts.bidder.key, accounts.trailing_accounts)?;
+    }
+
     auction_extended.total_uncancelled_bids = auction_extended
         .total_uncancelled_bids
         .checked_add(1)
         .ok_or(AuctionError::NumericalOverflowError)?;
-    auction_extended.serialize(&mut *accounts.auction_extended.data.borrow_mut())?;
+    auction_extended.open_bidder_pot()?;
+    auction_extended.save(accounts.auction_extended)?;
 
     let mut bid_price = args.amount;
 
@@ -290,26 +406,74 @@ pub fn place_bid<'r, 'b: 'r>(
         }
     }
 
-    // Confirm payers SPL token balance is enough to pay the bid.
-    let account: Account = Account::unpack_from_slice(&accounts.bidder_token.data.borrow())?;
-    if account.amount.saturating_sub(bid_price) < 0 {
-        msg!(
-            "Amount is too small: {:?}, compared to account amount of {:?}",
-            bid_price,
-            account.amount
-        );
-        return Err(AuctionError::BalanceTooLow.into());
+    // Dutch auctions sell at the current asking price, anything above it is not taken.
+    if let Some(schedule) = &auction_extended.dutch_auction_schedule {
+        let asking_price = auction.dutch_auction_price(schedule, clock.unix_timestamp)?;
+        if args.amount < asking_price {
+            msg!(
+                "Bid of {:?} is below the current asking price of {:?}",
+                args.amount,
+                asking_price
+            );
+            return Err(AuctionError::BidTooSmall.into());
+        }
+        bid_price = asking_price;
     }
 
-    // Transfer amount of SPL token to bid account.
-    spl_token_transfer(TokenTransferParams {
-        source: accounts.bidder_token.clone(),
-        destination: accounts.bidder_pot_token.clone(),
-        authority: accounts.transfer_authority.clone(),
-        authority_signer_seeds: bump_authority_seeds,
-        token_program: accounts.token_program.clone(),
-        amount: bid_price,
-    })?;
+    // Every raffle ticket costs the same, anything above the ticket price is not taken.
+    if let Some(config) = &auction_extended.raffle_config {
+        if args.amount < config.ticket_price {
+            return Err(AuctionError::BidTooSmall.into());
+        }
+        bid_price = config.ticket_price;
+    }
+
+    // Bids are ranked in the token_mint, bids paid in another mint the auction takes cost what
+    // they are worth at its current rate.
+    let payment = if pot_mint == auction.token_mint {
+        bid_price
+    } else {
+        auction_extended
+            .payment_mint(&pot_mint)?
+            .payment_for(bid_price, accounts.trailing_accounts)?
+    };
+
+    if is_lamport_bid {
+        // Lamport bids are paid straight from the bidder's wallet into their pot.
+        if accounts.bidder.lamports() < payment {
+            return Err(AuctionError::BalanceTooLow.into());
+        }
+
+        invoke(
+            &system_instruction::transfer(accounts.bidder.key, accounts.bidder_pot.key, payment),
+            &[
+                accounts.bidder.clone(),
+                accounts.bidder_pot.clone(),
+                accounts.system.clone(),
+            ],
+        )?;
+    } else {
+        // Confirm payers SPL token balance is enough to pay the bid.
+        let account: Account = Account::unpack_from_slice(&accounts.bidder_token.data.borrow())?;
+        if account.amount.saturating_sub(payment) < 0 {
+            msg!(
+                "Amount is too small: {:?}, compared to account amount of {:?}",
+                payment,
+                account.amount
+            );
+            return Err(AuctionError::BalanceTooLow.into());
+        }
+
+        // Transfer amount of SPL token to bid account.
+        spl_token_transfer(TokenTransferParams {
+            source: accounts.bidder_token.clone(),
+            destination: accounts.bidder_pot_token.clone(),
+            authority: accounts.transfer_authority.clone(),
+            authority_signer_seeds: bump_authority_seeds,
+            token_program: accounts.token_program.clone(),
+            amount: payment,
+        })?;
+    }
 
     // Serialize new Auction State
     auction.last_bid = Some(clock.unix_timestamp);
@@ -320,7 +484,53 @@ pub fn place_bid<'r, 'b: 'r>(
         clock.unix_timestamp,
         auction_extended.instant_sale_price,
     )?;
-    auction.serialize(&mut *accounts.auction.data.borrow_mut())?;
+
+    // Bid-book auctions rank the bid in their bid book instead.
+    if let BidState::BidBook { .. } = auction.bid_state {
+        let bid_book = accounts.bid_book.ok_or(AuctionError::InvalidBidBook)?;
+        if auction_extended.bid_book != Some(*bid_book.key) {
+            return Err(AuctionError::InvalidBidBook.into());
+        }
+        // Each bidder holds a single bid in the book, an outbid one is cancelled before bidding
+        // again, as the bidder metadata already requires.
+        BidBook::insert(bid_book, Bid(*accounts.bidder.key, bid_price))?;
+    }
+
+    // Give the current proxy bidder the chance to counter this bid.
+    if let Some(proxy_bidder) = auction_extended
+        .proxy_bidder
+        .filter(|key| key != accounts.bidder.key)
+    {
+        if !defend_proxy_bid(
+            program_id,
+            accounts.auction.key,
+            &mut auction,
+            &auction_extended,
+            &proxy_bidder,
+            accounts.proxy_bid,
+            accounts.proxy_bidder_meta,
+            clock.unix_timestamp,
+        )? {
+            auction_extended.proxy_bidder = None;
+            auction_extended.save(accounts.auction_extended)?;
+        }
+    }
+    auction.save(accounts.auction)?;
+
+    // Open editions and raffles keep no bids in the auction, so the bid is recorded in the ledger
+    // instead. Raffle winners are drawn from it, while open editions only record the bids of
+    // clients passing a page, those from before the ledger bid without one.
+    let is_open_edition = matches!(auction.bid_state, BidState::OpenEdition { .. });
+    if is_raffle || (is_open_edition && accounts.bid_ledger_page.is_some()) {
+        record_bid_in_ledger(
+            program_id,
+            &accounts,
+            &mut auction_extended,
+            bid_price,
+            clock.unix_timestamp,
+        )?;
+        auction_extended.save(accounts.auction_extended)?;
+    }
 
     // Update latest metadata with results from the bid.
     BidderMetadata {
@@ -332,5 +542,86 @@ pub fn place_bid<'r, 'b: 'r>(
     }
     .serialize(&mut *accounts.bidder_meta.data.borrow_mut())?;
 
+    AuctionEvent::BidPlaced {
+        auction: *accounts.auction.key,
+        bidder: *accounts.bidder.key,
+        amount: bid_price,
+        mint: pot_mint,
+        payment,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// Appends the bid to the last page of the auction's bid ledger, creating the page when the
+/// previous one is full.
+fn record_bid_in_ledger(
+    program_id: &Pubkey,
+    accounts: &Accounts,
+    auction_extended: &mut AuctionDataExtended,
+    amount: u64,
+    now: UnixTimestamp,
+) -> ProgramResult {
+    let bid_ledger_page = accounts
+        .bid_ledger_page
+        .ok_or(AuctionError::MissingBidLedgerPage)?;
+
+    let index = auction_extended.bid_ledger_entries.unwrap_or(0);
+    let page = BidLedgerPage::page_for(index);
+    let page_bytes = page.to_le_bytes();
+    let page_bump = assert_derivation(
+        program_id,
+        bid_ledger_page,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            LEDGER.as_bytes(),
+            &page_bytes,
+        ],
+    )?;
+
+    let mut ledger_page = if bid_ledger_page.data_is_empty() {
+        create_or_allocate_account_raw(
+            *program_id,
+            bid_ledger_page,
+            accounts.rent,
+            accounts.system,
+            accounts.payer,
+            BID_LEDGER_PAGE_LEN,
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                accounts.auction.key.as_ref(),
+                LEDGER.as_bytes(),
+                &page_bytes,
+                &[page_bump],
+            ],
+        )?;
+
+        BidLedgerPage {
+            auction_pubkey: *accounts.auction.key,
+            page,
+            entries: vec![],
+        }
+    } else {
+        assert_owned_by(bid_ledger_page, program_id)?;
+        BidLedgerPage::from_account_info(bid_ledger_page)?
+    };
+
+    ledger_page.entries.push(BidLedgerEntry {
+        bidder_pubkey: *accounts.bidder.key,
+        amount,
+        timestamp: now,
+    });
+    ledger_page.serialize(&mut *bid_ledger_page.data.borrow_mut())?;
+
+    auction_extended.bid_ledger_entries = Some(
+        index
+            .checked_add(1)
+            .ok_or(AuctionError::NumericalOverflowError)?,
+    );
+
     Ok(())
 }
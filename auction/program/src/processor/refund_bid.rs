@@ -0,0 +1,294 @@
+//! Permissionless crank returning losing bids once an auction is over. Anyone can pay for it, the
+//! pot of every bidder passed in is sent to that bidder's associated token account, so funds do
+//! not stay locked when bidders never come back to cancel.
+//!
+//! Bidders are passed as consecutive groups of accounts after the fixed ones, so a single
+//! transaction can refund several of them. The same rules as CancelBid apply to every bidder:
+//! winners are never refunded, and sealed bids stay locked through the reveal window. Bid-book
+//! auctions take their bid book in place of the bid commitment of every bidder.
+
+use crate::{
+    errors::AuctionError,
+    event::AuctionEvent,
+    processor::{
+        assert_bid_book_refundable, assert_sealed_bid_refundable, AuctionData, AuctionDataExtended,
+        AuctionState, Bid, BidderMetadata, BidderPot,
+    },
+    utils::{
+        assert_derivation, assert_is_ata, assert_owned_by, assert_pot_balance,
+        assert_token_program_matches_package, is_lamport_pot, pot_transfer, TokenTransferParams,
+    },
+    EXTENDED, PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::UnixTimestamp,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::{clock::Clock, Sysvar},
+    },
+};
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct RefundBidArgs {
+    /// The resource being auctioned. See AuctionData.
+    pub resource: Pubkey,
+}
+
+/// Number of accounts passed for every bidder refunded.
+const REFUND_BID_ACCOUNTS_PER_BIDDER: usize = 6;
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    token_program: &'a AccountInfo<'b>,
+    bidders: &'a [AccountInfo<'b>],
+}
+
+struct BidderAccounts<'a, 'b: 'a> {
+    bidder: &'a AccountInfo<'b>,
+    bidder_token: &'a AccountInfo<'b>,
+    bidder_pot: &'a AccountInfo<'b>,
+    bidder_pot_token: &'a AccountInfo<'b>,
+    bidder_meta: &'a AccountInfo<'b>,
+    bid_commitment: &'a AccountInfo<'b>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let fixed = Accounts {
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        token_program: next_account_info(account_iter)?,
+        bidders: account_iter.as_slice(),
+    };
+
+    assert_owned_by(fixed.auction, program_id)?;
+    assert_owned_by(fixed.auction_extended, program_id)?;
+    assert_token_program_matches_package(fixed.token_program)?;
+
+    if fixed.bidders.is_empty() || fixed.bidders.len() % REFUND_BID_ACCOUNTS_PER_BIDDER != 0 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    Ok(fixed)
+}
+
+fn parse_bidder_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<BidderAccounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = BidderAccounts {
+        bidder: next_account_info(account_iter)?,
+        bidder_token: next_account_info(account_iter)?,
+        bidder_pot: next_account_info(account_iter)?,
+        bidder_pot_token: next_account_info(account_iter)?,
+        bidder_meta: next_account_info(account_iter)?,
+        bid_commitment: next_account_info(account_iter)?,
+    };
+
+    assert_owned_by(accounts.bidder_pot, program_id)?;
+    assert_owned_by(accounts.bidder_meta, program_id)?;
+
+    Ok(accounts)
+}
+
+pub fn refund_bid<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    args: RefundBidArgs,
+) -> ProgramResult {
+    msg!("+ Processing RefundBid");
+    let accounts = parse_accounts(program_id, accounts)?;
+
+    let auction_bump = assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+
+    let auction_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        &[auction_bump],
+    ];
+
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+
+    let auction = AuctionData::from_account_info(accounts.auction)?;
+    let mut auction_extended = AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    // Until the auction is over, losing bids can still become winning ones.
+    if !auction.is_over(clock.unix_timestamp)? {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    for bidder_accounts in accounts.bidders.chunks(REFUND_BID_ACCOUNTS_PER_BIDDER) {
+        let bidder_accounts = parse_bidder_accounts(program_id, bidder_accounts)?;
+        refund_bidder(
+            program_id,
+            accounts.auction,
+            &auction,
+            &mut auction_extended,
+            &bidder_accounts,
+            accounts.token_program,
+            auction_seeds,
+            clock.unix_timestamp,
+        )?;
+    }
+
+    auction_extended.save(accounts.auction_extended)?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn refund_bidder<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    auction_info: &'a AccountInfo<'b>,
+    auction: &AuctionData,
+    auction_extended: &mut AuctionDataExtended,
+    accounts: &BidderAccounts<'a, 'b>,
+    token_program: &'a AccountInfo<'b>,
+    auction_seeds: &[&[u8]],
+    now: UnixTimestamp,
+) -> ProgramResult {
+    assert_derivation(
+        program_id,
+        accounts.bidder_meta,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            auction_info.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            "metadata".as_bytes(),
+        ],
+    )?;
+
+    assert_derivation(
+        program_id,
+        accounts.bidder_pot,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            auction_info.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+        ],
+    )?;
+
+    let mut bidder_pot = BidderPot::from_account_info(accounts.bidder_pot)?;
+    if bidder_pot.bidder_pot != *accounts.bidder_pot_token.key {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    // Funds only ever go back to the bidder's own associated token account, of the mint the bid
+    // was paid in, or to their wallet for lamport bids.
+    if is_lamport_pot(accounts.bidder_pot, accounts.bidder_pot_token) {
+        if accounts.bidder_token.key != accounts.bidder.key {
+            return Err(AuctionError::InvalidBidAccount.into());
+        }
+    } else {
+        assert_is_ata(
+            accounts.bidder_token,
+            accounts.bidder.key,
+            &bidder_pot.mint(auction),
+        )?;
+    }
+
+    let pot_amount = assert_pot_balance(
+        auction_info.key,
+        accounts.bidder_pot,
+        accounts.bidder_pot_token,
+    )?;
+
+    // Winners settle through ClaimBid.
+    if auction.is_winner(accounts.bidder.key).is_some() {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    assert_sealed_bid_refundable(
+        program_id,
+        auction_info,
+        auction,
+        auction_extended,
+        accounts.bidder.key,
+        Some(accounts.bid_commitment),
+        now,
+    )?;
+
+    // The bid is the one last placed, anyone can add to what the pot holds.
+    let metadata = BidderMetadata::from_account_info(accounts.bidder_meta)?;
+    assert_bid_book_refundable(
+        auction,
+        auction_extended,
+        Some(accounts.bid_commitment),
+        &Bid(*accounts.bidder.key, metadata.last_bid),
+    )?;
+
+    pot_transfer(
+        accounts.bidder_pot,
+        TokenTransferParams {
+            source: accounts.bidder_pot_token.clone(),
+            destination: accounts.bidder_token.clone(),
+            authority: auction_info.clone(),
+            authority_signer_seeds: auction_seeds,
+            token_program: token_program.clone(),
+            amount: pot_amount,
+        },
+    )?;
+
+    bidder_pot.emptied = true;
+    bidder_pot.save(accounts.bidder_pot)?;
+
+    let already_cancelled = metadata.cancelled;
+    BidderMetadata {
+        cancelled: true,
+        ..metadata
+    }
+    .serialize(&mut *accounts.bidder_meta.data.borrow_mut())?;
+
+    // Same as CancelBid, once ended the uncancelled bids keep their pre-ending count.
+    if !already_cancelled {
+        if auction.state != AuctionState::Ended {
+            auction_extended.total_uncancelled_bids =
+                auction_extended.total_uncancelled_bids.saturating_sub(1);
+        }
+        auction_extended.close_bidder_pot();
+    }
+
+    AuctionEvent::BidRefunded {
+        auction: *auction_info.key,
+        bidder: *accounts.bidder.key,
+        amount: pot_amount,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -0,0 +1,253 @@
+//! Reveals a sealed bid once a sealed-bid auction has passed its end time. The revealed amount
+//! must match the commitment and fit within the deposit, the rest of the deposit is returned and
+//! the bid is placed into the auction as if it were an English auction bid.
+
+use crate::{
+    errors::AuctionError,
+    event::AuctionEvent,
+    processor::{
+        bid_commitment_hash, AuctionData, AuctionDataExtended, AuctionState, Bid, BidCommitment,
+        BidState, BidderMetadata, BidderPot,
+    },
+    utils::{
+        assert_derivation, assert_initialized, assert_owned_by, assert_signer,
+        assert_token_program_matches_package, spl_token_transfer, TokenTransferParams,
+    },
+    COMMITMENT, EXTENDED, PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::{clock::Clock, Sysvar},
+    },
+    spl_token::state::Account,
+};
+
+/// Arguments for the RevealBid instruction discriminant .
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct RevealBidArgs {
+    /// Amount that was bid.
+    pub amount: u64,
+    /// Salt the bid was hashed with.
+    pub salt: u64,
+    /// Resource being bid on.
+    pub resource: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    bid_commitment: &'a AccountInfo<'b>,
+    bidder_meta: &'a AccountInfo<'b>,
+    bidder_pot: &'a AccountInfo<'b>,
+    bidder_pot_token: &'a AccountInfo<'b>,
+    bidder: &'a AccountInfo<'b>,
+    bidder_token: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    token_program: &'a AccountInfo<'b>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        bidder: next_account_info(account_iter)?,
+        bidder_token: next_account_info(account_iter)?,
+        bidder_pot: next_account_info(account_iter)?,
+        bidder_pot_token: next_account_info(account_iter)?,
+        bidder_meta: next_account_info(account_iter)?,
+        bid_commitment: next_account_info(account_iter)?,
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        token_program: next_account_info(account_iter)?,
+    };
+
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
+    assert_owned_by(accounts.bid_commitment, program_id)?;
+    assert_owned_by(accounts.bidder_meta, program_id)?;
+    assert_owned_by(accounts.bidder_pot, program_id)?;
+    assert_owned_by(accounts.bidder_pot_token, &spl_token::id())?;
+    assert_owned_by(accounts.bidder_token, &spl_token::id())?;
+    assert_signer(accounts.bidder)?;
+    assert_token_program_matches_package(accounts.token_program)?;
+
+    Ok(accounts)
+}
+
+pub fn reveal_bid<'r, 'b: 'r>(
+    program_id: &Pubkey,
+    accounts: &'r [AccountInfo<'b>],
+    args: RevealBidArgs,
+) -> ProgramResult {
+    msg!("+ Processing RevealBid");
+    let accounts = parse_accounts(program_id, accounts)?;
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    // Derive and load Auction.
+    let auction_bump = assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+
+    let auction_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.resource.as_ref(),
+        &[auction_bump],
+    ];
+
+    let mut auction = AuctionData::from_account_info(accounts.auction)?;
+    if !matches!(auction.bid_state, BidState::SealedBidAuction { .. })
+        || auction.state != AuctionState::Started
+    {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+    let auction_extended = AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+    let config = auction_extended
+        .sealed_bid_config
+        .as_ref()
+        .ok_or(AuctionError::InvalidState)?;
+
+    // Reveals are only accepted between the end time and the close of the reveal window.
+    if !auction.ended(clock.unix_timestamp)?
+        || clock.unix_timestamp > auction.reveal_deadline(config)?
+    {
+        return Err(AuctionError::RevealWindowClosed.into());
+    }
+
+    // A cancelled commitment has already been refunded and has nothing left to reveal.
+    assert_derivation(
+        program_id,
+        accounts.bidder_meta,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            "metadata".as_bytes(),
+        ],
+    )?;
+    let metadata = BidderMetadata::from_account_info(accounts.bidder_meta)?;
+    if metadata.cancelled {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    assert_derivation(
+        program_id,
+        accounts.bid_commitment,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            COMMITMENT.as_bytes(),
+        ],
+    )?;
+    let mut commitment = BidCommitment::from_account_info(accounts.bid_commitment)?;
+    if commitment.revealed {
+        return Err(AuctionError::BidAlreadyActive.into());
+    }
+
+    if bid_commitment_hash(args.amount, args.salt, accounts.bidder.key) != commitment.commitment {
+        return Err(AuctionError::InvalidReveal.into());
+    }
+
+    if args.amount > commitment.deposit {
+        msg!(
+            "Revealed bid of {:?} is larger than the deposit of {:?}",
+            args.amount,
+            commitment.deposit
+        );
+        return Err(AuctionError::BalanceTooLow.into());
+    }
+
+    // Confirm we're looking at the real SPL account for this bidder.
+    assert_derivation(
+        program_id,
+        accounts.bidder_pot,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+        ],
+    )?;
+    let bidder_pot = BidderPot::from_account_info(accounts.bidder_pot)?;
+    if bidder_pot.bidder_pot != *accounts.bidder_pot_token.key {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    let actual_account: Account = assert_initialized(accounts.bidder_pot_token)?;
+    if actual_account.owner != *accounts.auction.key {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    // Return whatever was deposited on top of the bid, leaving exactly the bid in the pot.
+    let excess = actual_account.amount.saturating_sub(args.amount);
+    if excess > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: accounts.bidder_pot_token.clone(),
+            destination: accounts.bidder_token.clone(),
+            authority: accounts.auction.clone(),
+            authority_signer_seeds: auction_seeds,
+            token_program: accounts.token_program.clone(),
+            amount: excess,
+        })?;
+    }
+
+    auction.place_bid(
+        Bid(*accounts.bidder.key, args.amount),
+        auction_extended.tick_size,
+        auction_extended.gap_tick_size_percentage,
+        clock.unix_timestamp,
+        auction_extended.instant_sale_price,
+    )?;
+    auction.save(accounts.auction)?;
+
+    commitment.revealed = true;
+    commitment.serialize(&mut *accounts.bid_commitment.data.borrow_mut())?;
+
+    BidderMetadata {
+        last_bid: args.amount,
+        last_bid_timestamp: clock.unix_timestamp,
+        ..metadata
+    }
+    .serialize(&mut *accounts.bidder_meta.data.borrow_mut())?;
+
+    AuctionEvent::BidRevealed {
+        auction: *accounts.auction.key,
+        bidder: *accounts.bidder.key,
+        amount: args.amount,
+        refunded: excess,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -42,6 +42,6 @@ pub fn set_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramRe
     }
 
     auction.authority = *new_authority.key;
-    auction.serialize(&mut *auction_act.data.borrow_mut())?;
+    auction.save(auction_act)?;
     Ok(())
 }
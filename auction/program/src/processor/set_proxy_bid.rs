@@ -0,0 +1,503 @@
+//! Registers a proxy bid on a running English auction. The bidder escrows a maximum in their pot
+//! and whenever someone else outbids them, the program counters in the same instruction by the
+//! smallest valid step until the maximum runs out.
+//!
+//! Only one proxy bid is active on an auction at a time. When a second one is registered the
+//! lower maximum is bid in full and the higher one counters it, ties going to the proxy bid that
+//! was registered first.
+
+use crate::{
+    errors::AuctionError,
+    processor::{
+        AuctionData, AuctionDataExtended, AuctionState, Bid, BidState, BidderMetadata, BidderPot,
+        ProxyBid, PROXY_BID_LEN,
+    },
+    utils::{
+        assert_derivation, assert_initialized, assert_owned_by, assert_signer,
+        assert_token_program_matches_package, create_or_allocate_account_raw, spl_token_transfer,
+        TokenTransferParams,
+    },
+    EXTENDED, PREFIX, PROXY,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::UnixTimestamp,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        sysvar::{clock::Clock, Sysvar},
+    },
+    spl_token::state::Account,
+};
+
+/// Arguments for the SetProxyBid instruction discriminant .
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SetProxyBidArgs {
+    /// Largest bid the program may place on the bidder's behalf.
+    pub max_amount: u64,
+    /// Resource being bid on.
+    pub resource: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    bidder_meta: &'a AccountInfo<'b>,
+    bidder_pot: &'a AccountInfo<'b>,
+    bidder_pot_token: &'a AccountInfo<'b>,
+    bidder: &'a AccountInfo<'b>,
+    bidder_token: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    payer: &'a AccountInfo<'b>,
+    proxy_bid: &'a AccountInfo<'b>,
+    rent: &'a AccountInfo<'b>,
+    system: &'a AccountInfo<'b>,
+    token_program: &'a AccountInfo<'b>,
+    transfer_authority: &'a AccountInfo<'b>,
+    current_proxy_bid: Option<&'a AccountInfo<'b>>,
+    current_proxy_bidder_meta: Option<&'a AccountInfo<'b>>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        bidder: next_account_info(account_iter)?,
+        bidder_token: next_account_info(account_iter)?,
+        bidder_pot: next_account_info(account_iter)?,
+        bidder_pot_token: next_account_info(account_iter)?,
+        bidder_meta: next_account_info(account_iter)?,
+        proxy_bid: next_account_info(account_iter)?,
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        transfer_authority: next_account_info(account_iter)?,
+        payer: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        rent: next_account_info(account_iter)?,
+        system: next_account_info(account_iter)?,
+        token_program: next_account_info(account_iter)?,
+        current_proxy_bid: next_account_info(account_iter).ok(),
+        current_proxy_bidder_meta: next_account_info(account_iter).ok(),
+    };
+
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
+    assert_owned_by(accounts.bidder_meta, program_id)?;
+    assert_owned_by(accounts.bidder_pot, program_id)?;
+    assert_owned_by(accounts.bidder_pot_token, &spl_token::id())?;
+    assert_owned_by(accounts.bidder_token, &spl_token::id())?;
+
+    if !accounts.proxy_bid.data_is_empty() {
+        assert_owned_by(accounts.proxy_bid, program_id)?;
+    }
+
+    assert_signer(accounts.bidder)?;
+    assert_signer(accounts.payer)?;
+    assert_signer(accounts.transfer_authority)?;
+    assert_token_program_matches_package(accounts.token_program)?;
+
+    Ok(accounts)
+}
+
+pub fn set_proxy_bid<'r, 'b: 'r>(
+    program_id: &Pubkey,
+    accounts: &'r [AccountInfo<'b>],
+    args: SetProxyBidArgs,
+) -> ProgramResult {
+    msg!("+ Processing SetProxyBid");
+    let accounts = parse_accounts(program_id, accounts)?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+    let mut auction = AuctionData::from_account_info(accounts.auction)?;
+
+    // Proxy bids raise English auction bids, which are ranked against each other.
+    match auction.bid_state {
+        BidState::EnglishAuction { .. } => (),
+        BidState::SealedBidAuction { .. } => return Err(AuctionError::BidMustBeSealed.into()),
+        _ => return Err(AuctionError::InvalidState.into()),
+    }
+
+    // Load the clock, used for various auction timing.
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    // Verify auction has not ended.
+    if auction.ended(clock.unix_timestamp)? {
+        auction.state = auction.state.end()?;
+        auction.save(accounts.auction)?;
+        msg!("Auction ended!");
+        return Ok(());
+    }
+
+    // Can't bid on an auction that isn't running.
+    if auction.state != AuctionState::Started {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    // A proxy bid defends a live bid, placed beforehand with PlaceBid.
+    assert_derivation(
+        program_id,
+        accounts.bidder_meta,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            "metadata".as_bytes(),
+        ],
+    )?;
+    let metadata = BidderMetadata::from_account_info(accounts.bidder_meta)?;
+    if metadata.cancelled {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    // Confirm we're looking at the real SPL account for this bidder.
+    let pot_bump = assert_derivation(
+        program_id,
+        accounts.bidder_pot,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+        ],
+    )?;
+    let bidder_pot = BidderPot::from_account_info(accounts.bidder_pot)?;
+    if bidder_pot.bidder_pot != *accounts.bidder_pot_token.key {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    let actual_account: Account = assert_initialized(accounts.bidder_pot_token)?;
+    if actual_account.owner != *accounts.auction.key {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    // The maximum is held in the pot as is, so proxy bids are only taken in the token_mint.
+    if actual_account.mint != auction.token_mint {
+        return Err(AuctionError::IncorrectMint.into());
+    }
+
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+    let mut auction_extended = AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+
+    // The maximum must itself be a bid the auction would accept.
+    let mut max_amount = args.max_amount;
+    if let Some(instant_sale_price) = auction_extended.instant_sale_price {
+        if max_amount > instant_sale_price {
+            msg!("Received maximum is more than instant_sale_price so it was reduced to instant_sale_price - {:?}", instant_sale_price);
+            max_amount = instant_sale_price;
+        }
+    }
+
+    if let Some(tick) = auction_extended.tick_size {
+        if max_amount.checked_rem(tick) != Some(0) {
+            return Err(AuctionError::BidMustBeMultipleOfTickSize.into());
+        }
+    }
+
+    if max_amount <= metadata.last_bid {
+        msg!(
+            "Maximum of {:?} does not raise the current bid of {:?}",
+            max_amount,
+            metadata.last_bid
+        );
+        return Err(AuctionError::BidTooSmall.into());
+    }
+
+    // Escrow the maximum, the pot already holds the current bid and any earlier maximum.
+    let top_up = max_amount.saturating_sub(actual_account.amount);
+    if top_up > 0 {
+        let account: Account = Account::unpack_from_slice(&accounts.bidder_token.data.borrow())?;
+        if account.amount < top_up {
+            msg!(
+                "Amount is too small: {:?}, compared to account amount of {:?}",
+                top_up,
+                account.amount
+            );
+            return Err(AuctionError::BalanceTooLow.into());
+        }
+
+        spl_token_transfer(TokenTransferParams {
+            source: accounts.bidder_token.clone(),
+            destination: accounts.bidder_pot_token.clone(),
+            authority: accounts.transfer_authority.clone(),
+            authority_signer_seeds: &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                accounts.auction.key.as_ref(),
+                accounts.bidder.key.as_ref(),
+                &[pot_bump],
+            ],
+            token_program: accounts.token_program.clone(),
+            amount: top_up,
+        })?;
+    }
+
+    // Derive the proxy bid key and create it if this is the bidder's first proxy bid.
+    let proxy_bump = assert_derivation(
+        program_id,
+        accounts.proxy_bid,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.auction.key.as_ref(),
+            accounts.bidder.key.as_ref(),
+            PROXY.as_bytes(),
+        ],
+    )?;
+
+    if accounts.proxy_bid.data_is_empty() {
+        create_or_allocate_account_raw(
+            *program_id,
+            accounts.proxy_bid,
+            accounts.rent,
+            accounts.system,
+            accounts.payer,
+            PROXY_BID_LEN,
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                accounts.auction.key.as_ref(),
+                accounts.bidder.key.as_ref(),
+                PROXY.as_bytes(),
+                &[proxy_bump],
+            ],
+        )?;
+    }
+
+    ProxyBid {
+        bidder_pubkey: *accounts.bidder.key,
+        auction_pubkey: *accounts.auction.key,
+        max_amount,
+    }
+    .serialize(&mut *accounts.proxy_bid.data.borrow_mut())?;
+
+    // Settle against the proxy bid already on the auction, only the winner stays active.
+    let mut proxy_bidder = Some(*accounts.bidder.key);
+    if let Some(current) = auction_extended
+        .proxy_bidder
+        .filter(|key| key != accounts.bidder.key)
+    {
+        let current_proxy_bid = accounts
+            .current_proxy_bid
+            .ok_or(AuctionError::MissingProxyBid)?;
+        let current_proxy_bidder_meta = accounts
+            .current_proxy_bidder_meta
+            .ok_or(AuctionError::MissingProxyBid)?;
+        let (current_proxy, current_metadata) = load_proxy_bid(
+            program_id,
+            accounts.auction.key,
+            &current,
+            current_proxy_bid,
+            current_proxy_bidder_meta,
+        )?;
+
+        if !current_metadata.cancelled {
+            if current_proxy.max_amount >= max_amount {
+                raise_bid(
+                    &mut auction,
+                    &auction_extended,
+                    accounts.bidder.key,
+                    max_amount,
+                    accounts.bidder_meta,
+                    clock.unix_timestamp,
+                )?;
+                proxy_bidder = None;
+                if defend_proxy_bid(
+                    program_id,
+                    accounts.auction.key,
+                    &mut auction,
+                    &auction_extended,
+                    &current,
+                    Some(current_proxy_bid),
+                    Some(current_proxy_bidder_meta),
+                    clock.unix_timestamp,
+                )? {
+                    proxy_bidder = Some(current);
+                }
+            } else {
+                raise_bid(
+                    &mut auction,
+                    &auction_extended,
+                    &current,
+                    current_proxy.max_amount,
+                    current_proxy_bidder_meta,
+                    clock.unix_timestamp,
+                )?;
+            }
+        }
+    }
+
+    // The new proxy bid has to be able to take the lead, or there is nothing for it to defend.
+    if proxy_bidder == Some(*accounts.bidder.key)
+        && !defend_proxy_bid(
+            program_id,
+            accounts.auction.key,
+            &mut auction,
+            &auction_extended,
+            accounts.bidder.key,
+            Some(accounts.proxy_bid),
+            Some(accounts.bidder_meta),
+            clock.unix_timestamp,
+        )?
+    {
+        msg!("Maximum of {:?} cannot outbid the top bid", max_amount);
+        return Err(AuctionError::BidTooSmall.into());
+    }
+
+    auction.last_bid = Some(clock.unix_timestamp);
+    auction.save(accounts.auction)?;
+
+    auction_extended.proxy_bidder = proxy_bidder;
+    auction_extended.save(accounts.auction_extended)?;
+
+    Ok(())
+}
+
+/// Lets the current proxy bidder answer a bid placed by someone else, from the proxy bid and
+/// bidder metadata accounts passed in for them. Returns whether their proxy bid is still active.
+pub(crate) fn defend_proxy_bid(
+    program_id: &Pubkey,
+    auction_key: &Pubkey,
+    auction: &mut AuctionData,
+    auction_extended: &AuctionDataExtended,
+    proxy_bidder: &Pubkey,
+    proxy_bid: Option<&AccountInfo>,
+    proxy_bidder_meta: Option<&AccountInfo>,
+    now: UnixTimestamp,
+) -> Result<bool, ProgramError> {
+    let proxy_bid = proxy_bid.ok_or(AuctionError::MissingProxyBid)?;
+    let proxy_bidder_meta = proxy_bidder_meta.ok_or(AuctionError::MissingProxyBid)?;
+    let (proxy, metadata) = load_proxy_bid(
+        program_id,
+        auction_key,
+        proxy_bidder,
+        proxy_bid,
+        proxy_bidder_meta,
+    )?;
+
+    if metadata.cancelled {
+        return Ok(false);
+    }
+
+    match auction.counter_proxy_bid(
+        proxy_bidder,
+        proxy.max_amount,
+        auction_extended.tick_size,
+        auction_extended.gap_tick_size_percentage,
+        now,
+        auction_extended.instant_sale_price,
+    )? {
+        Some(standing_bid) => {
+            if standing_bid != metadata.last_bid {
+                BidderMetadata {
+                    last_bid: standing_bid,
+                    last_bid_timestamp: now,
+                    ..metadata
+                }
+                .serialize(&mut *proxy_bidder_meta.data.borrow_mut())?;
+            }
+            Ok(true)
+        }
+        None => {
+            msg!("Proxy bid of {:?} is exhausted", proxy_bidder);
+            Ok(false)
+        }
+    }
+}
+
+/// Loads a bidder's proxy bid and metadata, checking both belong to them on this auction.
+fn load_proxy_bid(
+    program_id: &Pubkey,
+    auction_key: &Pubkey,
+    bidder: &Pubkey,
+    proxy_bid: &AccountInfo,
+    bidder_meta: &AccountInfo,
+) -> Result<(ProxyBid, BidderMetadata), ProgramError> {
+    assert_owned_by(proxy_bid, program_id)?;
+    assert_owned_by(bidder_meta, program_id)?;
+
+    assert_derivation(
+        program_id,
+        proxy_bid,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            auction_key.as_ref(),
+            bidder.as_ref(),
+            PROXY.as_bytes(),
+        ],
+    )?;
+    assert_derivation(
+        program_id,
+        bidder_meta,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            auction_key.as_ref(),
+            bidder.as_ref(),
+            "metadata".as_bytes(),
+        ],
+    )?;
+
+    Ok((
+        ProxyBid::from_account_info(proxy_bid)?,
+        BidderMetadata::from_account_info(bidder_meta)?,
+    ))
+}
+
+/// Raises a bidder's standing bid to `amount`, which is already held in their pot.
+fn raise_bid(
+    auction: &mut AuctionData,
+    auction_extended: &AuctionDataExtended,
+    bidder: &Pubkey,
+    amount: u64,
+    bidder_meta: &AccountInfo,
+    now: UnixTimestamp,
+) -> ProgramResult {
+    let metadata = BidderMetadata::from_account_info(bidder_meta)?;
+    if amount <= metadata.last_bid {
+        return Ok(());
+    }
+
+    auction.increase_bid(
+        Bid(*bidder, amount),
+        auction_extended.tick_size,
+        auction_extended.gap_tick_size_percentage,
+        now,
+        auction_extended.instant_sale_price,
+    )?;
+
+    BidderMetadata {
+        last_bid: amount,
+        last_bid_timestamp: now,
+        ..metadata
+    }
+    .serialize(&mut *bidder_meta.data.borrow_mut())?;
+
+    Ok(())
+}
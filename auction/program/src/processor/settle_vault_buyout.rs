@@ -0,0 +1,247 @@
+//! Settles a buyout auction once bidding is over, permissionless. The winning bid is claimed into
+//! the redeem treasury of the vault, and the vault is combined with the winner as its authority,
+//! leaving the shareholders to redeem their shares for their part of the bid. Outbid buyers cancel
+//! their bids for a refund as in any other auction.
+//!
+//! Should the vault have been combined some other way in the meantime, the buyout is cancelled
+//! instead and every bid refunded.
+
+use crate::{
+    errors::AuctionError,
+    event::AuctionEvent,
+    instruction::claim_bid_instruction,
+    processor::{AuctionData, AuctionState, ClaimBidArgs},
+    utils::{assert_derivation, assert_initialized, assert_owned_by},
+    PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    metaplex_token_vault::{
+        instruction::create_combine_vault_by_buyout_instruction,
+        state::{Vault, VaultState, BUYOUT},
+    },
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::invoke_signed,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+    spl_token::state::Account,
+};
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SettleVaultBuyoutArgs {
+    /// The vault being bought out.
+    pub vault: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    buyout_authority: &'a AccountInfo<'b>,
+    vault: &'a AccountInfo<'b>,
+    fraction_mint: &'a AccountInfo<'b>,
+    fraction_treasury: &'a AccountInfo<'b>,
+    redeem_treasury: &'a AccountInfo<'b>,
+    fraction_burn_authority: &'a AccountInfo<'b>,
+    bidder_pot_token: &'a AccountInfo<'b>,
+    bidder_pot: &'a AccountInfo<'b>,
+    bidder: &'a AccountInfo<'b>,
+    bidder_token: &'a AccountInfo<'b>,
+    mint: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    token_program: &'a AccountInfo<'b>,
+    vault_program: &'a AccountInfo<'b>,
+    auction_program: &'a AccountInfo<'b>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        buyout_authority: next_account_info(account_iter)?,
+        vault: next_account_info(account_iter)?,
+        fraction_mint: next_account_info(account_iter)?,
+        fraction_treasury: next_account_info(account_iter)?,
+        redeem_treasury: next_account_info(account_iter)?,
+        fraction_burn_authority: next_account_info(account_iter)?,
+        bidder_pot_token: next_account_info(account_iter)?,
+        bidder_pot: next_account_info(account_iter)?,
+        bidder: next_account_info(account_iter)?,
+        bidder_token: next_account_info(account_iter)?,
+        mint: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        token_program: next_account_info(account_iter)?,
+        vault_program: next_account_info(account_iter)?,
+        auction_program: next_account_info(account_iter)?,
+    };
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
+    assert_owned_by(accounts.vault, &metaplex_token_vault::id())?;
+    assert_owned_by(accounts.redeem_treasury, &spl_token::id())?;
+    if *accounts.vault_program.key != metaplex_token_vault::id()
+        || accounts.auction_program.key != program_id
+        || *accounts.token_program.key != spl_token::id()
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(accounts)
+}
+
+pub fn settle_vault_buyout<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    args: SettleVaultBuyoutArgs,
+) -> ProgramResult {
+    msg!("+ Processing SettleVaultBuyout");
+    let accounts = parse_accounts(program_id, accounts)?;
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    if *accounts.vault.key != args.vault {
+        return Err(AuctionError::InvalidVaultBuyout.into());
+    }
+
+    let buyout_bump = assert_derivation(
+        program_id,
+        accounts.buyout_authority,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.vault.as_ref(),
+            BUYOUT.as_bytes(),
+        ],
+    )?;
+    let buyout_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        args.vault.as_ref(),
+        BUYOUT.as_bytes(),
+        &[buyout_bump],
+    ];
+
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.buyout_authority.key.as_ref(),
+        ],
+    )?;
+
+    let mut auction = AuctionData::from_account_info(accounts.auction)?;
+    if auction.authority != *accounts.buyout_authority.key {
+        return Err(AuctionError::InvalidAuthority.into());
+    }
+
+    if auction.state == AuctionState::Cancelled || !auction.is_over(clock.unix_timestamp)? {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    // The vault can no longer be combined, so nobody gets it and every bid is refunded.
+    let vault = Vault::from_account_info(accounts.vault)?;
+    if vault.state != VaultState::Active {
+        msg!("Vault is no longer active, cancelling the buyout");
+        auction.state = auction.state.cancel()?;
+        auction.bid_state.clear();
+        auction.save(accounts.auction)?;
+
+        AuctionEvent::AuctionCancelled {
+            auction: *accounts.auction.key,
+        }
+        .emit()?;
+
+        return Ok(());
+    }
+
+    // Without a bid the buyout can only be started again.
+    if auction.winner_at(0) != Some(*accounts.bidder.key) {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    if auction.state != AuctionState::Ended {
+        auction.state = auction.state.end()?;
+        auction.save(accounts.auction)?;
+
+        AuctionEvent::AuctionEnded {
+            auction: *accounts.auction.key,
+            ended_at: auction.ended_at,
+        }
+        .emit()?;
+    }
+
+    // Claim the winning bid into the redeem treasury, signing as the authority on the auction.
+    let treasury_before = assert_initialized::<Account>(accounts.redeem_treasury)?.amount;
+    invoke_signed(
+        &claim_bid_instruction(
+            *program_id,
+            *accounts.redeem_treasury.key,
+            *accounts.buyout_authority.key,
+            *accounts.bidder.key,
+            *accounts.bidder_pot_token.key,
+            *accounts.mint.key,
+            Some(*accounts.bidder_token.key),
+            None,
+            ClaimBidArgs {
+                resource: *accounts.buyout_authority.key,
+            },
+        ),
+        &[
+            accounts.redeem_treasury.clone(),
+            accounts.bidder_pot_token.clone(),
+            accounts.bidder_pot.clone(),
+            accounts.buyout_authority.clone(),
+            accounts.auction.clone(),
+            accounts.bidder.clone(),
+            accounts.mint.clone(),
+            accounts.clock_sysvar.clone(),
+            accounts.token_program.clone(),
+            accounts.auction_extended.clone(),
+            accounts.bidder_token.clone(),
+            accounts.auction_program.clone(),
+        ],
+        &[buyout_seeds],
+    )?;
+    let paid = assert_initialized::<Account>(accounts.redeem_treasury)?
+        .amount
+        .checked_sub(treasury_before)
+        .ok_or(AuctionError::NumericalOverflowError)?;
+
+    invoke_signed(
+        &create_combine_vault_by_buyout_instruction(
+            metaplex_token_vault::id(),
+            *accounts.vault.key,
+            *accounts.fraction_mint.key,
+            *accounts.fraction_treasury.key,
+            *accounts.redeem_treasury.key,
+            *accounts.bidder.key,
+            *accounts.buyout_authority.key,
+            *accounts.fraction_burn_authority.key,
+            paid,
+        ),
+        &[
+            accounts.vault.clone(),
+            accounts.fraction_mint.clone(),
+            accounts.fraction_treasury.clone(),
+            accounts.redeem_treasury.clone(),
+            accounts.bidder.clone(),
+            accounts.buyout_authority.clone(),
+            accounts.fraction_burn_authority.clone(),
+            accounts.token_program.clone(),
+            accounts.vault_program.clone(),
+        ],
+        &[buyout_seeds],
+    )?;
+
+    Ok(())
+}
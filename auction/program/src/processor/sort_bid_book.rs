@@ -0,0 +1,108 @@
+//! Permissionless crank sorting the bid book of a bid-book auction once bidding has closed, so
+//! winners can be looked up when bids are claimed. Large books take several calls, each moving up
+//! to the number of bids asked for to their final rank.
+
+use crate::{
+    errors::AuctionError,
+    processor::{AuctionData, AuctionDataExtended, AuctionState, BidBook, BidState},
+    utils::{assert_derivation, assert_owned_by},
+    EXTENDED, PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::{clock::Clock, Sysvar},
+    },
+};
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SortBidBookArgs {
+    /// The resource being auctioned. See AuctionData.
+    pub resource: Pubkey,
+    /// Most bids to move to their final rank in this call.
+    pub steps: u64,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    bid_book: &'a AccountInfo<'b>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        bid_book: next_account_info(account_iter)?,
+    };
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
+    assert_owned_by(accounts.bid_book, program_id)?;
+    Ok(accounts)
+}
+
+pub fn sort_bid_book<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    args: SortBidBookArgs,
+) -> ProgramResult {
+    msg!("+ Processing SortBidBook");
+    let accounts = parse_accounts(program_id, accounts)?;
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+
+    let auction = AuctionData::from_account_info(accounts.auction)?;
+    let auction_extended = AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+
+    if !matches!(auction.bid_state, BidState::BidBook { .. }) {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    if auction_extended.bid_book != Some(*accounts.bid_book.key) {
+        return Err(AuctionError::InvalidBidBook.into());
+    }
+
+    // Sorting stops the book from taking bids, so it waits until bidding has closed.
+    if auction.state == AuctionState::Cancelled || !auction.is_over(clock.unix_timestamp)? {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    if BidBook::sort(accounts.bid_book, args.steps)? {
+        msg!("Bid book sorted!");
+    }
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use crate::{
     errors::AuctionError,
+    event::AuctionEvent,
     processor::{AuctionData, AuctionState, Bid, BidState, WinnerLimit},
     utils::{assert_derivation, assert_owned_by, assert_signer, create_or_allocate_account_raw},
     PREFIX,
@@ -75,22 +76,14 @@ pub fn start_auction<'a, 'b: 'a>(
         return Err(AuctionError::InvalidAuthority.into());
     }
 
-    // Calculate the relative end time.
-    let ended_at = if let Some(end_auction_at) = auction.end_auction_at {
-        match clock.unix_timestamp.checked_add(end_auction_at) {
-            Some(val) => Some(val),
-            None => return Err(AuctionError::NumericalOverflowError.into()),
-        }
-    } else {
-        None
-    };
+    auction.start(clock.unix_timestamp)?;
+    auction.save(accounts.auction)?;
 
-    AuctionData {
-        ended_at,
-        state: auction.state.start()?,
-        ..auction
+    AuctionEvent::AuctionStarted {
+        auction: *accounts.auction.key,
+        ended_at: auction.ended_at,
     }
-    .serialize(&mut *accounts.auction.data.borrow_mut())?;
+    .emit()?;
 
     Ok(())
 }
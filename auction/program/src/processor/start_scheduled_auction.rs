@@ -0,0 +1,117 @@
+//! Permissionless crank starting an auction once its scheduled start time has passed, for auctions
+//! that should be running before anyone bids. Bids start such auctions by themselves.
+
+use crate::{
+    errors::AuctionError,
+    event::AuctionEvent,
+    processor::{AuctionData, AuctionDataExtended},
+    utils::{assert_derivation, assert_owned_by},
+    EXTENDED, PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::{Clock, UnixTimestamp},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct StartScheduledAuctionArgs {
+    /// The resource being auctioned. See AuctionData.
+    pub resource: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+    };
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
+    Ok(accounts)
+}
+
+pub fn start_scheduled_auction<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    args: StartScheduledAuctionArgs,
+) -> ProgramResult {
+    msg!("+ Processing StartScheduledAuction");
+    let accounts = parse_accounts(program_id, accounts)?;
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+
+    let mut auction = AuctionData::from_account_info(accounts.auction)?;
+    if !start_if_scheduled(
+        program_id,
+        &args.resource,
+        &mut auction,
+        accounts.auction_extended,
+        clock.unix_timestamp,
+    )? {
+        return Err(AuctionError::AuctionTransitionInvalid.into());
+    }
+
+    auction.save(accounts.auction)?;
+
+    AuctionEvent::AuctionStarted {
+        auction: *accounts.auction.key,
+        ended_at: auction.ended_at,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// Starts the auction if it is scheduled to have started by now, reading the start time from the
+/// auction extended account. The caller saves the auction.
+pub(crate) fn start_if_scheduled(
+    program_id: &Pubkey,
+    resource: &Pubkey,
+    auction: &mut AuctionData,
+    auction_extended: &AccountInfo,
+    now: UnixTimestamp,
+) -> Result<bool, ProgramError> {
+    assert_derivation(
+        program_id,
+        auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+
+    let start_auction_at =
+        AuctionDataExtended::from_account_info(auction_extended)?.start_auction_at;
+    auction.start_if_scheduled(start_auction_at, now)
+}
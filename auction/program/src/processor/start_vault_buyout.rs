@@ -0,0 +1,231 @@
+//! Starts a buyout auction for the whole of a fractionalized vault, permissionless. The auction is
+//! an English auction for a single winner, paid in the mint of the vault's redeem treasury, with
+//! the reserve as its minimum price. The reserve is the circulating shares at the price share
+//! holders voted for. Its resource and authority are the buyout authority of the
+//! vault, so only SettleVaultBuyout can settle it. A buyout that closed without any bids can be
+//! started again.
+
+use crate::{
+    errors::AuctionError,
+    event::AuctionEvent,
+    processor::{
+        create_auction, AuctionData, AuctionExtendedSettings, AuctionState, CreateAuctionArgs,
+        PriceFloor, WinnerLimit,
+    },
+    utils::{assert_derivation, assert_initialized, assert_owned_by},
+    PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    metaplex_token_vault::{
+        state::{ExternalPriceAccount, Vault, VaultState, BUYOUT},
+        utils::reserve_price,
+    },
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::{Clock, UnixTimestamp},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+    spl_token::state::{Account, Mint},
+};
+
+/// How long a buyout auction runs for once started.
+pub const BUYOUT_AUCTION_DURATION: UnixTimestamp = 3 * 24 * 60 * 60;
+/// Bids this close to the end of a buyout auction keep it open, so it cannot be sniped.
+pub const BUYOUT_AUCTION_GAP: UnixTimestamp = 15 * 60;
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct StartVaultBuyoutArgs {
+    /// The vault being bought out.
+    pub vault: Pubkey,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    payer: &'a AccountInfo<'b>,
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+    buyout_authority: &'a AccountInfo<'b>,
+    vault: &'a AccountInfo<'b>,
+    fraction_mint: &'a AccountInfo<'b>,
+    fraction_treasury: &'a AccountInfo<'b>,
+    redeem_treasury: &'a AccountInfo<'b>,
+    external_pricing: &'a AccountInfo<'b>,
+    clock_sysvar: &'a AccountInfo<'b>,
+    rent: &'a AccountInfo<'b>,
+    system: &'a AccountInfo<'b>,
+    reserve_votes: &'a AccountInfo<'b>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        payer: next_account_info(account_iter)?,
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+        buyout_authority: next_account_info(account_iter)?,
+        vault: next_account_info(account_iter)?,
+        fraction_mint: next_account_info(account_iter)?,
+        fraction_treasury: next_account_info(account_iter)?,
+        redeem_treasury: next_account_info(account_iter)?,
+        external_pricing: next_account_info(account_iter)?,
+        clock_sysvar: next_account_info(account_iter)?,
+        rent: next_account_info(account_iter)?,
+        system: next_account_info(account_iter)?,
+        reserve_votes: next_account_info(account_iter)?,
+    };
+    assert_owned_by(accounts.vault, &metaplex_token_vault::id())?;
+    assert_owned_by(accounts.fraction_mint, &spl_token::id())?;
+    assert_owned_by(accounts.fraction_treasury, &spl_token::id())?;
+    assert_owned_by(accounts.redeem_treasury, &spl_token::id())?;
+    assert_owned_by(accounts.external_pricing, &metaplex_token_vault::id())?;
+    Ok(accounts)
+}
+
+pub fn start_vault_buyout<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    args: StartVaultBuyoutArgs,
+) -> ProgramResult {
+    msg!("+ Processing StartVaultBuyout");
+    let accounts = parse_accounts(accounts)?;
+    let clock = Clock::from_account_info(accounts.clock_sysvar)?;
+
+    if *accounts.vault.key != args.vault {
+        return Err(AuctionError::InvalidVaultBuyout.into());
+    }
+
+    assert_derivation(
+        program_id,
+        accounts.buyout_authority,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.vault.as_ref(),
+            BUYOUT.as_bytes(),
+        ],
+    )?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            accounts.buyout_authority.key.as_ref(),
+        ],
+    )?;
+
+    let vault = Vault::from_account_info(accounts.vault)?;
+    if vault.state != VaultState::Active
+        || vault.fraction_mint != *accounts.fraction_mint.key
+        || vault.fraction_treasury != *accounts.fraction_treasury.key
+        || vault.redeem_treasury != *accounts.redeem_treasury.key
+        || vault.pricing_lookup_address != *accounts.external_pricing.key
+    {
+        return Err(AuctionError::InvalidVaultBuyout.into());
+    }
+
+    // Bids are paid out into the redeem treasury, so they are made in its mint.
+    let redeem_treasury: Account = assert_initialized(accounts.redeem_treasury)?;
+    let external_pricing = ExternalPriceAccount::from_account_info(accounts.external_pricing)?;
+    if redeem_treasury.mint != external_pricing.price_mint {
+        return Err(AuctionError::InvalidVaultBuyout.into());
+    }
+
+    let circulating_shares =
+        circulating_shares(accounts.fraction_mint, accounts.fraction_treasury)?;
+    let price_per_share = reserve_price(
+        &metaplex_token_vault::id(),
+        accounts.vault,
+        accounts.reserve_votes,
+        circulating_shares,
+    )?;
+    let reserve = circulating_shares
+        .checked_mul(price_per_share)
+        .ok_or(AuctionError::NumericalOverflowError)?;
+
+    let mut auction = if accounts.auction.data_is_empty() {
+        create_auction(
+            program_id,
+            &[
+                accounts.payer.clone(),
+                accounts.auction.clone(),
+                accounts.auction_extended.clone(),
+                accounts.rent.clone(),
+                accounts.system.clone(),
+            ],
+            CreateAuctionArgs {
+                winners: WinnerLimit::Capped(1),
+                end_auction_at: Some(BUYOUT_AUCTION_DURATION),
+                end_auction_gap: Some(BUYOUT_AUCTION_GAP),
+                token_mint: redeem_treasury.mint,
+                authority: *accounts.buyout_authority.key,
+                resource: *accounts.buyout_authority.key,
+                price_floor: PriceFloor::MinimumPrice([reserve, 0, 0, 0]),
+                tick_size: None,
+                gap_tick_size_percentage: None,
+            },
+            AuctionExtendedSettings::default(),
+        )?;
+        AuctionData::from_account_info(accounts.auction)?
+    } else {
+        // Only a buyout that closed without anyone bidding can be run again, at the reserve
+        // holders ask for now.
+        let mut auction = AuctionData::from_account_info(accounts.auction)?;
+        if auction.state == AuctionState::Cancelled
+            || !auction.is_over(clock.unix_timestamp)?
+            || auction.num_winners() > 0
+        {
+            return Err(AuctionError::AuctionTransitionInvalid.into());
+        }
+        auction.state = AuctionState::Created;
+        auction.last_bid = None;
+        auction.price_floor = PriceFloor::MinimumPrice([reserve, 0, 0, 0]);
+        auction
+    };
+
+    auction.start(clock.unix_timestamp)?;
+    auction.save(accounts.auction)?;
+
+    AuctionEvent::AuctionStarted {
+        auction: *accounts.auction.key,
+        ended_at: auction.ended_at,
+    }
+    .emit()?;
+
+    AuctionEvent::VaultBuyoutStarted {
+        auction: *accounts.auction.key,
+        vault: args.vault,
+        reserve,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// Shares of the vault held outside its fraction treasury, which the buyout pays for.
+fn circulating_shares(
+    fraction_mint: &AccountInfo,
+    fraction_treasury: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    let fraction_mint: Mint = assert_initialized(fraction_mint)?;
+    let fraction_treasury: Account = assert_initialized(fraction_treasury)?;
+
+    let circulating_shares = fraction_mint
+        .supply
+        .checked_sub(fraction_treasury.amount)
+        .ok_or(AuctionError::NumericalOverflowError)?;
+    if circulating_shares == 0 {
+        return Err(AuctionError::InvalidVaultBuyout.into());
+    }
+
+    Ok(circulating_shares)
+}
@@ -0,0 +1,153 @@
+//! Lets the authority correct the parameters of an auction before it starts. Every editable field
+//! is rewritten with the values passed in, and checked against the same rules as CreateAuction.
+
+use crate::{
+    errors::AuctionError,
+    processor::{
+        assert_valid_auction_settings, AuctionData, AuctionDataExtended, AuctionExtendedSettings,
+        AuctionName, AuctionState, BidState, CreateAuctionArgs, PriceFloor, WinnerLimit,
+    },
+    utils::{assert_derivation, assert_owned_by, assert_signer},
+    EXTENDED, PREFIX,
+};
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::UnixTimestamp,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct UpdateAuctionArgs {
+    /// The resource being auctioned. See AuctionData.
+    pub resource: Pubkey,
+    /// End time is the cut-off point that the auction is forced to end by. See AuctionData.
+    pub end_auction_at: Option<UnixTimestamp>,
+    /// Gap time is how much time after the previous bid where the auction ends. See AuctionData.
+    pub end_auction_gap: Option<UnixTimestamp>,
+    /// Set a price floor.
+    pub price_floor: PriceFloor,
+    /// Add a tick size increment
+    pub tick_size: Option<u64>,
+    /// Add a minimum percentage increase each bid must meet.
+    pub gap_tick_size_percentage: Option<u8>,
+    /// Add a instant sale price.
+    pub instant_sale_price: Option<u64>,
+    /// Auction name
+    pub name: Option<AuctionName>,
+}
+
+struct Accounts<'a, 'b: 'a> {
+    authority: &'a AccountInfo<'b>,
+    auction: &'a AccountInfo<'b>,
+    auction_extended: &'a AccountInfo<'b>,
+}
+
+fn parse_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<Accounts<'a, 'b>, ProgramError> {
+    let account_iter = &mut accounts.iter();
+    let accounts = Accounts {
+        authority: next_account_info(account_iter)?,
+        auction: next_account_info(account_iter)?,
+        auction_extended: next_account_info(account_iter)?,
+    };
+    assert_owned_by(accounts.auction, program_id)?;
+    assert_owned_by(accounts.auction_extended, program_id)?;
+    assert_signer(accounts.authority)?;
+    Ok(accounts)
+}
+
+pub fn update_auction<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    args: UpdateAuctionArgs,
+) -> ProgramResult {
+    msg!("+ Processing UpdateAuction");
+    let accounts = parse_accounts(program_id, accounts)?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+        ],
+    )?;
+
+    assert_derivation(
+        program_id,
+        accounts.auction_extended,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+    )?;
+
+    let mut auction = AuctionData::from_account_info(accounts.auction)?;
+    let mut auction_extended = AuctionDataExtended::from_account_info(accounts.auction_extended)?;
+
+    // Check authority is correct.
+    if auction.authority != *accounts.authority.key {
+        return Err(AuctionError::InvalidAuthority.into());
+    }
+
+    // Bidders must be able to rely on the rules once bidding is open.
+    if auction.state != AuctionState::Created {
+        return Err(AuctionError::InvalidState.into());
+    }
+
+    // The number of winners sized the auction account, so it stays as created.
+    let winners = match auction.bid_state {
+        BidState::OpenEdition { .. } => WinnerLimit::Unlimited(0),
+        BidState::EnglishAuction { max, .. }
+        | BidState::DutchAuction { max, .. }
+        | BidState::SealedBidAuction { max, .. }
+        | BidState::Raffle { max, .. }
+        | BidState::BidBook { max, .. } => WinnerLimit::Capped(max),
+    };
+
+    assert_valid_auction_settings(
+        &CreateAuctionArgs {
+            winners,
+            end_auction_at: args.end_auction_at,
+            end_auction_gap: args.end_auction_gap,
+            token_mint: auction.token_mint,
+            authority: auction.authority,
+            resource: args.resource,
+            price_floor: args.price_floor.clone(),
+            tick_size: args.tick_size,
+            gap_tick_size_percentage: args.gap_tick_size_percentage,
+        },
+        &auction.bid_state,
+        &AuctionExtendedSettings {
+            instant_sale_price: args.instant_sale_price,
+            name: args.name,
+            ..auction_extended.settings()
+        },
+    )?;
+
+    auction.end_auction_at = args.end_auction_at;
+    auction.end_auction_gap = args.end_auction_gap;
+    auction.price_floor = args.price_floor;
+    auction.save(accounts.auction)?;
+
+    auction_extended.tick_size = args.tick_size;
+    auction_extended.gap_tick_size_percentage = args.gap_tick_size_percentage;
+    auction_extended.instant_sale_price = args.instant_sale_price;
+    auction_extended.name = args.name;
+    auction_extended.save(accounts.auction_extended)?;
+
+    Ok(())
+}
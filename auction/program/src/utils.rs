@@ -1,7 +1,8 @@
 use solana_program::program_pack::IsInitialized;
 
 use {
-    crate::errors::AuctionError,
+    crate::{errors::AuctionError, processor::BidGate},
+    metaplex_token_metadata::state::Metadata,
     solana_program::{
         account_info::AccountInfo,
         entrypoint::ProgramResult,
@@ -13,7 +14,8 @@ use {
         system_instruction,
         sysvar::{rent::Rent, Sysvar},
     },
-    std::convert::TryInto,
+    spl_token::state::Account,
+    std::{convert::TryInto, str::FromStr},
 };
 
 pub fn assert_initialized<T: Pack + IsInitialized>(
@@ -77,6 +79,98 @@ pub fn assert_derivation(
     Ok(bump)
 }
 
+/// Address of the associated token account of `wallet` for `mint`.
+pub fn get_associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), spl_token::id().as_ref(), mint.as_ref()],
+        &Pubkey::from_str("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL").unwrap(),
+    )
+    .0
+}
+
+pub fn assert_is_ata(account: &AccountInfo, wallet: &Pubkey, mint: &Pubkey) -> ProgramResult {
+    if *account.key != get_associated_token_address(wallet, mint) {
+        return Err(AuctionError::DerivedKeyInvalid.into());
+    }
+
+    Ok(())
+}
+
+/// Checks the bidder holds the token required by the bid gate. The proof is taken from the end of
+/// `accounts`: a token account of the bidder holding it, followed by its token metadata when the
+/// gate is on a creator.
+pub fn assert_passes_bid_gate(
+    gate: &BidGate,
+    bidder: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let proof_len = match gate {
+        BidGate::Mint(_) => 1,
+        BidGate::Creator(_) => 2,
+    };
+    if accounts.len() < proof_len {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let proof = &accounts[accounts.len() - proof_len..];
+
+    assert_owned_by(&proof[0], &spl_token::id())?;
+    let token: Account = assert_initialized(&proof[0])?;
+    if token.owner != *bidder || token.amount == 0 {
+        return Err(AuctionError::BidGateNotMet.into());
+    }
+
+    match gate {
+        BidGate::Mint(mint) => {
+            if token.mint != *mint {
+                return Err(AuctionError::BidGateNotMet.into());
+            }
+        }
+        BidGate::Creator(creator) => {
+            let metadata_program = metaplex_token_metadata::id();
+            assert_owned_by(&proof[1], &metadata_program)?;
+            assert_derivation(
+                &metadata_program,
+                &proof[1],
+                &[
+                    metaplex_token_metadata::state::PREFIX.as_bytes(),
+                    metadata_program.as_ref(),
+                    token.mint.as_ref(),
+                ],
+            )?;
+
+            // Only verified creators count, anyone can list themselves unverified.
+            let metadata = Metadata::from_account_info(&proof[1])?;
+            if !metadata
+                .data
+                .creators
+                .iter()
+                .flatten()
+                .any(|c| c.address == *creator && c.verified)
+            {
+                return Err(AuctionError::BidGateNotMet.into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Closes an account owned by this program, moving its rent to `destination`.
+pub fn close_program_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let lamports = destination
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(AuctionError::NumericalOverflowError)?;
+    **destination.lamports.borrow_mut() = lamports;
+    **account.lamports.borrow_mut() = 0;
+
+    for byte in account.data.borrow_mut().iter_mut() {
+        *byte = 0;
+    }
+
+    Ok(())
+}
+
 #[inline(always)]
 pub fn create_or_allocate_account_raw<'a>(
     program_id: Pubkey,
@@ -166,6 +260,62 @@ pub fn spl_token_transfer(params: TokenTransferParams<'_, '_>) -> ProgramResult
     result.map_err(|_| AuctionError::TokenTransferFailed.into())
 }
 
+/// Whether the bid is paid in lamports, held by the bidder pot itself. Such bids pass the bidder
+/// pot in place of the pot token account.
+pub fn is_lamport_pot(bidder_pot: &AccountInfo, bidder_pot_token: &AccountInfo) -> bool {
+    bidder_pot.key == bidder_pot_token.key
+}
+
+/// Amount held for a bid: the balance of the pot token account, which must be owned by the
+/// auction, or for lamport bids the lamports the bidder pot holds above its rent exempt balance.
+pub fn assert_pot_balance(
+    auction: &Pubkey,
+    bidder_pot: &AccountInfo,
+    bidder_pot_token: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    if is_lamport_pot(bidder_pot, bidder_pot_token) {
+        let rent = Rent::get()?;
+        return Ok(bidder_pot
+            .lamports()
+            .saturating_sub(rent.minimum_balance(bidder_pot.data_len())));
+    }
+
+    assert_owned_by(bidder_pot_token, &spl_token::id())?;
+    let account: Account = assert_initialized(bidder_pot_token)?;
+    if account.owner != *auction {
+        return Err(AuctionError::BidderPotTokenAccountOwnerMismatch.into());
+    }
+
+    Ok(account.amount)
+}
+
+/// Pays out of a bidder pot. Lamport bids are moved straight out of the bidder pot, which the
+/// program owns, anything else through the token program like spl_token_transfer.
+pub fn pot_transfer(
+    bidder_pot: &AccountInfo,
+    params: TokenTransferParams<'_, '_>,
+) -> ProgramResult {
+    if !is_lamport_pot(bidder_pot, &params.source) {
+        return spl_token_transfer(params);
+    }
+
+    let rent = Rent::get()?;
+    let remaining = bidder_pot
+        .lamports()
+        .checked_sub(params.amount)
+        .filter(|remaining| *remaining >= rent.minimum_balance(bidder_pot.data_len()))
+        .ok_or(AuctionError::TokenTransferFailed)?;
+    let received = params
+        .destination
+        .lamports()
+        .checked_add(params.amount)
+        .ok_or(AuctionError::NumericalOverflowError)?;
+    **bidder_pot.lamports.borrow_mut() = remaining;
+    **params.destination.lamports.borrow_mut() = received;
+
+    Ok(())
+}
+
 /// TokenMintToParams
 pub struct TokenCreateAccount<'a> {
     /// payer
@@ -1,11 +1,22 @@
 use metaplex_auction::{
     instruction,
     processor::{
-        CancelBidArgs, ClaimBidArgs, CreateAuctionArgs, CreateAuctionArgsV2, EndAuctionArgs,
-        PlaceBidArgs, PriceFloor, StartAuctionArgs, WinnerLimit,
+        AuctionExtendedSettings, BidBook, CancelAuctionArgs, CancelBidArgs, ClaimBidArgs,
+        CloseAuctionArgs, CloseBidderPotArgs, CommitBidArgs, CreateAuctionArgs,
+        CreateAuctionArgsV2, DrawWinnersArgs, EndAuctionArgs, IncreaseBidArgs, MigrateAuctionArgs,
+        PlaceBidArgs, PriceFloor, RefundBidArgs, RevealBidArgs, SetProxyBidArgs, SortBidBookArgs,
+        StartAuctionArgs, StartScheduledAuctionArgs, StartVaultBuyoutArgs, UpdateAuctionArgs,
+        WinnerLimit,
     },
 };
-use solana_program::{hash::Hash, program_pack::Pack, pubkey::Pubkey, system_instruction};
+use solana_program::{
+    clock::UnixTimestamp,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction, system_program, sysvar,
+};
 use solana_program_test::*;
 use solana_sdk::{
     account::Account,
@@ -13,6 +24,7 @@ use solana_sdk::{
     transaction::Transaction,
     transport::TransportError,
 };
+use std::str::FromStr;
 
 fn string_to_array(value: &str) -> Result<[u8; 32], TransportError> {
     if value.len() > 32 {
@@ -36,9 +48,20 @@ pub async fn create_mint(
     payer: &Keypair,
     recent_blockhash: &Hash,
 ) -> Result<(Keypair, Keypair), TransportError> {
+    let pool_mint = Keypair::new();
+    let manager = create_mint_at(banks_client, payer, recent_blockhash, &pool_mint).await?;
+    Ok((pool_mint, manager))
+}
+
+/// Creates a mint at an address chosen ahead of time, returning its mint authority.
+pub async fn create_mint_at(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    pool_mint: &Keypair,
+) -> Result<Keypair, TransportError> {
     let rent = banks_client.get_rent().await.unwrap();
     let mint_rent = rent.minimum_balance(spl_token::state::Mint::LEN);
-    let pool_mint = Keypair::new();
     let manager = Keypair::new();
     let mut transaction = Transaction::new_with_payer(
         &[
@@ -60,9 +83,9 @@ pub async fn create_mint(
         ],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[payer, &pool_mint], *recent_blockhash);
+    transaction.sign(&[payer, pool_mint], *recent_blockhash);
     banks_client.process_transaction(transaction).await?;
-    Ok((pool_mint, manager))
+    Ok(manager)
 }
 
 pub async fn create_token_account(
@@ -100,6 +123,42 @@ pub async fn create_token_account(
     Ok(())
 }
 
+/// Creates the associated token account of `wallet` for `mint` and returns its address.
+pub async fn create_associated_token_account(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Pubkey, TransportError> {
+    let ata_program_id = Pubkey::from_str("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL").unwrap();
+    let (ata, _) = Pubkey::find_program_address(
+        &[wallet.as_ref(), spl_token::id().as_ref(), mint.as_ref()],
+        &ata_program_id,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: ata_program_id,
+            accounts: vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(ata, false),
+                AccountMeta::new_readonly(*wallet, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+            ],
+            data: vec![],
+        }],
+        Some(&payer.pubkey()),
+        &[payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(ata)
+}
+
 pub async fn mint_tokens(
     banks_client: &mut BanksClient,
     payer: &Keypair,
@@ -127,6 +186,33 @@ pub async fn mint_tokens(
     Ok(())
 }
 
+pub async fn transfer_tokens(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    source: &Pubkey,
+    destination: &Pubkey,
+    owner: &Keypair,
+    amount: u64,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[spl_token::instruction::transfer(
+            &spl_token::id(),
+            source,
+            destination,
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+        &[payer, owner],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
 pub async fn get_token_balance(banks_client: &mut BanksClient, token: &Pubkey) -> u64 {
     let token_account = banks_client.get_account(*token).await.unwrap().unwrap();
     let account_info: spl_token::state::Account =
@@ -149,31 +235,42 @@ pub async fn create_auction(
     recent_blockhash: &Hash,
     resource: &Pubkey,
     mint_keypair: &Pubkey,
-    max_winners: usize,
+    winners: WinnerLimit,
     name: &str,
-    instant_sale_price: Option<u64>,
     price_floor: PriceFloor,
     gap_tick_size_percentage: Option<u8>,
     tick_size: Option<u64>,
+    end_auction_at: Option<UnixTimestamp>,
+    settings: AuctionExtendedSettings,
 ) -> Result<(), TransportError> {
     let transaction: Transaction;
-    if instant_sale_price.is_some() {
+    if settings != AuctionExtendedSettings::default() {
         transaction = Transaction::new_signed_with_payer(
             &[instruction::create_auction_instruction_v2(
                 *program_id,
                 payer.pubkey(),
                 CreateAuctionArgsV2 {
                     authority: payer.pubkey(),
-                    end_auction_at: None,
+                    end_auction_at,
                     end_auction_gap: None,
                     resource: *resource,
                     token_mint: *mint_keypair,
-                    winners: WinnerLimit::Capped(max_winners),
+                    winners,
                     price_floor,
                     gap_tick_size_percentage,
                     tick_size,
                     name: Some(string_to_array(name)?),
-                    instant_sale_price,
+                    instant_sale_price: settings.instant_sale_price,
+                    dutch_auction_schedule: settings.dutch_auction_schedule,
+                    sealed_bid_config: settings.sealed_bid_config,
+                    settlement_mode: settings.settlement_mode,
+                    start_auction_at: settings.start_auction_at,
+                    bid_gate: settings.bid_gate,
+                    raffle_config: settings.raffle_config,
+                    bid_book: settings.bid_book,
+                    payment_mints: settings.payment_mints,
+                    lamport_bids: settings.lamport_bids,
+                    cancellation_rules: settings.cancellation_rules,
                 },
             )],
             Some(&payer.pubkey()),
@@ -187,11 +284,11 @@ pub async fn create_auction(
                 payer.pubkey(),
                 CreateAuctionArgs {
                     authority: payer.pubkey(),
-                    end_auction_at: None,
+                    end_auction_at,
                     end_auction_gap: None,
                     resource: *resource,
                     token_mint: *mint_keypair,
-                    winners: WinnerLimit::Capped(max_winners),
+                    winners,
                     price_floor,
                     gap_tick_size_percentage,
                     tick_size,
@@ -206,6 +303,32 @@ pub async fn create_auction(
     Ok(())
 }
 
+pub async fn create_bid_book(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    bid_book: &Keypair,
+    max: usize,
+) -> Result<(), TransportError> {
+    let rent = banks_client.get_rent().await.unwrap();
+    let space = BidBook::len_for(max);
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::create_account(
+            &payer.pubkey(),
+            &bid_book.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            program_id,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, bid_book],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
 pub async fn end_auction(
     banks_client: &mut BanksClient,
     program_id: &Pubkey,
@@ -217,6 +340,7 @@ pub async fn end_auction(
         &[instruction::end_auction_instruction(
             *program_id,
             payer.pubkey(),
+            None,
             EndAuctionArgs {
                 resource: *resource,
                 reveal: None,
@@ -230,6 +354,104 @@ pub async fn end_auction(
     Ok(())
 }
 
+pub async fn cancel_auction(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    recent_blockhash: &Hash,
+    payer: &Keypair,
+    resource: &Pubkey,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::cancel_auction_instruction(
+            *program_id,
+            payer.pubkey(),
+            CancelAuctionArgs {
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+pub async fn start_vault_buyout(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    recent_blockhash: &Hash,
+    payer: &Keypair,
+    vault: &Pubkey,
+    fraction_mint: &Pubkey,
+    fraction_treasury: &Pubkey,
+    redeem_treasury: &Pubkey,
+    external_pricing: &Pubkey,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::start_vault_buyout_instruction(
+            *program_id,
+            payer.pubkey(),
+            *fraction_mint,
+            *fraction_treasury,
+            *redeem_treasury,
+            *external_pricing,
+            StartVaultBuyoutArgs { vault: *vault },
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+pub async fn start_scheduled_auction(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    recent_blockhash: &Hash,
+    payer: &Keypair,
+    resource: &Pubkey,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::start_scheduled_auction_instruction(
+            *program_id,
+            StartScheduledAuctionArgs {
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+pub async fn draw_winners(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    recent_blockhash: &Hash,
+    payer: &Keypair,
+    resource: &Pubkey,
+    bid_ledger_pages: &[u64],
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::draw_winners_instruction(
+            *program_id,
+            bid_ledger_pages,
+            DrawWinnersArgs {
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
 pub async fn start_auction(
     banks_client: &mut BanksClient,
     program_id: &Pubkey,
@@ -253,6 +475,27 @@ pub async fn start_auction(
     Ok(())
 }
 
+pub async fn update_auction(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    recent_blockhash: &Hash,
+    payer: &Keypair,
+    args: UpdateAuctionArgs,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::update_auction_instruction(
+            *program_id,
+            payer.pubkey(),
+            args,
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn place_bid(
     banks_client: &mut BanksClient,
@@ -265,6 +508,8 @@ pub async fn place_bid(
     resource: &Pubkey,
     mint: &Pubkey,
     amount: u64,
+    proxy_bidder: Option<Pubkey>,
+    bid_ledger_page: Option<u64>,
 ) -> Result<(), TransportError> {
     let transaction = Transaction::new_signed_with_payer(
         &[instruction::place_bid_instruction(
@@ -275,6 +520,9 @@ pub async fn place_bid(
             *mint,           // Token Mint
             transfer_authority.pubkey(), // Approved to Move Tokens
             payer.pubkey(),  // Pays for Transactions
+            proxy_bidder,    // Current proxy bidder, if any
+            bid_ledger_page, // Open-edition bid ledger page, if any
+            None,            // Bid book, bid-book auctions only
             PlaceBidArgs {
                 amount,
                 resource: *resource,
@@ -288,6 +536,184 @@ pub async fn place_bid(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
+pub async fn place_gated_bid(
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    bidder: &Keypair,
+    bidder_spl_account: &Keypair,
+    transfer_authority: &Keypair,
+    resource: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    proof_token: &Pubkey,
+) -> Result<(), TransportError> {
+    let mut instruction = instruction::place_bid_instruction(
+        *program_id,
+        bidder.pubkey(),
+        bidder.pubkey(),
+        bidder_spl_account.pubkey(),
+        *mint,
+        transfer_authority.pubkey(),
+        payer.pubkey(),
+        None,
+        None,
+        None,
+        PlaceBidArgs {
+            amount,
+            resource: *resource,
+        },
+    );
+    instruction
+        .accounts
+        .extend(instruction::bid_gate_proof_accounts(*proof_token, None));
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[bidder, transfer_authority, payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn place_bid_on_book(
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    bidder: &Keypair,
+    bidder_spl_account: &Keypair,
+    transfer_authority: &Keypair,
+    resource: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    bid_book: &Pubkey,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::place_bid_instruction(
+            *program_id,
+            bidder.pubkey(),
+            bidder.pubkey(),
+            bidder_spl_account.pubkey(),
+            *mint,
+            transfer_authority.pubkey(),
+            payer.pubkey(),
+            None,
+            None,
+            Some(*bid_book),
+            PlaceBidArgs {
+                amount,
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[bidder, transfer_authority, payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+pub async fn sort_bid_book(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    recent_blockhash: &Hash,
+    payer: &Keypair,
+    resource: &Pubkey,
+    bid_book: &Pubkey,
+    steps: u64,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::sort_bid_book_instruction(
+            *program_id,
+            *bid_book,
+            SortBidBookArgs {
+                resource: *resource,
+                steps,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn increase_bid(
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    bidder: &Keypair,
+    bidder_spl_account: &Keypair,
+    transfer_authority: &Keypair,
+    resource: &Pubkey,
+    amount: u64,
+    proxy_bidder: Option<Pubkey>,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::increase_bid_instruction(
+            *program_id,
+            bidder.pubkey(),
+            bidder.pubkey(),
+            bidder_spl_account.pubkey(),
+            transfer_authority.pubkey(),
+            proxy_bidder,
+            IncreaseBidArgs {
+                amount,
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[bidder, transfer_authority, payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn set_proxy_bid(
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    bidder: &Keypair,
+    bidder_spl_account: &Keypair,
+    transfer_authority: &Keypair,
+    resource: &Pubkey,
+    max_amount: u64,
+    proxy_bidder: Option<Pubkey>,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_proxy_bid_instruction(
+            *program_id,
+            bidder.pubkey(),
+            bidder.pubkey(),
+            bidder_spl_account.pubkey(),
+            transfer_authority.pubkey(),
+            payer.pubkey(),
+            proxy_bidder,
+            SetProxyBidArgs {
+                max_amount,
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[bidder, transfer_authority, payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn cancel_bid(
     banks_client: &mut BanksClient,
@@ -298,6 +724,7 @@ pub async fn cancel_bid(
     bidder_spl_account: &Keypair,
     resource: &Pubkey,
     mint: &Pubkey,
+    bid_book: Option<Pubkey>,
 ) -> Result<(), TransportError> {
     let transaction = Transaction::new_signed_with_payer(
         &[instruction::cancel_bid_instruction(
@@ -306,6 +733,7 @@ pub async fn cancel_bid(
             bidder.pubkey(),
             bidder_spl_account.pubkey(),
             *mint,
+            bid_book,
             CancelBidArgs {
                 resource: *resource,
             },
@@ -318,6 +746,181 @@ pub async fn cancel_bid(
     Ok(())
 }
 
+pub async fn refund_bid(
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    resource: &Pubkey,
+    mint: &Pubkey,
+    bidders: &[(Pubkey, Pubkey)],
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::refund_bid_instruction(
+            *program_id,
+            *mint,
+            bidders,
+            None,
+            RefundBidArgs {
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+pub async fn close_bidder_pot(
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    bidder: &Keypair,
+    bidder_spl_account: &Keypair,
+    resource: &Pubkey,
+    authority: Option<&Keypair>,
+) -> Result<(), TransportError> {
+    let mut signers = vec![bidder, payer];
+    signers.extend(authority);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_bidder_pot_instruction(
+            *program_id,
+            bidder.pubkey(),
+            bidder_spl_account.pubkey(),
+            payer.pubkey(),
+            authority.map(|authority| authority.pubkey()),
+            CloseBidderPotArgs {
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &signers,
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+pub async fn close_auction(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    recent_blockhash: &Hash,
+    payer: &Keypair,
+    resource: &Pubkey,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_auction_instruction(
+            *program_id,
+            payer.pubkey(),
+            payer.pubkey(),
+            CloseAuctionArgs {
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+pub async fn migrate_auction(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    recent_blockhash: &Hash,
+    payer: &Keypair,
+    authority: &Keypair,
+    resource: &Pubkey,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::migrate_auction_instruction(
+            *program_id,
+            authority.pubkey(),
+            MigrateAuctionArgs {
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[payer, authority],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn commit_bid(
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    bidder: &Keypair,
+    bidder_spl_account: &Keypair,
+    transfer_authority: &Keypair,
+    resource: &Pubkey,
+    mint: &Pubkey,
+    commitment: Hash,
+    deposit: u64,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::commit_bid_instruction(
+            *program_id,
+            bidder.pubkey(),
+            bidder.pubkey(),
+            bidder_spl_account.pubkey(),
+            *mint,
+            transfer_authority.pubkey(),
+            payer.pubkey(),
+            CommitBidArgs {
+                commitment,
+                deposit,
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[bidder, transfer_authority, payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn reveal_bid(
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    bidder: &Keypair,
+    bidder_spl_account: &Keypair,
+    resource: &Pubkey,
+    amount: u64,
+    salt: u64,
+) -> Result<(), TransportError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::reveal_bid_instruction(
+            *program_id,
+            bidder.pubkey(),
+            bidder.pubkey(),
+            bidder_spl_account.pubkey(),
+            RevealBidArgs {
+                amount,
+                salt,
+                resource: *resource,
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[bidder, payer],
+        *recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+    Ok(())
+}
+
 pub async fn approve(
     banks_client: &mut BanksClient,
     recent_blockhash: &Hash,
@@ -356,6 +959,8 @@ pub async fn claim_bid(
     seller: &Pubkey,
     resource: &Pubkey,
     mint: &Pubkey,
+    bidder_token: Option<Pubkey>,
+    bid_book: Option<Pubkey>,
 ) -> Result<(), TransportError> {
     let transaction = Transaction::new_signed_with_payer(
         &[instruction::claim_bid_instruction(
@@ -365,7 +970,8 @@ pub async fn claim_bid(
             bidder.pubkey(),
             bidder_spl_account.pubkey(),
             *mint,
-            None,
+            bidder_token,
+            bid_book,
             ClaimBidArgs {
                 resource: *resource,
             },
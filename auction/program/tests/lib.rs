@@ -1,7 +1,10 @@
 #![allow(warnings)]
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{borsh::try_from_slice_unchecked, instruction::InstructionError};
+use solana_program::{
+    borsh::try_from_slice_unchecked, clock::UnixTimestamp, instruction::InstructionError,
+    program_option::COption,
+};
 use solana_program_test::*;
 use solana_sdk::program_pack::Pack;
 use solana_sdk::{
@@ -18,24 +21,128 @@ use metaplex_auction::{
     errors::AuctionError,
     instruction,
     processor::{
-        process_instruction, AuctionData, AuctionState, Bid, BidState, BidderPot, CancelBidArgs,
-        CreateAuctionArgs, PlaceBidArgs, PriceFloor, StartAuctionArgs, WinnerLimit,
+        bid_commitment_hash, process_instruction, AuctionData, AuctionDataExtended, AuctionDataV2,
+        AuctionExtendedSettings, AuctionState, Bid, BidGate, BidLedgerPage, BidState, BidderPot,
+        CancelBidArgs, CancellationRules, ClaimBidArgs, CreateAuctionArgs, DutchAuctionSchedule,
+        PaymentMint, PaymentRate, PlaceBidArgs, PriceFloor, RaffleConfig, SealedBidConfig,
+        SettlementMode, StartAuctionArgs, UpdateAuctionArgs, WinnerLimit, AUCTION_DATA_V2_KEY,
+        BASE_AUCTION_DATA_SIZE, BID_LENGTH, LEGACY_AUCTION_DATA_EXTENDED_SIZE,
     },
-    PREFIX,
+    EXTENDED, LEDGER, PREFIX,
 };
+use metaplex_token_vault::state::{Key as VaultKey, Vault, VaultState, MAX_VAULT_V2_SIZE};
 use std::mem;
 
 mod helpers;
 
-/// Initialize an auction with a random resource, and generate bidders with tokens that can be used
-/// for testing.
-async fn setup_auction(
+/// Auction created by setup_auction, a started English auction without any of the optional
+/// settings unless a test asks for them.
+struct AuctionSetup<'a> {
     start: bool,
-    max_winners: usize,
-    instant_sale: Option<u64>,
+    winners: WinnerLimit,
     price_floor: PriceFloor,
     gap_tick_size_percentage: Option<u8>,
     tick_size: Option<u64>,
+    end_auction_at: Option<UnixTimestamp>,
+    bid_book: Option<&'a Keypair>,
+    settings: AuctionExtendedSettings,
+}
+
+impl<'a> AuctionSetup<'a> {
+    fn new(winners: WinnerLimit) -> Self {
+        AuctionSetup {
+            start: true,
+            winners,
+            price_floor: PriceFloor::None([0; 32]),
+            gap_tick_size_percentage: None,
+            tick_size: None,
+            end_auction_at: None,
+            bid_book: None,
+            settings: AuctionExtendedSettings::default(),
+        }
+    }
+
+    fn not_started(mut self) -> Self {
+        self.start = false;
+        self
+    }
+
+    fn price_floor(mut self, price_floor: PriceFloor) -> Self {
+        self.price_floor = price_floor;
+        self
+    }
+
+    fn gap_tick_size_percentage(mut self, gap_tick_size_percentage: u8) -> Self {
+        self.gap_tick_size_percentage = Some(gap_tick_size_percentage);
+        self
+    }
+
+    fn tick_size(mut self, tick_size: u64) -> Self {
+        self.tick_size = Some(tick_size);
+        self
+    }
+
+    fn end_auction_at(mut self, end_auction_at: UnixTimestamp) -> Self {
+        self.end_auction_at = Some(end_auction_at);
+        self
+    }
+
+    fn bid_book(mut self, bid_book: &'a Keypair) -> Self {
+        self.bid_book = Some(bid_book);
+        self.settings.bid_book = Some(bid_book.pubkey());
+        self
+    }
+
+    fn instant_sale_price(mut self, instant_sale_price: u64) -> Self {
+        self.settings.instant_sale_price = Some(instant_sale_price);
+        self
+    }
+
+    fn dutch_auction_schedule(mut self, schedule: DutchAuctionSchedule) -> Self {
+        self.settings.dutch_auction_schedule = Some(schedule);
+        self
+    }
+
+    fn sealed_bid_config(mut self, config: SealedBidConfig) -> Self {
+        self.settings.sealed_bid_config = Some(config);
+        self
+    }
+
+    fn settlement_mode(mut self, settlement_mode: SettlementMode) -> Self {
+        self.settings.settlement_mode = Some(settlement_mode);
+        self
+    }
+
+    fn start_auction_at(mut self, start_auction_at: UnixTimestamp) -> Self {
+        self.settings.start_auction_at = Some(start_auction_at);
+        self
+    }
+
+    fn bid_gate(mut self, bid_gate: BidGate) -> Self {
+        self.settings.bid_gate = Some(bid_gate);
+        self
+    }
+
+    fn raffle_config(mut self, config: RaffleConfig) -> Self {
+        self.settings.raffle_config = Some(config);
+        self
+    }
+
+    fn payment_mints(mut self, payment_mints: Vec<PaymentMint>) -> Self {
+        self.settings.payment_mints = Some(payment_mints);
+        self
+    }
+
+    fn cancellation_rules(mut self, rules: CancellationRules) -> Self {
+        self.settings.cancellation_rules = Some(rules);
+        self
+    }
+}
+
+/// Initialize an auction with a random resource, and generate bidders with tokens that can be used
+/// for testing.
+async fn setup_auction(
+    setup: AuctionSetup<'_>,
 ) -> (
     Pubkey,
     BanksClient,
@@ -55,31 +162,82 @@ async fn setup_auction(
     // Start executing test.
     let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
 
+    let (bidders, resource, mint, mint_manager, auction_pubkey) = create_test_auction(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &recent_blockhash,
+        setup,
+    )
+    .await;
+
+    (
+        program_id,
+        banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_manager,
+        auction_pubkey,
+        recent_blockhash,
+    )
+}
+
+/// Creates the auction and bidders of setup_auction on a test that is already running, for tests
+/// that need the ProgramTestContext.
+async fn create_test_auction(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    setup: AuctionSetup<'_>,
+) -> (
+    Vec<(Keypair, Keypair, Pubkey)>,
+    Pubkey,
+    Pubkey,
+    Pubkey,
+    Pubkey,
+) {
     // Create a Token mint to mint some test tokens with.
-    let (mint_keypair, mint_manager) =
-        helpers::create_mint(&mut banks_client, &payer, &recent_blockhash)
-            .await
-            .unwrap();
+    let (mint_keypair, mint_manager) = helpers::create_mint(banks_client, payer, recent_blockhash)
+        .await
+        .unwrap();
 
     // Derive Auction PDA account for lookup.
     let resource = Pubkey::new_unique();
-    let seeds = &[PREFIX.as_bytes(), &program_id.as_ref(), resource.as_ref()];
-    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+    let seeds = &[PREFIX.as_bytes(), program_id.as_ref(), resource.as_ref()];
+    let (auction_pubkey, _) = Pubkey::find_program_address(seeds, program_id);
+
+    // Bid books are allocated by the auction creator, sized for every winner.
+    if let (Some(bid_book), WinnerLimit::Capped(max)) = (setup.bid_book, &setup.winners) {
+        helpers::create_bid_book(
+            banks_client,
+            program_id,
+            payer,
+            recent_blockhash,
+            bid_book,
+            *max,
+        )
+        .await
+        .unwrap();
+    }
 
     // Run Create Auction instruction.
     let err = helpers::create_auction(
-        &mut banks_client,
-        &program_id,
-        &payer,
-        &recent_blockhash,
+        banks_client,
+        program_id,
+        payer,
+        recent_blockhash,
         &resource,
         &mint_keypair.pubkey(),
-        max_winners,
+        setup.winners,
         "Some name",
-        instant_sale,
-        price_floor,
-        gap_tick_size_percentage,
-        tick_size,
+        setup.price_floor,
+        setup.gap_tick_size_percentage,
+        setup.tick_size,
+        setup.end_auction_at,
+        setup.settings,
     )
     .await
     .unwrap();
@@ -94,9 +252,9 @@ async fn setup_auction(
 
         // Generate User SPL Wallet Account
         helpers::create_token_account(
-            &mut banks_client,
-            &payer,
-            &recent_blockhash,
+            banks_client,
+            payer,
+            recent_blockhash,
             &bidder,
             &mint_keypair.pubkey(),
             &payer.pubkey(),
@@ -112,14 +270,14 @@ async fn setup_auction(
                 auction_pubkey.as_ref(),
                 bidder.pubkey().as_ref(),
             ],
-            &program_id,
+            program_id,
         );
 
         // Generate Auction SPL Pot to Transfer to.
         helpers::create_token_account(
-            &mut banks_client,
-            &payer,
-            &recent_blockhash,
+            banks_client,
+            payer,
+            recent_blockhash,
             &auction_spl_pot,
             &mint_keypair.pubkey(),
             &auction_pubkey,
@@ -129,9 +287,9 @@ async fn setup_auction(
 
         // Mint Tokens
         helpers::mint_tokens(
-            &mut banks_client,
-            &payer,
-            &recent_blockhash,
+            banks_client,
+            payer,
+            recent_blockhash,
             &mint_keypair.pubkey(),
             &bidder.pubkey(),
             &mint_manager,
@@ -144,7 +302,7 @@ async fn setup_auction(
     }
 
     // Verify Auction was created as expected.
-    let auction: AuctionData = try_from_slice_unchecked(
+    let auction = AuctionData::from_bytes(
         &banks_client
             .get_account(auction_pubkey)
             .await
@@ -157,32 +315,22 @@ async fn setup_auction(
     assert_eq!(auction.authority, payer.pubkey());
     assert_eq!(auction.last_bid, None);
     assert_eq!(auction.state as i32, AuctionState::create() as i32);
-    assert_eq!(auction.end_auction_at, None);
+    assert_eq!(auction.end_auction_at, setup.end_auction_at);
 
     // Start Auction.
-    if start {
-        helpers::start_auction(
-            &mut banks_client,
-            &program_id,
-            &recent_blockhash,
-            &payer,
-            &resource,
-        )
-        .await
-        .unwrap();
+    if setup.start {
+        helpers::start_auction(banks_client, program_id, recent_blockhash, payer, &resource)
+            .await
+            .unwrap();
     }
 
-    return (
-        program_id,
-        banks_client,
+    (
         bidders,
-        payer,
         resource,
         mint_keypair.pubkey(),
         mint_manager.pubkey(),
         auction_pubkey,
-        recent_blockhash,
-    );
+    )
 }
 
 /// Used to drive tests in the functions below.
@@ -291,12 +439,9 @@ async fn test_correct_runs() {
             auction_pubkey,
             recent_blockhash,
         ) = setup_auction(
-            true,
-            strategy.max_winners,
-            None,
-            strategy.price_floor.clone(),
-            Some(0),
-            None,
+            AuctionSetup::new(WinnerLimit::Capped(strategy.max_winners))
+                .price_floor(strategy.price_floor.clone())
+                .gap_tick_size_percentage(0),
         )
         .await;
 
@@ -336,6 +481,8 @@ async fn test_correct_runs() {
                         &resource,
                         &mint,
                         amount,
+                        None,
+                        None,
                     )
                     .await
                     .expect("place_bid");
@@ -369,6 +516,7 @@ async fn test_correct_runs() {
                         &bidders[bidder].1,
                         &resource,
                         &mint,
+                        None,
                     )
                     .await
                     .expect("cancel_bid");
@@ -403,7 +551,7 @@ async fn test_correct_runs() {
                     .expect("end_auction");
 
                     // Assert Auction is actually in ended state.
-                    let auction: AuctionData = try_from_slice_unchecked(
+                    let auction = AuctionData::from_bytes(
                         &banks_client
                             .get_account(auction_pubkey)
                             .await
@@ -420,7 +568,7 @@ async fn test_correct_runs() {
 
         // Verify a bid was created, and Metadata for this bidder correctly reflects
         // the last bid as expected.
-        let auction: AuctionData = try_from_slice_unchecked(
+        let auction = AuctionData::from_bytes(
             &banks_client
                 .get_account(auction_pubkey)
                 .await
@@ -477,6 +625,8 @@ async fn test_correct_runs() {
                             &collection.pubkey(),
                             &resource,
                             &mint,
+                            None,
+                            None,
                         )
                         .await;
                         println!("{:?}", err);
@@ -544,6 +694,8 @@ async fn handle_failing_action(
                 &resource,
                 &mint,
                 amount,
+                None,
+                None,
             )
             .await?;
 
@@ -572,6 +724,7 @@ async fn handle_failing_action(
                 &bidders[bidder].1,
                 &resource,
                 &mint,
+                None,
             )
             .await?;
 
@@ -602,14 +755,15 @@ async fn handle_failing_action(
             .await?;
 
             // Assert Auction is actually in ended state.
-            let auction: AuctionData = try_from_slice_unchecked(
+            let auction = AuctionData::from_bytes(
                 &banks_client
                     .get_account(*auction_pubkey)
                     .await
                     .expect("get_account")
                     .expect("account not found")
                     .data,
-            )?;
+            )
+            .expect("auction");
 
             assert!(auction.ended_at.is_some());
         }
@@ -676,14 +830,12 @@ async fn test_incorrect_runs() {
             mint_authority,
             auction_pubkey,
             recent_blockhash,
-        ) = setup_auction(
-            true,
-            strategy.max_winners,
-            None,
-            strategy.price_floor.clone(),
-            strategy.gap_tick_size_percentage,
-            strategy.tick_size,
-        )
+        ) = setup_auction(AuctionSetup {
+            gap_tick_size_percentage: strategy.gap_tick_size_percentage,
+            tick_size: strategy.tick_size,
+            ..AuctionSetup::new(WinnerLimit::Capped(strategy.max_winners))
+                .price_floor(strategy.price_floor.clone())
+        })
         .await;
 
         let mut failed = false;
@@ -726,12 +878,9 @@ async fn test_place_instant_sale_bid() {
         auction_pubkey,
         recent_blockhash,
     ) = setup_auction(
-        true,
-        1,
-        Some(instant_sale_price),
-        PriceFloor::None([0; 32]),
-        Some(0),
-        None,
+        AuctionSetup::new(WinnerLimit::Capped(1))
+            .instant_sale_price(instant_sale_price)
+            .gap_tick_size_percentage(0),
     )
     .await;
 
@@ -765,6 +914,8 @@ async fn test_place_instant_sale_bid() {
         &resource,
         &mint,
         bid_price,
+        None,
+        None,
     )
     .await
     .expect("place_bid");
@@ -815,14 +966,15 @@ async fn test_all_bids_are_taken_by_instant_sale_price() {
         mint_authority,
         auction_pubkey,
         recent_blockhash,
-    ) = setup_auction(
-        true,
-        strategy.max_winners,
-        strategy.instant_sale_price,
-        strategy.price_floor,
-        Some(0),
-        None,
-    )
+    ) = setup_auction(AuctionSetup {
+        settings: AuctionExtendedSettings {
+            instant_sale_price: strategy.instant_sale_price,
+            ..Default::default()
+        },
+        ..AuctionSetup::new(WinnerLimit::Capped(strategy.max_winners))
+            .price_floor(strategy.price_floor)
+            .gap_tick_size_percentage(0)
+    })
     .await;
 
     // Interpret test actions one by one.
@@ -861,6 +1013,8 @@ async fn test_all_bids_are_taken_by_instant_sale_price() {
                     &resource,
                     &mint,
                     amount,
+                    None,
+                    None,
                 )
                 .await
                 .expect("place_bid");
@@ -879,7 +1033,7 @@ async fn test_all_bids_are_taken_by_instant_sale_price() {
         }
     }
 
-    let auction: AuctionData = try_from_slice_unchecked(
+    let auction = AuctionData::from_bytes(
         &banks_client
             .get_account(auction_pubkey)
             .await
@@ -928,12 +1082,9 @@ async fn test_claim_bid_with_instant_sale_price() {
         auction_pubkey,
         recent_blockhash,
     ) = setup_auction(
-        true,
-        5,
-        Some(instant_sale_price),
-        PriceFloor::None([0; 32]),
-        Some(0),
-        None,
+        AuctionSetup::new(WinnerLimit::Capped(5))
+            .instant_sale_price(instant_sale_price)
+            .gap_tick_size_percentage(0),
     )
     .await;
 
@@ -961,6 +1112,8 @@ async fn test_claim_bid_with_instant_sale_price() {
         &resource,
         &mint,
         instant_sale_price,
+        None,
+        None,
     )
     .await
     .expect("place_bid");
@@ -990,6 +1143,8 @@ async fn test_claim_bid_with_instant_sale_price() {
         &collection.pubkey(),
         &resource,
         &mint,
+        None,
+        None,
     )
     .await
     .unwrap();
@@ -1034,14 +1189,15 @@ async fn test_cancel_bid_with_instant_sale_price() {
         mint_authority,
         auction_pubkey,
         recent_blockhash,
-    ) = setup_auction(
-        true,
-        strategy.max_winners,
-        strategy.instant_sale_price,
-        strategy.price_floor,
-        Some(0),
-        None,
-    )
+    ) = setup_auction(AuctionSetup {
+        settings: AuctionExtendedSettings {
+            instant_sale_price: strategy.instant_sale_price,
+            ..Default::default()
+        },
+        ..AuctionSetup::new(WinnerLimit::Capped(strategy.max_winners))
+            .price_floor(strategy.price_floor)
+            .gap_tick_size_percentage(0)
+    })
     .await;
 
     // Interpret test actions one by one.
@@ -1080,6 +1236,8 @@ async fn test_cancel_bid_with_instant_sale_price() {
                     &resource,
                     &mint,
                     amount,
+                    None,
+                    None,
                 )
                 .await
                 .expect("place_bid");
@@ -1112,6 +1270,7 @@ async fn test_cancel_bid_with_instant_sale_price() {
                     &bidders[bidder].1,
                     &resource,
                     &mint,
+                    None,
                 )
                 .await
                 .unwrap_err()
@@ -1129,3 +1288,3379 @@ async fn test_cancel_bid_with_instant_sale_price() {
         }
     }
 }
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_dutch_auction_sells_at_asking_price() {
+    let schedule = DutchAuctionSchedule {
+        start_price: 5000,
+        floor_price: 1000,
+        decay_interval: 100,
+    };
+
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(
+        AuctionSetup::new(WinnerLimit::Capped(1))
+            .end_auction_at(1000)
+            .dutch_auction_schedule(schedule),
+    )
+    .await;
+
+    // Bids below the asking price are rejected, bids above it only pay the asking price.
+    let attempts = [(0, 4000, false), (1, 6000, true), (2, 6000, false)];
+    for &(bidder, amount, accepted) in attempts.iter() {
+        let pre_balance =
+            helpers::get_token_balance(&mut banks_client, &bidders[bidder].0.pubkey()).await;
+
+        let transfer_authority = Keypair::new();
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            amount,
+        )
+        .await
+        .expect("approve");
+
+        let result = helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            amount,
+            None,
+            None,
+        )
+        .await;
+        assert_eq!(result.is_ok(), accepted);
+
+        let post_balance =
+            helpers::get_token_balance(&mut banks_client, &bidders[bidder].0.pubkey()).await;
+        if accepted {
+            assert_eq!(post_balance, pre_balance - 5000);
+        } else {
+            assert_eq!(post_balance, pre_balance);
+        }
+    }
+
+    // The single lot was taken, so the auction ended with the first bidder at the asking price.
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+
+    assert_eq!(auction.state, AuctionState::Ended);
+    assert_eq!(auction.winner_at(0), Some(bidders[1].0.pubkey()));
+    assert_eq!(auction.bid_state.amount(0), 5000);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_sealed_bid_auction_takes_commitments_only() {
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(
+        AuctionSetup::new(WinnerLimit::Capped(1))
+            .end_auction_at(100_000)
+            .sealed_bid_config(SealedBidConfig {
+                reveal_window: 1000,
+                forfeit_unrevealed: true,
+            }),
+    )
+    .await;
+
+    let (bidder, pot) = (&bidders[0].0, &bidders[0].1);
+    let pre_balance = helpers::get_token_balance(&mut banks_client, &bidder.pubkey()).await;
+    let commitment = bid_commitment_hash(5000, 42, &bidder.pubkey());
+
+    let transfer_authority = Keypair::new();
+    helpers::approve(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &transfer_authority.pubkey(),
+        bidder,
+        16000,
+    )
+    .await
+    .expect("approve");
+
+    // The deposit is taken up front, hiding the real bid.
+    helpers::commit_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        bidder,
+        pot,
+        &transfer_authority,
+        &resource,
+        &mint,
+        commitment,
+        8000,
+    )
+    .await
+    .expect("commit_bid");
+
+    let post_balance = helpers::get_token_balance(&mut banks_client, &bidder.pubkey()).await;
+    assert_eq!(post_balance, pre_balance - 8000);
+    let pot_balance = helpers::get_token_balance(&mut banks_client, &pot.pubkey()).await;
+    assert_eq!(pot_balance, 8000);
+
+    // Open bids, a second commitment and early reveals are all rejected.
+    let result = helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        bidder,
+        pot,
+        &transfer_authority,
+        &resource,
+        &mint,
+        5000,
+        None,
+        None,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = helpers::commit_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        bidder,
+        pot,
+        &transfer_authority,
+        &resource,
+        &mint,
+        commitment,
+        8000,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = helpers::reveal_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        bidder,
+        pot,
+        &resource,
+        5000,
+        42,
+    )
+    .await;
+    assert!(result.is_err());
+
+    // Nothing is placed into the auction until bids are revealed.
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+
+    assert_eq!(auction.state, AuctionState::Started);
+    assert_eq!(auction.winner_at(0), None);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_sealed_bid_auction_rejects_tick_size() {
+    let program_id = Pubkey::new_unique();
+    let program_test =
+        ProgramTest::new("metaplex_auction", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (mint_keypair, _) = helpers::create_mint(&mut banks_client, &payer, &recent_blockhash)
+        .await
+        .unwrap();
+
+    // Nobody sees the other bids, so there is nothing to outbid by a tick.
+    let err = helpers::create_auction(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &recent_blockhash,
+        &Pubkey::new_unique(),
+        &mint_keypair.pubkey(),
+        WinnerLimit::Capped(1),
+        "Some name",
+        PriceFloor::None([0; 32]),
+        None,
+        Some(100),
+        Some(100_000),
+        AuctionExtendedSettings {
+            sealed_bid_config: Some(SealedBidConfig {
+                reveal_window: 1000,
+                forfeit_unrevealed: true,
+            }),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::InvalidSealedBidSettings as u32)
+        )
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_second_price_auction_charges_next_bid() {
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(
+        AuctionSetup::new(WinnerLimit::Capped(1)).settlement_mode(SettlementMode::SecondPrice),
+    )
+    .await;
+
+    for &(bidder, amount) in [(0, 1000), (1, 3000), (2, 2000)].iter() {
+        let transfer_authority = Keypair::new();
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            amount,
+        )
+        .await
+        .expect("approve");
+
+        helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            amount,
+            None,
+            None,
+        )
+        .await
+        .expect("place_bid");
+    }
+
+    helpers::end_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("end_auction");
+
+    // The winner's refund goes to a token account of their own.
+    let (collection, refund) = (Keypair::new(), Keypair::new());
+    for &(account, owner) in [
+        (&collection, payer.pubkey()),
+        (&refund, bidders[1].0.pubkey()),
+    ]
+    .iter()
+    {
+        helpers::create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            account,
+            &mint,
+            &owner,
+        )
+        .await
+        .unwrap();
+    }
+
+    helpers::claim_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &collection.pubkey(),
+        &resource,
+        &mint,
+        Some(refund.pubkey()),
+        None,
+    )
+    .await
+    .expect("claim_bid");
+
+    // The winner pays the runner up's bid and gets the rest of their own back.
+    let balance = helpers::get_token_balance(&mut banks_client, &collection.pubkey()).await;
+    assert_eq!(balance, 2000);
+
+    let balance = helpers::get_token_balance(&mut banks_client, &refund.pubkey()).await;
+    assert_eq!(balance, 1000);
+
+    let balance = helpers::get_token_balance(&mut banks_client, &bidders[1].1.pubkey()).await;
+    assert_eq!(balance, 0);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_increase_bid_reranks_in_place() {
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(AuctionSetup::new(WinnerLimit::Capped(2)).tick_size(100)).await;
+
+    let transfer_authority = Keypair::new();
+    for &(bidder, amount) in [(0, 1000), (1, 2000)].iter() {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            amount * 2,
+        )
+        .await
+        .expect("approve");
+
+        helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            amount,
+            None,
+            None,
+        )
+        .await
+        .expect("place_bid");
+    }
+
+    let pre_balance = helpers::get_token_balance(&mut banks_client, &bidders[0].0.pubkey()).await;
+
+    // Lower bids and bids off the tick size are rejected.
+    for &amount in [900, 2050].iter() {
+        let result = helpers::increase_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[0].0,
+            &bidders[0].1,
+            &transfer_authority,
+            &resource,
+            amount,
+            None,
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    helpers::increase_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        2500,
+        None,
+    )
+    .await
+    .expect("increase_bid");
+
+    // Only the difference was taken, and the raised bid now leads.
+    let post_balance = helpers::get_token_balance(&mut banks_client, &bidders[0].0.pubkey()).await;
+    assert_eq!(post_balance, pre_balance - 1500);
+
+    let balance = helpers::get_token_balance(&mut banks_client, &bidders[0].1.pubkey()).await;
+    assert_eq!(balance, 2500);
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+
+    assert_eq!(auction.winner_at(0), Some(bidders[0].0.pubkey()));
+    assert_eq!(auction.bid_state.amount(0), 2500);
+    assert_eq!(auction.winner_at(1), Some(bidders[1].0.pubkey()));
+    assert_eq!(auction.bid_state.amount(1), 2000);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_proxy_bid_counters_until_maximum() {
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(AuctionSetup::new(WinnerLimit::Capped(1)).tick_size(100)).await;
+
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+        &program_id,
+    );
+
+    let transfer_authority = Keypair::new();
+    for bidder in 0..2 {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            10000,
+        )
+        .await
+        .expect("approve");
+    }
+
+    helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1000,
+        None,
+        None,
+    )
+    .await
+    .expect("place_bid");
+
+    helpers::set_proxy_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        3000,
+        None,
+    )
+    .await
+    .expect("set_proxy_bid");
+
+    // The whole maximum is escrowed, only the standing bid shows in the auction.
+    let balance = helpers::get_token_balance(&mut banks_client, &bidders[0].1.pubkey()).await;
+    assert_eq!(balance, 3000);
+
+    // A competing bid has to bring the proxy bid along and is countered by one tick.
+    let err = helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1500,
+        None,
+        None,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::MissingProxyBid as u32)
+        )
+    );
+
+    helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1500,
+        Some(bidders[0].0.pubkey()),
+        None,
+    )
+    .await
+    .expect("place_bid");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction.winner_at(0), Some(bidders[0].0.pubkey()));
+    assert_eq!(auction.bid_state.amount(0), 1600);
+
+    // Outbidding the maximum exhausts the proxy bid.
+    helpers::increase_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &transfer_authority,
+        &resource,
+        3500,
+        Some(bidders[0].0.pubkey()),
+    )
+    .await
+    .expect("increase_bid");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction.winner_at(0), Some(bidders[1].0.pubkey()));
+    assert_eq!(auction.bid_state.amount(0), 3500);
+
+    let auction_extended: AuctionDataExtended = try_from_slice_unchecked(
+        &banks_client
+            .get_account(auction_extended_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction_extended.proxy_bidder, None);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_open_edition_bids_are_recorded_in_ledger() {
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(AuctionSetup::new(WinnerLimit::Unlimited(0))).await;
+
+    let transfer_authority = Keypair::new();
+    for bidder in 0..3 {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            1000,
+        )
+        .await
+        .expect("approve");
+    }
+
+    // Clients from before the ledger still bid without a page, their bids aren't recorded.
+    helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[2].0,
+        &bidders[2].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        50,
+        None,
+        None,
+    )
+    .await
+    .expect("place_bid");
+
+    for &(bidder, amount) in [(0, 100), (1, 200)].iter() {
+        helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            amount,
+            None,
+            Some(0),
+        )
+        .await
+        .expect("place_bid");
+    }
+
+    let (bid_ledger_page_pubkey, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            auction_pubkey.as_ref(),
+            LEDGER.as_bytes(),
+            &0u64.to_le_bytes(),
+        ],
+        &program_id,
+    );
+    let ledger_page: BidLedgerPage = try_from_slice_unchecked(
+        &banks_client
+            .get_account(bid_ledger_page_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+
+    assert_eq!(ledger_page.auction_pubkey, auction_pubkey);
+    assert_eq!(ledger_page.page, 0);
+    assert_eq!(ledger_page.entries.len(), 2);
+    assert_eq!(ledger_page.entries[0].bidder_pubkey, bidders[0].0.pubkey());
+    assert_eq!(ledger_page.entries[0].amount, 100);
+    assert_eq!(ledger_page.entries[1].bidder_pubkey, bidders[1].0.pubkey());
+    assert_eq!(ledger_page.entries[1].amount, 200);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_cancelled_auction_refunds_every_bid() {
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(AuctionSetup::new(WinnerLimit::Capped(1))).await;
+
+    let transfer_authority = Keypair::new();
+    for &(bidder, amount) in [(0, 1000), (1, 2000)].iter() {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            amount,
+        )
+        .await
+        .expect("approve");
+
+        helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            amount,
+            None,
+            None,
+        )
+        .await
+        .expect("place_bid");
+    }
+
+    helpers::cancel_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("cancel_auction");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+
+    assert_eq!(auction.state, AuctionState::Cancelled);
+    assert_eq!(auction.winner_at(0), None);
+
+    // A cancelled auction can be neither cancelled again nor bid on.
+    let result = helpers::cancel_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[2].0,
+        &bidders[2].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        3000,
+        None,
+        None,
+    )
+    .await;
+    assert!(result.is_err());
+
+    // The former winner gets their whole bid back.
+    let pre_balance = helpers::get_token_balance(&mut banks_client, &bidders[1].0.pubkey()).await;
+    helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &resource,
+        &mint,
+        None,
+    )
+    .await
+    .expect("cancel_bid");
+
+    let post_balance = helpers::get_token_balance(&mut banks_client, &bidders[1].0.pubkey()).await;
+    assert_eq!(post_balance, pre_balance + 2000);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_reserve_not_met_refunds_every_bid() {
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(
+        AuctionSetup::new(WinnerLimit::Capped(2))
+            .price_floor(PriceFloor::ReservePrice([5000, 0, 0, 0])),
+    )
+    .await;
+
+    // Bids below the reserve are still taken.
+    let transfer_authority = Keypair::new();
+    for &(bidder, amount) in [(0, 1000), (1, 2000)].iter() {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            amount,
+        )
+        .await
+        .expect("approve");
+
+        helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            amount,
+            None,
+            None,
+        )
+        .await
+        .expect("place_bid");
+    }
+
+    helpers::end_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("end_auction");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+
+    assert_eq!(auction.state, AuctionState::Ended);
+    assert_eq!(
+        auction.price_floor,
+        PriceFloor::ReserveNotMet([5000, 0, 0, 0])
+    );
+    assert_eq!(auction.num_winners(), 0);
+    assert_eq!(auction.winner_at(0), None);
+    assert_eq!(auction.is_winner(&bidders[1].0.pubkey()), None);
+
+    // The top bidder gets their whole bid back.
+    let pre_balance = helpers::get_token_balance(&mut banks_client, &bidders[1].0.pubkey()).await;
+    helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &resource,
+        &mint,
+        None,
+    )
+    .await
+    .expect("cancel_bid");
+
+    let post_balance = helpers::get_token_balance(&mut banks_client, &bidders[1].0.pubkey()).await;
+    assert_eq!(post_balance, pre_balance + 2000);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_refund_bid_returns_losing_bids() {
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(AuctionSetup::new(WinnerLimit::Capped(1))).await;
+
+    let transfer_authority = Keypair::new();
+    for &(bidder, amount) in [(0, 1000), (1, 2000), (2, 3000)].iter() {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            amount,
+        )
+        .await
+        .expect("approve");
+
+        helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            amount,
+            None,
+            None,
+        )
+        .await
+        .expect("place_bid");
+    }
+
+    let mut atas = vec![];
+    for bidder in bidders.iter().take(3) {
+        let ata = helpers::create_associated_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &bidder.0.pubkey(),
+            &mint,
+        )
+        .await
+        .expect("create_associated_token_account");
+        atas.push(ata);
+    }
+
+    let losers = vec![
+        (bidders[0].0.pubkey(), bidders[0].1.pubkey()),
+        (bidders[1].0.pubkey(), bidders[1].1.pubkey()),
+    ];
+
+    // Nothing is refunded while the auction is running.
+    let result = helpers::refund_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &resource,
+        &mint,
+        &losers,
+    )
+    .await;
+    assert!(result.is_err());
+
+    helpers::end_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("end_auction");
+
+    // Both losing bidders are refunded in a single transaction.
+    helpers::refund_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &resource,
+        &mint,
+        &losers,
+    )
+    .await
+    .expect("refund_bid");
+
+    for (ata, amount) in atas.iter().zip([1000, 2000].iter()) {
+        let balance = helpers::get_token_balance(&mut banks_client, ata).await;
+        assert_eq!(balance, *amount);
+    }
+    for bidder in bidders.iter().take(2) {
+        let balance = helpers::get_token_balance(&mut banks_client, &bidder.1.pubkey()).await;
+        assert_eq!(balance, 0);
+
+        let bidder_pot: BidderPot = try_from_slice_unchecked(
+            &banks_client
+                .get_account(bidder.2)
+                .await
+                .expect("get_account")
+                .expect("account not found")
+                .data,
+        )
+        .unwrap();
+        assert!(bidder_pot.emptied);
+    }
+
+    // The winner is not refunded.
+    let result = helpers::refund_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &resource,
+        &mint,
+        &[(bidders[2].0.pubkey(), bidders[2].1.pubkey())],
+    )
+    .await;
+    assert!(result.is_err());
+
+    let balance = helpers::get_token_balance(&mut banks_client, &atas[2]).await;
+    assert_eq!(balance, 0);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_close_accounts_once_bids_are_settled() {
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        mint_authority,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(AuctionSetup::new(WinnerLimit::Capped(1))).await;
+
+    let transfer_authority = Keypair::new();
+    for &(bidder, amount) in [(0, 1000), (1, 2000)].iter() {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            amount,
+        )
+        .await
+        .expect("approve");
+
+        helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            amount,
+            None,
+            None,
+        )
+        .await
+        .expect("place_bid");
+    }
+
+    helpers::end_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("end_auction");
+
+    // Neither the auction nor the unclaimed winning pot can be closed yet.
+    let result = helpers::close_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = helpers::close_bidder_pot(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &resource,
+        None,
+    )
+    .await;
+    assert!(result.is_err());
+
+    helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &resource,
+        &mint,
+        None,
+    )
+    .await
+    .expect("cancel_bid");
+
+    let collection = Keypair::new();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &collection,
+        &mint,
+        &payer.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    helpers::claim_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &collection.pubkey(),
+        &resource,
+        &mint,
+        None,
+        None,
+    )
+    .await
+    .expect("claim_bid");
+
+    // Metaplex redeems prizes off the metadata of the winner, only the authority can let it go.
+    let result = helpers::close_bidder_pot(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &resource,
+        Some(&bidders[1].0),
+    )
+    .await;
+    assert!(result.is_err());
+
+    for (bidder, authority) in bidders.iter().take(2).zip([None, Some(&payer)].iter()) {
+        helpers::close_bidder_pot(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidder.0,
+            &bidder.1,
+            &resource,
+            *authority,
+        )
+        .await
+        .expect("close_bidder_pot");
+
+        for closed in [bidder.1.pubkey(), bidder.2].iter() {
+            let account = banks_client
+                .get_account(*closed)
+                .await
+                .expect("get_account");
+            assert!(account.is_none());
+        }
+    }
+
+    helpers::close_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("close_auction");
+
+    let account = banks_client
+        .get_account(auction_pubkey)
+        .await
+        .expect("get_account");
+    assert!(account.is_none());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_scheduled_auction_starts_by_itself() {
+    // Scheduled far in the future, neither bids nor the crank can start it.
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        _,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(
+        AuctionSetup::new(WinnerLimit::Capped(1))
+            .not_started()
+            .start_auction_at(UnixTimestamp::MAX / 2),
+    )
+    .await;
+
+    let transfer_authority = Keypair::new();
+    helpers::approve(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &transfer_authority.pubkey(),
+        &bidders[0].0,
+        1000,
+    )
+    .await
+    .expect("approve");
+
+    let result = helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1000,
+        None,
+        None,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = helpers::start_scheduled_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction.state, AuctionState::Created);
+
+    // Once the start time has passed, the first bid starts the auction.
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        _,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(
+        AuctionSetup::new(WinnerLimit::Capped(1))
+            .not_started()
+            .start_auction_at(1),
+    )
+    .await;
+
+    let transfer_authority = Keypair::new();
+    helpers::approve(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &transfer_authority.pubkey(),
+        &bidders[0].0,
+        1000,
+    )
+    .await
+    .expect("approve");
+
+    helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1000,
+        None,
+        None,
+    )
+    .await
+    .expect("place_bid");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction.state, AuctionState::Started);
+    assert_eq!(auction.winner_at(0), Some(bidders[0].0.pubkey()));
+
+    // Without any bid, anyone can crank it into the started state.
+    let (program_id, mut banks_client, _, payer, resource, _, _, auction_pubkey, recent_blockhash) =
+        setup_auction(
+            AuctionSetup::new(WinnerLimit::Capped(1))
+                .not_started()
+                .start_auction_at(1),
+        )
+        .await;
+
+    helpers::start_scheduled_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("start_scheduled_auction");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction.state, AuctionState::Started);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_update_auction_before_start() {
+    let (program_id, mut banks_client, _, payer, resource, _, _, auction_pubkey, recent_blockhash) =
+        setup_auction(
+            AuctionSetup::new(WinnerLimit::Capped(1))
+                .not_started()
+                .end_auction_at(60),
+        )
+        .await;
+
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+        &program_id,
+    );
+
+    let update = UpdateAuctionArgs {
+        resource,
+        end_auction_at: Some(3600),
+        end_auction_gap: Some(60),
+        price_floor: PriceFloor::MinimumPrice([500, 0, 0, 0]),
+        tick_size: Some(10),
+        gap_tick_size_percentage: Some(5),
+        instant_sale_price: Some(10_000),
+        name: Some([1; 32]),
+    };
+
+    // Updates are held to the same rules as creation.
+    let result = helpers::update_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        UpdateAuctionArgs {
+            gap_tick_size_percentage: Some(101),
+            ..update.clone()
+        },
+    )
+    .await;
+    assert!(result.is_err());
+
+    helpers::update_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        update.clone(),
+    )
+    .await
+    .expect("update_auction");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction.end_auction_at, Some(3600));
+    assert_eq!(auction.end_auction_gap, Some(60));
+    assert_eq!(
+        auction.price_floor,
+        PriceFloor::MinimumPrice([500, 0, 0, 0])
+    );
+
+    let auction_extended: AuctionDataExtended = try_from_slice_unchecked(
+        &banks_client
+            .get_account(auction_extended_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction_extended.tick_size, Some(10));
+    assert_eq!(auction_extended.gap_tick_size_percentage, Some(5));
+    assert_eq!(auction_extended.instant_sale_price, Some(10_000));
+    assert_eq!(auction_extended.name, Some([1; 32]));
+
+    helpers::start_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("start_auction");
+
+    // Once started, the auction is locked in.
+    let result = helpers::update_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        UpdateAuctionArgs {
+            end_auction_at: Some(7200),
+            ..update
+        },
+    )
+    .await;
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_update_legacy_auction_extended() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test =
+        ProgramTest::new("metaplex_auction", program_id, processor!(process_instruction));
+    let authority = Keypair::new();
+
+    // Auctions created before the newer settings, with the auction extended account at its old
+    // size. The second has one of those settings, which it has no room for.
+    let legacy_extended = AuctionDataExtended {
+        total_uncancelled_bids: 0,
+        tick_size: None,
+        gap_tick_size_percentage: None,
+        instant_sale_price: None,
+        name: None,
+        dutch_auction_schedule: None,
+        sealed_bid_config: None,
+        settlement_mode: None,
+        proxy_bidder: None,
+        bid_ledger_entries: None,
+        payer: None,
+        open_bidder_pots: None,
+        start_auction_at: None,
+        bid_gate: None,
+        raffle_config: None,
+        raffle_seed: None,
+        raffle_draw_slot: None,
+        bid_book: None,
+        payment_mints: None,
+        lamport_bids: None,
+        cancellation_rules: None,
+    };
+    let resources = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let extended = [
+        legacy_extended.clone(),
+        AuctionDataExtended {
+            settlement_mode: Some(SettlementMode::SecondPrice),
+            ..legacy_extended.clone()
+        },
+    ];
+    for (resource, auction_extended) in resources.iter().zip(extended.iter()) {
+        let (auction_pubkey, _) = Pubkey::find_program_address(
+            &[PREFIX.as_bytes(), program_id.as_ref(), resource.as_ref()],
+            &program_id,
+        );
+        let auction = AuctionData {
+            authority: authority.pubkey(),
+            token_mint: Pubkey::new_unique(),
+            last_bid: None,
+            ended_at: None,
+            end_auction_at: None,
+            end_auction_gap: None,
+            price_floor: PriceFloor::None([0; 32]),
+            state: AuctionState::Created,
+            bid_state: BidState::new_english(1),
+        };
+        let mut data =
+            vec![0; BASE_AUCTION_DATA_SIZE + BID_LENGTH * BidState::max_array_size_for(1)];
+        auction.serialize(&mut data.as_mut_slice()).unwrap();
+        program_test.add_account(
+            auction_pubkey,
+            Account {
+                lamports: 1_000_000_000,
+                data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let (auction_extended_pubkey, _) = Pubkey::find_program_address(
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                resource.as_ref(),
+                EXTENDED.as_bytes(),
+            ],
+            &program_id,
+        );
+        let mut data = vec![0; LEGACY_AUCTION_DATA_EXTENDED_SIZE];
+        auction_extended
+            .serialize(&mut data.as_mut_slice())
+            .unwrap();
+        program_test.add_account(
+            auction_extended_pubkey,
+            Account {
+                lamports: 1_000_000_000,
+                data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let update = |resource: Pubkey| {
+        Transaction::new_signed_with_payer(
+            &[instruction::update_auction_instruction(
+                program_id,
+                authority.pubkey(),
+                UpdateAuctionArgs {
+                    resource,
+                    end_auction_at: Some(3600),
+                    end_auction_gap: None,
+                    price_floor: PriceFloor::None([0; 32]),
+                    tick_size: None,
+                    gap_tick_size_percentage: None,
+                    instant_sale_price: None,
+                    name: Some([1; 32]),
+                },
+            )],
+            Some(&payer.pubkey()),
+            &[&payer, &authority],
+            recent_blockhash,
+        )
+    };
+
+    banks_client
+        .process_transaction(update(resources[0]))
+        .await
+        .expect("update_auction");
+
+    let err = banks_client
+        .process_transaction(update(resources[1]))
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::LegacyAuctionDataExtended as u32)
+        )
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_gated_auction_only_takes_holder_bids() {
+    let gate_mint = Keypair::new();
+    let (
+        program_id,
+        mut banks_client,
+        bidders,
+        payer,
+        resource,
+        mint,
+        _,
+        auction_pubkey,
+        recent_blockhash,
+    ) = setup_auction(
+        AuctionSetup::new(WinnerLimit::Capped(2)).bid_gate(BidGate::Mint(gate_mint.pubkey())),
+    )
+    .await;
+
+    // Only the first bidder holds a gating token.
+    let gate_mint_authority =
+        helpers::create_mint_at(&mut banks_client, &payer, &recent_blockhash, &gate_mint)
+            .await
+            .unwrap();
+
+    let proof = Keypair::new();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &proof,
+        &gate_mint.pubkey(),
+        &bidders[0].0.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    helpers::mint_tokens(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &gate_mint.pubkey(),
+        &proof.pubkey(),
+        &gate_mint_authority,
+        1,
+    )
+    .await
+    .unwrap();
+
+    let transfer_authority = Keypair::new();
+    for bidder in 0..2 {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            1000,
+        )
+        .await
+        .expect("approve");
+    }
+
+    // Bids without a proof, or with someone else's, are turned away.
+    let result = helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1000,
+        None,
+        None,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = helpers::place_gated_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1000,
+        &proof.pubkey(),
+    )
+    .await;
+    assert!(result.is_err());
+
+    helpers::place_gated_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1000,
+        &proof.pubkey(),
+    )
+    .await
+    .expect("place_gated_bid");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction.winner_at(0), Some(bidders[0].0.pubkey()));
+    assert_eq!(auction.winner_at(1), None);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_raffle_draws_winners_from_tickets() {
+    // Winners are drawn from the hash of a later slot, which takes warping to.
+    let program_id = Pubkey::new_unique();
+    let mut context =
+        ProgramTest::new("metaplex_auction", program_id, processor!(process_instruction))
+            .start_with_context()
+            .await;
+    let mut banks_client = context.banks_client.clone();
+    let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+    let recent_blockhash = context.last_blockhash;
+    let (bidders, resource, mint, _, auction_pubkey) = create_test_auction(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &recent_blockhash,
+        AuctionSetup::new(WinnerLimit::Capped(1))
+            .end_auction_at(3600)
+            .raffle_config(RaffleConfig { ticket_price: 100 }),
+    )
+    .await;
+
+    let transfer_authority = Keypair::new();
+    for bidder in 0..4 {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            1000,
+        )
+        .await
+        .expect("approve");
+    }
+
+    // Tickets cost the ticket price, no less and no more.
+    let err = helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        50,
+        None,
+        Some(0),
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::BidTooSmall as u32)
+        )
+    );
+
+    let pre_balance = helpers::get_token_balance(&mut banks_client, &bidders[0].0.pubkey()).await;
+    for bidder in 0..4 {
+        helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            150,
+            None,
+            Some(0),
+        )
+        .await
+        .expect("place_bid");
+    }
+    let post_balance = helpers::get_token_balance(&mut banks_client, &bidders[0].0.pubkey()).await;
+    assert_eq!(pre_balance - post_balance, 100);
+
+    // Tickets stay in the draw until the winners are drawn.
+    let err = helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[3].0,
+        &bidders[3].1,
+        &resource,
+        &mint,
+        None,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::RaffleNotDrawn as u32)
+        )
+    );
+
+    helpers::end_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("end_auction");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction.state, AuctionState::Started);
+
+    // The first draw only commits to a slot ahead, nothing is drawn until it has passed.
+    helpers::draw_winners(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+        &[0],
+    )
+    .await
+    .expect("draw_winners");
+
+    let (auction_extended_pubkey, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            resource.as_ref(),
+            EXTENDED.as_bytes(),
+        ],
+        &program_id,
+    );
+    let auction_extended: AuctionDataExtended = try_from_slice_unchecked(
+        &banks_client
+            .get_account(auction_extended_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction_extended.raffle_seed, None);
+    let draw_slot = auction_extended.raffle_draw_slot.unwrap();
+
+    context.warp_to_slot(draw_slot).unwrap();
+    let recent_blockhash = banks_client.get_recent_blockhash().await.unwrap();
+    let err = helpers::draw_winners(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+        &[0],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::RaffleDrawSlotNotReached as u32)
+        )
+    );
+
+    context.warp_to_slot(draw_slot + 1).unwrap();
+    let recent_blockhash = banks_client.get_recent_blockhash().await.unwrap();
+    helpers::draw_winners(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+        &[0],
+    )
+    .await
+    .expect("draw_winners");
+
+    let auction = AuctionData::from_bytes(
+        &banks_client
+            .get_account(auction_pubkey)
+            .await
+            .expect("get_account")
+            .expect("account not found")
+            .data,
+    )
+    .unwrap();
+    assert_eq!(auction.state, AuctionState::Ended);
+    assert_eq!(auction.num_winners(), 1);
+    assert_eq!(auction.winner_at(1), None);
+
+    let winner = auction.winner_at(0).unwrap();
+    assert!(bidders[..4]
+        .iter()
+        .any(|bidder| bidder.0.pubkey() == winner));
+
+    // Losing tickets are refunded, the winning one is kept.
+    for bidder in 0..3 {
+        let result = helpers::cancel_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[bidder].0,
+            &bidders[bidder].1,
+            &resource,
+            &mint,
+            None,
+        )
+        .await;
+        assert_eq!(result.is_ok(), bidders[bidder].0.pubkey() != winner);
+    }
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_bid_book_ranks_bids_outside_the_auction() {
+    let bid_book = Keypair::new();
+    let (program_id, mut banks_client, bidders, payer, resource, mint, _, _, recent_blockhash) =
+        setup_auction(
+            AuctionSetup::new(WinnerLimit::Capped(2))
+                .end_auction_at(3600)
+                .bid_book(&bid_book),
+        )
+        .await;
+
+    let transfer_authority = Keypair::new();
+    for bidder in 0..5 {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[bidder].0,
+            1000,
+        )
+        .await
+        .expect("approve");
+    }
+
+    // Once the book is full every bid outbids the lowest one.
+    for (bidder, amount) in [(0, 100), (1, 300), (2, 200), (3, 400)].iter() {
+        helpers::place_bid_on_book(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[*bidder].0,
+            &bidders[*bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            *amount,
+            &bid_book.pubkey(),
+        )
+        .await
+        .expect("place_bid_on_book");
+    }
+
+    let err = helpers::place_bid_on_book(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[4].0,
+        &bidders[4].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        250,
+        &bid_book.pubkey(),
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::BidTooSmall as u32)
+        )
+    );
+
+    // Outbid bids can be taken back, bids in the book are firm.
+    helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &resource,
+        &mint,
+        Some(bid_book.pubkey()),
+    )
+    .await
+    .expect("cancel_bid");
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &bidders[0].1.pubkey()).await,
+        0
+    );
+
+    let err = helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &resource,
+        &mint,
+        Some(bid_book.pubkey()),
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::InvalidState as u32)
+        )
+    );
+
+    helpers::end_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("end_auction");
+
+    let collection = Keypair::new();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &collection,
+        &mint,
+        &payer.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    // Winners are only known once the book is sorted.
+    let err = helpers::claim_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &payer,
+        &bidders[3].0,
+        &bidders[3].1,
+        &collection.pubkey(),
+        &resource,
+        &mint,
+        Some(bidders[3].0.pubkey()),
+        Some(bid_book.pubkey()),
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::BidBookNotSorted as u32)
+        )
+    );
+
+    for steps in [1, 10].iter() {
+        helpers::sort_bid_book(
+            &mut banks_client,
+            &program_id,
+            &recent_blockhash,
+            &payer,
+            &resource,
+            &bid_book.pubkey(),
+            *steps,
+        )
+        .await
+        .expect("sort_bid_book");
+    }
+
+    // The header is followed by the winning bids, highest first.
+    let book = helpers::get_account(&mut banks_client, &bid_book.pubkey()).await;
+    let bid_at = |index: usize| {
+        let offset = 56 + index * 40;
+        Bid::try_from_slice(&book.data[offset..offset + 40]).unwrap()
+    };
+    assert_eq!(bid_at(0), Bid(bidders[3].0.pubkey(), 400));
+    assert_eq!(bid_at(1), Bid(bidders[1].0.pubkey(), 300));
+
+    helpers::claim_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &collection.pubkey(),
+        &resource,
+        &mint,
+        Some(bidders[1].0.pubkey()),
+        Some(bid_book.pubkey()),
+    )
+    .await
+    .expect("claim_bid");
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &collection.pubkey()).await,
+        300
+    );
+
+    helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[2].0,
+        &bidders[2].1,
+        &resource,
+        &mint,
+        Some(bid_book.pubkey()),
+    )
+    .await
+    .expect("cancel_bid");
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_bid_book_ignores_pot_top_ups() {
+    let bid_book = Keypair::new();
+    let (program_id, mut banks_client, bidders, payer, resource, mint, _, _, recent_blockhash) =
+        setup_auction(
+            AuctionSetup::new(WinnerLimit::Capped(2))
+                .end_auction_at(3600)
+                .bid_book(&bid_book),
+        )
+        .await;
+
+    let transfer_authority = Keypair::new();
+    for (bidder, amount) in [(0, 100), (1, 300), (2, 200)].iter() {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidders[*bidder].0,
+            *amount,
+        )
+        .await
+        .expect("approve");
+        helpers::place_bid_on_book(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidders[*bidder].0,
+            &bidders[*bidder].1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            *amount,
+            &bid_book.pubkey(),
+        )
+        .await
+        .expect("place_bid_on_book");
+    }
+
+    // Anyone can send tokens to the pot of a winning bid, it stays firm all the same.
+    helpers::transfer_tokens(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &bidders[4].0.pubkey(),
+        &bidders[1].1.pubkey(),
+        &payer,
+        1,
+    )
+    .await
+    .expect("transfer_tokens");
+
+    let err = helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &resource,
+        &mint,
+        Some(bid_book.pubkey()),
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::InvalidState as u32)
+        )
+    );
+
+    helpers::end_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("end_auction");
+    helpers::sort_bid_book(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+        &bid_book.pubkey(),
+        10,
+    )
+    .await
+    .expect("sort_bid_book");
+
+    let collection = Keypair::new();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &collection,
+        &mint,
+        &payer.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    // The winner pays their bid and gets the rest of the pot back.
+    let refund = Keypair::new();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &refund,
+        &mint,
+        &bidders[1].0.pubkey(),
+    )
+    .await
+    .unwrap();
+    helpers::claim_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &collection.pubkey(),
+        &resource,
+        &mint,
+        Some(refund.pubkey()),
+        Some(bid_book.pubkey()),
+    )
+    .await
+    .expect("claim_bid");
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &collection.pubkey()).await,
+        300
+    );
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &refund.pubkey()).await,
+        1
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_migrate_auction_to_fixed_layout() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test =
+        ProgramTest::new("metaplex_auction", program_id, processor!(process_instruction));
+
+    // An auction as CreateAuction used to lay it out, straight through Borsh.
+    let authority = Keypair::new();
+    let resource = Pubkey::new_unique();
+    let (auction_pubkey, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            &program_id.to_bytes(),
+            &resource.to_bytes(),
+        ],
+        &program_id,
+    );
+    let legacy = AuctionData {
+        authority: authority.pubkey(),
+        token_mint: Pubkey::new_unique(),
+        last_bid: Some(10),
+        ended_at: None,
+        end_auction_at: Some(3600),
+        end_auction_gap: None,
+        price_floor: PriceFloor::MinimumPrice([50, 0, 0, 0]),
+        state: AuctionState::Started,
+        bid_state: BidState::EnglishAuction {
+            bids: vec![
+                Bid(Pubkey::new_unique(), 100),
+                Bid(Pubkey::new_unique(), 200),
+            ],
+            max: 2,
+        },
+    };
+    let mut data = vec![0; BASE_AUCTION_DATA_SIZE + BID_LENGTH * BidState::max_array_size_for(2)];
+    legacy.serialize(&mut data.as_mut_slice()).unwrap();
+    program_test.add_account(
+        auction_pubkey,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    // And one already moved onto the new layout.
+    let migrated_resource = Pubkey::new_unique();
+    let (migrated_pubkey, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            &program_id.to_bytes(),
+            &migrated_resource.to_bytes(),
+        ],
+        &program_id,
+    );
+    let mut data = vec![0; AuctionDataV2::len_for(BidState::max_array_size_for(2))];
+    AuctionDataV2::pack(&legacy, &mut data).unwrap();
+    program_test.add_account(
+        migrated_pubkey,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // Only the authority of the auction can move it.
+    let err = helpers::migrate_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &payer,
+        &resource,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::InvalidAuthority as u32)
+        )
+    );
+
+    helpers::migrate_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &authority,
+        &resource,
+    )
+    .await
+    .expect("migrate_auction");
+
+    let migrated = helpers::get_account(&mut banks_client, &auction_pubkey).await;
+    assert_eq!(migrated.data[64], AUCTION_DATA_V2_KEY);
+    assert_eq!(AuctionData::from_bytes(&migrated.data).unwrap(), legacy);
+
+    let err = helpers::migrate_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &authority,
+        &migrated_resource,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::AuctionAlreadyMigrated as u32)
+        )
+    );
+
+    // Auctions are still created in the Borsh layout, until clients read the new one.
+    let (program_id, mut banks_client, _, payer, resource, _, _, _, recent_blockhash) =
+        setup_auction(AuctionSetup::new(WinnerLimit::Capped(2)).not_started()).await;
+    let (auction_pubkey, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            &program_id.to_bytes(),
+            &resource.to_bytes(),
+        ],
+        &program_id,
+    );
+    let created = helpers::get_account(&mut banks_client, &auction_pubkey).await;
+    assert_ne!(created.data[64], AUCTION_DATA_V2_KEY);
+    let created: AuctionData = try_from_slice_unchecked(&created.data).unwrap();
+
+    helpers::migrate_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("migrate_auction");
+
+    let migrated = helpers::get_account(&mut banks_client, &auction_pubkey).await;
+    assert_eq!(migrated.data[64], AUCTION_DATA_V2_KEY);
+    assert_eq!(AuctionData::from_bytes(&migrated.data).unwrap(), created);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_bid_in_payment_mint() {
+    // Each token of the payment mint is worth two of the auction's own.
+    let payment_mint = Keypair::new();
+    let (program_id, mut banks_client, bidders, payer, resource, mint, _, _, recent_blockhash) =
+        setup_auction(
+            AuctionSetup::new(WinnerLimit::Capped(1))
+                .end_auction_at(3600)
+                .payment_mints(vec![PaymentMint {
+                    mint: payment_mint.pubkey(),
+                    rate: PaymentRate::Fixed {
+                        numerator: 2,
+                        denominator: 1,
+                    },
+                }]),
+        )
+        .await;
+
+    let payment_mint_manager =
+        helpers::create_mint_at(&mut banks_client, &payer, &recent_blockhash, &payment_mint)
+            .await
+            .unwrap();
+
+    let (auction_pubkey, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), program_id.as_ref(), resource.as_ref()],
+        &program_id,
+    );
+
+    // A bidder holding only the payment mint, with a pot of that mint.
+    let bidder = Keypair::new();
+    let bidder_pot = Keypair::new();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &bidder,
+        &payment_mint.pubkey(),
+        &payer.pubkey(),
+    )
+    .await
+    .unwrap();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &bidder_pot,
+        &payment_mint.pubkey(),
+        &auction_pubkey,
+    )
+    .await
+    .unwrap();
+    helpers::mint_tokens(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &payment_mint.pubkey(),
+        &bidder.pubkey(),
+        &payment_mint_manager,
+        1000,
+    )
+    .await
+    .unwrap();
+
+    let transfer_authority = Keypair::new();
+    for spl_wallet in [&bidders[0].0, &bidder].iter() {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            spl_wallet,
+            1000,
+        )
+        .await
+        .expect("approve");
+    }
+
+    helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        250,
+        None,
+        None,
+    )
+    .await
+    .expect("place_bid");
+
+    // The mint passed in must be the one the pot holds.
+    let err = helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidder,
+        &bidder_pot,
+        &transfer_authority,
+        &resource,
+        &mint,
+        300,
+        None,
+        None,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::IncorrectMint as u32)
+        )
+    );
+
+    // A bid of 300 costs 150 tokens of the payment mint, and outranks the bid of 250.
+    helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidder,
+        &bidder_pot,
+        &transfer_authority,
+        &resource,
+        &payment_mint.pubkey(),
+        300,
+        None,
+        None,
+    )
+    .await
+    .expect("place_bid");
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &bidder_pot.pubkey()).await,
+        150
+    );
+
+    let auction = helpers::get_account(&mut banks_client, &auction_pubkey).await;
+    let auction = AuctionData::from_bytes(&auction.data).unwrap();
+    assert_eq!(auction.is_winner(&bidder.pubkey()), Some(0));
+
+    helpers::end_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("end_auction");
+
+    // The winning bid is claimed in the mint it was paid in.
+    let collection = Keypair::new();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &collection,
+        &payment_mint.pubkey(),
+        &payer.pubkey(),
+    )
+    .await
+    .unwrap();
+    helpers::claim_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &payer,
+        &bidder,
+        &bidder_pot,
+        &collection.pubkey(),
+        &resource,
+        &payment_mint.pubkey(),
+        None,
+        None,
+    )
+    .await
+    .expect("claim_bid");
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &collection.pubkey()).await,
+        150
+    );
+
+    helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &resource,
+        &mint,
+        None,
+    )
+    .await
+    .expect("cancel_bid");
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &bidders[0].0.pubkey()).await,
+        10_000_000
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_bid_in_lamports() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test =
+        ProgramTest::new("metaplex_auction", program_id, processor!(process_instruction));
+
+    // Lamport bids are only taken by auctions in wrapped SOL.
+    let mut native_mint = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::None,
+        supply: 0,
+        decimals: 9,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut native_mint);
+    program_test.add_account(
+        spl_token::native_mint::id(),
+        Account {
+            lamports: 1_000_000_000,
+            data: native_mint,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // Auctions only take lamport bids when created with lamport_bids.
+    let resource = Pubkey::new_unique();
+    helpers::create_auction(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &recent_blockhash,
+        &resource,
+        &spl_token::native_mint::id(),
+        WinnerLimit::Capped(1),
+        "Some name",
+        PriceFloor::None([0; 32]),
+        None,
+        None,
+        None,
+        AuctionExtendedSettings::default(),
+    )
+    .await
+    .expect("create_auction");
+    helpers::start_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("start_auction");
+
+    let (auction_pubkey, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), program_id.as_ref(), resource.as_ref()],
+        &program_id,
+    );
+    let bidder = Keypair::new();
+    let (bidder_pot, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            auction_pubkey.as_ref(),
+            bidder.pubkey().as_ref(),
+        ],
+        &program_id,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&payer.pubkey(), &bidder.pubkey(), 10_000_000),
+            instruction::place_bid_instruction(
+                program_id,
+                bidder.pubkey(),
+                bidder.pubkey(),
+                bidder_pot,
+                spl_token::native_mint::id(),
+                bidder.pubkey(),
+                payer.pubkey(),
+                None,
+                None,
+                None,
+                PlaceBidArgs {
+                    amount: 1_000_000,
+                    resource,
+                },
+            ),
+        ],
+        Some(&payer.pubkey()),
+        &[&bidder, &payer],
+        recent_blockhash,
+    );
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(AuctionError::LamportBidsNotEnabled as u32)
+        )
+    );
+
+    // Bid books only take bids paid into token accounts.
+    let err = helpers::create_auction(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &recent_blockhash,
+        &Pubkey::new_unique(),
+        &spl_token::native_mint::id(),
+        WinnerLimit::Capped(1),
+        "Some name",
+        PriceFloor::None([0; 32]),
+        None,
+        None,
+        None,
+        AuctionExtendedSettings {
+            bid_book: Some(Pubkey::new_unique()),
+            lamport_bids: Some(true),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::InvalidBidBookSettings as u32)
+        )
+    );
+
+    let resource = Pubkey::new_unique();
+    helpers::create_auction(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &recent_blockhash,
+        &resource,
+        &spl_token::native_mint::id(),
+        WinnerLimit::Capped(1),
+        "Some name",
+        PriceFloor::None([0; 32]),
+        None,
+        None,
+        None,
+        AuctionExtendedSettings {
+            lamport_bids: Some(true),
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("create_auction");
+    helpers::start_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("start_auction");
+
+    let (auction_pubkey, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), program_id.as_ref(), resource.as_ref()],
+        &program_id,
+    );
+
+    // Each bidder pays from their wallet into their pot, which stands in for both token accounts.
+    let transfer_authority = Keypair::new();
+    let mut bidders = vec![];
+    for amount in [1_000_000, 2_000_000].iter() {
+        let bidder = Keypair::new();
+        let (bidder_pot, _) = Pubkey::find_program_address(
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                auction_pubkey.as_ref(),
+                bidder.pubkey().as_ref(),
+            ],
+            &program_id,
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::transfer(&payer.pubkey(), &bidder.pubkey(), 10_000_000),
+                instruction::place_bid_instruction(
+                    program_id,
+                    bidder.pubkey(),
+                    bidder.pubkey(),
+                    bidder_pot,
+                    spl_token::native_mint::id(),
+                    transfer_authority.pubkey(),
+                    payer.pubkey(),
+                    None,
+                    None,
+                    None,
+                    PlaceBidArgs {
+                        amount: *amount,
+                        resource,
+                    },
+                ),
+            ],
+            Some(&payer.pubkey()),
+            &[&bidder, &transfer_authority, &payer],
+            recent_blockhash,
+        );
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .expect("place_bid");
+        assert_eq!(
+            helpers::get_account(&mut banks_client, &bidder.pubkey())
+                .await
+                .lamports,
+            10_000_000 - amount
+        );
+        bidders.push((bidder, bidder_pot));
+    }
+
+    // The outbid bidder gets their lamports back.
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::cancel_bid_instruction(
+            program_id,
+            bidders[0].0.pubkey(),
+            bidders[0].0.pubkey(),
+            bidders[0].1,
+            spl_token::native_mint::id(),
+            None,
+            CancelBidArgs { resource },
+        )],
+        Some(&payer.pubkey()),
+        &[&bidders[0].0, &payer],
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("cancel_bid");
+    assert_eq!(
+        helpers::get_account(&mut banks_client, &bidders[0].0.pubkey())
+            .await
+            .lamports,
+        10_000_000
+    );
+
+    helpers::end_auction(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &resource,
+    )
+    .await
+    .expect("end_auction");
+
+    // The winning bid is claimed in lamports to a wallet.
+    let seller = Pubkey::new_unique();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::claim_bid_instruction(
+            program_id,
+            seller,
+            payer.pubkey(),
+            bidders[1].0.pubkey(),
+            bidders[1].1,
+            spl_token::native_mint::id(),
+            None,
+            None,
+            ClaimBidArgs { resource },
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("claim_bid");
+    assert_eq!(
+        helpers::get_account(&mut banks_client, &seller)
+            .await
+            .lamports,
+        2_000_000
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_cancel_winning_bid_with_cancellation_rules() {
+    // Cancelling a winning bid costs a tenth of it.
+    let (program_id, mut banks_client, bidders, payer, resource, mint, _, _, recent_blockhash) =
+        setup_auction(
+            AuctionSetup::new(WinnerLimit::Capped(1))
+                .end_auction_at(3600)
+                .cancellation_rules(CancellationRules {
+                    lock_up: None,
+                    penalty_basis_points: Some(1000),
+                }),
+        )
+        .await;
+
+    let transfer_authority = Keypair::new();
+    helpers::approve(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &transfer_authority.pubkey(),
+        &bidders[0].0,
+        1000,
+    )
+    .await
+    .expect("approve");
+    helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1000,
+        None,
+        None,
+    )
+    .await
+    .expect("place_bid");
+
+    // The penalty is paid to the authority, which created the auction.
+    let penalty_account = Keypair::new();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &penalty_account,
+        &mint,
+        &payer.pubkey(),
+    )
+    .await
+    .unwrap();
+    helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &resource,
+        &mint,
+        Some(penalty_account.pubkey()),
+    )
+    .await
+    .expect("cancel_bid");
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &bidders[0].0.pubkey()).await,
+        10_000_000 - 100
+    );
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &penalty_account.pubkey()).await,
+        100
+    );
+
+    // Before the lock-up window opens, winning bids can still be cancelled for the penalty.
+    let (program_id, mut banks_client, bidders, payer, resource, mint, _, _, recent_blockhash) =
+        setup_auction(
+            AuctionSetup::new(WinnerLimit::Capped(1))
+                .end_auction_at(3600)
+                .cancellation_rules(CancellationRules {
+                    lock_up: Some(600),
+                    penalty_basis_points: Some(1000),
+                }),
+        )
+        .await;
+
+    let transfer_authority = Keypair::new();
+    helpers::approve(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &transfer_authority.pubkey(),
+        &bidders[0].0,
+        1000,
+    )
+    .await
+    .expect("approve");
+    helpers::place_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &transfer_authority,
+        &resource,
+        &mint,
+        1000,
+        None,
+        None,
+    )
+    .await
+    .expect("place_bid");
+
+    let penalty_account = Keypair::new();
+    helpers::create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &penalty_account,
+        &mint,
+        &payer.pubkey(),
+    )
+    .await
+    .unwrap();
+    helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &resource,
+        &mint,
+        Some(penalty_account.pubkey()),
+    )
+    .await
+    .expect("cancel_bid");
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &bidders[0].0.pubkey()).await,
+        10_000_000 - 100
+    );
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &penalty_account.pubkey()).await,
+        100
+    );
+
+    // Inside the lock-up window, winning bids can't be cancelled. Starting the auction at a
+    // scheduled time long past opens the window as soon as the first bid starts it.
+    let (program_id, mut banks_client, bidders, payer, resource, mint, _, _, recent_blockhash) =
+        setup_auction(
+            AuctionSetup::new(WinnerLimit::Capped(1))
+                .not_started()
+                .start_auction_at(1)
+                .end_auction_at(UnixTimestamp::MAX / 2)
+                .cancellation_rules(CancellationRules {
+                    lock_up: Some(UnixTimestamp::MAX / 2 - 1),
+                    penalty_basis_points: None,
+                }),
+        )
+        .await;
+
+    let transfer_authority = Keypair::new();
+    for bidder in bidders[..2].iter() {
+        helpers::approve(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &transfer_authority.pubkey(),
+            &bidder.0,
+            1000,
+        )
+        .await
+        .expect("approve");
+    }
+    for (bidder, amount) in bidders[..2].iter().zip([500, 1000].iter()) {
+        helpers::place_bid(
+            &mut banks_client,
+            &recent_blockhash,
+            &program_id,
+            &payer,
+            &bidder.0,
+            &bidder.1,
+            &transfer_authority,
+            &resource,
+            &mint,
+            *amount,
+            None,
+            None,
+        )
+        .await
+        .expect("place_bid");
+    }
+
+    let err = helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[1].0,
+        &bidders[1].1,
+        &resource,
+        &mint,
+        None,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::BidLockedUp as u32)
+        )
+    );
+
+    // Outbid bids are not affected.
+    helpers::cancel_bid(
+        &mut banks_client,
+        &recent_blockhash,
+        &program_id,
+        &payer,
+        &bidders[0].0,
+        &bidders[0].1,
+        &resource,
+        &mint,
+        None,
+    )
+    .await
+    .expect("cancel_bid");
+    assert_eq!(
+        helpers::get_token_balance(&mut banks_client, &bidders[0].0.pubkey()).await,
+        10_000_000
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_start_vault_buyout_requires_active_vault() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test =
+        ProgramTest::new("metaplex_auction", program_id, processor!(process_instruction));
+
+    let (fraction_mint, fraction_treasury, redeem_treasury) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    for key in [fraction_mint, fraction_treasury, redeem_treasury].iter() {
+        program_test.add_account(
+            *key,
+            Account {
+                lamports: 1_000_000_000,
+                data: vec![],
+                owner: spl_token::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    // A vault that has already been combined by its authority.
+    let vault = Pubkey::new_unique();
+    let external_pricing = Pubkey::new_unique();
+    let combined = Vault {
+        key: VaultKey::VaultV2,
+        token_program: spl_token::id(),
+        fraction_mint,
+        authority: Pubkey::new_unique(),
+        fraction_treasury,
+        redeem_treasury,
+        allow_further_share_creation: false,
+        pricing_lookup_address: external_pricing,
+        token_type_count: 1,
+        state: VaultState::Combined,
+        locked_price_per_share: 10,
+        original_authority: Pubkey::new_unique(),
+    };
+    let mut data = vec![0; MAX_VAULT_V2_SIZE];
+    combined.serialize(&mut data.as_mut_slice()).unwrap();
+    for (key, data) in [(vault, data), (external_pricing, vec![])].iter() {
+        program_test.add_account(
+            *key,
+            Account {
+                lamports: 1_000_000_000,
+                data: data.clone(),
+                owner: metaplex_token_vault::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let err = helpers::start_vault_buyout(
+        &mut banks_client,
+        &program_id,
+        &recent_blockhash,
+        &payer,
+        &vault,
+        &fraction_mint,
+        &fraction_treasury,
+        &redeem_treasury,
+        &external_pricing,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AuctionError::InvalidVaultBuyout as u32)
+        )
+    );
+}
@@ -0,0 +1,64 @@
+//! Decodes the events logged by the auction, vault, metadata and metaplex programs back out of
+//! transaction logs.
+
+use {
+    borsh::BorshDeserialize, metaplex::event::MetaplexEvent, metaplex_auction::event::AuctionEvent,
+    metaplex_token_metadata::event::MetadataEvent, metaplex_token_vault::event::VaultEvent,
+    thiserror::Error,
+};
+
+/// Prefix the runtime puts in front of every msg! line in transaction logs.
+pub const PROGRAM_LOG_PREFIX: &str = "Program log: ";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Event {
+    Auction(AuctionEvent),
+    Vault(VaultEvent),
+    Metadata(MetadataEvent),
+    Metaplex(MetaplexEvent),
+}
+
+#[derive(Error, Debug)]
+pub enum DecodeError {
+    /// The event payload was not valid base64.
+    #[error("Event is not valid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+
+    /// The event payload did not deserialize into an event of its program.
+    #[error("Event could not be deserialized: {0}")]
+    Borsh(#[from] std::io::Error),
+}
+
+fn decode<T: BorshDeserialize>(payload: &str) -> Result<T, DecodeError> {
+    let data = base64::decode(payload.trim())?;
+    Ok(T::try_from_slice(&data)?)
+}
+
+/// Decodes a single log line, either as the runtime prints it or with the "Program log: " prefix
+/// already stripped. Returns None for lines that are not events.
+pub fn parse_log(line: &str) -> Option<Result<Event, DecodeError>> {
+    let line = line.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(line);
+
+    if let Some(payload) = line.strip_prefix(metaplex_auction::event::EVENT_PREFIX) {
+        return Some(decode(payload).map(Event::Auction));
+    }
+    if let Some(payload) = line.strip_prefix(metaplex_token_vault::event::EVENT_PREFIX) {
+        return Some(decode(payload).map(Event::Vault));
+    }
+    if let Some(payload) = line.strip_prefix(metaplex_token_metadata::event::EVENT_PREFIX) {
+        return Some(decode(payload).map(Event::Metadata));
+    }
+    line.strip_prefix(metaplex::event::EVENT_PREFIX)
+        .map(|payload| decode(payload).map(Event::Metaplex))
+}
+
+/// Decodes every event in a transaction's log messages, in the order they were logged.
+pub fn parse_logs<I, S>(logs: I) -> Vec<Result<Event, DecodeError>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    logs.into_iter()
+        .filter_map(|line| parse_log(line.as_ref()))
+        .collect()
+}
@@ -0,0 +1,75 @@
+use {
+    borsh::BorshSerialize,
+    event_decoder::{parse_log, parse_logs, DecodeError, Event},
+    metaplex::event::MetaplexEvent,
+    metaplex_auction::event::{AuctionEvent, EVENT_PREFIX},
+    metaplex_token_vault::event::VaultEvent,
+    solana_program::pubkey::Pubkey,
+};
+
+fn log_line(prefix: &str, data: Vec<u8>) -> String {
+    format!("Program log: {}{}", prefix, base64::encode(data))
+}
+
+#[test]
+fn test_parse_logs() {
+    let bid = AuctionEvent::BidPlaced {
+        auction: Pubkey::new_unique(),
+        bidder: Pubkey::new_unique(),
+        amount: 100,
+        mint: Pubkey::new_unique(),
+        payment: 100,
+    };
+    let combined = VaultEvent::VaultCombined {
+        vault: Pubkey::new_unique(),
+        new_authority: Pubkey::new_unique(),
+        price_per_share: 5,
+        paid: 50,
+    };
+    let payout = MetaplexEvent::PayoutMade {
+        auction_manager: Pubkey::new_unique(),
+        safety_deposit_box: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        destination: Pubkey::new_unique(),
+        amount: 95,
+    };
+
+    let logs = vec![
+        "Program p1exdMJcjVao65QdewkaZRUnU6VPSXhus9n2GzWfh98 invoke [1]".to_string(),
+        log_line(EVENT_PREFIX, bid.try_to_vec().unwrap()),
+        "Program log: Bid placed".to_string(),
+        log_line(
+            metaplex_token_vault::event::EVENT_PREFIX,
+            combined.try_to_vec().unwrap(),
+        ),
+        log_line(metaplex::event::EVENT_PREFIX, payout.try_to_vec().unwrap()),
+    ];
+
+    let events: Vec<Event> = parse_logs(&logs)
+        .into_iter()
+        .map(|event| event.unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            Event::Auction(bid),
+            Event::Vault(combined),
+            Event::Metaplex(payout),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_malformed_event() {
+    assert!(parse_log("Program log: Bid placed").is_none());
+
+    match parse_log(&format!("{}not base64!", EVENT_PREFIX)) {
+        Some(Err(DecodeError::Base64(_))) => (),
+        other => panic!("Expected a base64 error, got {:?}", other),
+    }
+
+    match parse_log(&log_line(EVENT_PREFIX, vec![255])) {
+        Some(Err(DecodeError::Borsh(_))) => (),
+        other => panic!("Expected a borsh error, got {:?}", other),
+    }
+}
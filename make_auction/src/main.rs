@@ -415,6 +415,9 @@ fn make_auction(
     let seeds = &["auction".as_bytes(), auction_program.as_ref(), vault_pubkey.as_ref()];
     let (auction_key, _) = Pubkey::find_program_address(seeds, &auction_program);
 
+    let seeds = &["auction".as_bytes(), auction_program.as_ref(), vault_pubkey.as_ref(), "extended".as_bytes()];
+    let (auction_extended_key, _) = Pubkey::find_program_address(seeds, &auction_program);
+
     let seeds = &["metaplex".as_bytes(), auction_key.as_ref()];
     let (auction_manager_key, _) = Pubkey::find_program_address(seeds, &metaplex_program);
 
@@ -446,6 +449,7 @@ fn make_auction(
         auction_manager_key,
         vault_pubkey,
         auction_key,
+        auction_extended_key,
         payer.pubkey(),
         payer.pubkey(),
         accept_payment.pubkey(),
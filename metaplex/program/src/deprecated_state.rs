@@ -187,7 +187,7 @@ impl AuctionManager for AuctionManagerV1 {
 
         let mut winning_config_item_index = None;
         for i in 0..winning_config.items.len() {
-            if winning_config.items[i].safety_deposit_box_index
+            if winning_config.items[i].safety_deposit_box_index as u64
                 == SafetyDepositBox::get_order(safety_deposit_info)
             {
                 winning_config_item_index = Some(i);
@@ -472,7 +472,7 @@ impl AuctionManager for AuctionManagerV1 {
             if (winning_index as usize) < winning_configs.len() {
                 let winning_config = &winning_configs[winning_index as usize];
                 if let Some(item_index) = winning_config_item_index {
-                    if winning_config.items[item_index as usize].safety_deposit_box_index
+                    if winning_config.items[item_index as usize].safety_deposit_box_index as u64
                         != safety_deposit.order
                     {
                         return Err(MetaplexError::WinningConfigSafetyDepositMismatch.into());
@@ -484,7 +484,7 @@ impl AuctionManager for AuctionManagerV1 {
                 return Err(MetaplexError::InvalidWinningConfigIndex.into());
             }
         } else if let Some(participation) = &self.settings.participation_config {
-            if participation.safety_deposit_box_index != safety_deposit.order {
+            if participation.safety_deposit_box_index as u64 != safety_deposit.order {
                 return Err(MetaplexError::ParticipationSafetyDepositMismatch.into());
             }
         } else {
@@ -515,10 +515,10 @@ impl AuctionManagerV1 {
 
 fn count_item_amount_by_safety_deposit_order(
     items: &Vec<WinningConfigItem>,
-    safety_deposit_index: u8,
+    safety_deposit_index: u64,
 ) -> u64 {
     let item = items.iter().find_map(|i| {
-        if i.safety_deposit_box_index == safety_deposit_index {
+        if i.safety_deposit_box_index as u64 == safety_deposit_index {
             Some(i)
         } else {
             None
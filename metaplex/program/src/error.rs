@@ -470,6 +470,25 @@ pub enum MetaplexError {
     /// Auction manager already in finished state
     #[error("Auction manager already in finished state")]
     AuctionManagerInFishedState,
+
+    /// Auction was cancelled, nothing can be redeemed from it except by the auctioneer
+    #[error("Auction was cancelled")]
+    AuctionCancelled,
+
+    /// Winners of bid-book auctions are only found through their bid book, which prizes cannot be
+    /// redeemed against yet
+    #[error("Auctions with a bid book are not supported")]
+    BidBookAuctionNotSupported,
+
+    /// Winning bids are claimed into the accept payment account, so bids paid in other mints
+    /// can't be taken
+    #[error("Auctions taking other payment mints are not supported")]
+    PaymentMintsNotSupported,
+
+    /// Winning bids are claimed into the accept payment account, so bids paid in lamports can't
+    /// be taken
+    #[error("Auctions taking lamport bids are not supported")]
+    LamportBidsNotSupported,
 }
 
 impl PrintProgramError for MetaplexError {
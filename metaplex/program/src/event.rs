@@ -0,0 +1,47 @@
+//! Events logged by the metaplex program, so indexers can follow auction managers without parsing
+//! its other log messages. Each event is logged as EVENT_PREFIX followed by its Borsh
+//! serialization in base64. New events and fields are only ever appended, so older logs keep
+//! decoding.
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{entrypoint::ProgramResult, msg, pubkey::Pubkey},
+};
+
+/// Marks an event among the program logs, followed by the encoded event.
+pub const EVENT_PREFIX: &str = "metaplex event: ";
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub enum MetaplexEvent {
+    /// An auction manager was set up to sell the contents of vault through auction.
+    AuctionManagerInitialized {
+        auction_manager: Pubkey,
+        auction: Pubkey,
+        vault: Pubkey,
+        authority: Pubkey,
+    },
+    /// A bidder redeemed their prize, either a winning item or a participation reward.
+    PrizeRedeemed {
+        auction_manager: Pubkey,
+        bidder_metadata: Pubkey,
+        winning_index: Option<u64>,
+        participation: bool,
+    },
+    /// Part of the auction proceeds for a safety deposit box was paid out to destination.
+    PayoutMade {
+        auction_manager: Pubkey,
+        safety_deposit_box: Pubkey,
+        recipient: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    },
+}
+
+impl MetaplexEvent {
+    /// Logs the event for indexers.
+    pub fn emit(&self) -> ProgramResult {
+        msg!("{}{}", EVENT_PREFIX, base64::encode(self.try_to_vec()?));
+        Ok(())
+    }
+}
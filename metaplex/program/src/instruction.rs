@@ -94,6 +94,7 @@ pub enum MetaplexInstruction {
     ///   6. `[]` Store that this auction manager will belong to
     ///   7. `[]` System sysvar
     ///   8. `[]` Rent sysvar
+    ///   9. `[]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended']), the auction must not take lamport bids or other payment mints
     DeprecatedInitAuctionManagerV1(AuctionManagerSettingsV1),
 
     /// Validates that a given safety deposit box has in it contents that match the expected WinningConfig in the auction manager.
@@ -272,7 +273,8 @@ pub enum MetaplexInstruction {
     ///   9. `[]` Auction program
     ///   10. `[]` Clock sysvar
     ///   11. `[]` Token program
-    ///   12. `[]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   12. `[writable]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///   13. `[writable]` The bidders token account, refunded whatever the bidder pot holds beyond the settlement price
     ClaimBid,
 
     /// At any time, the auction manager authority may empty whatever funds are in the accept payment account
@@ -311,6 +313,10 @@ pub enum MetaplexInstruction {
     ///   13. `[]` Rent sysvar
     ///   14. `[]` AuctionWinnerTokenTypeTracker, pda of seed ['metaplex', program id, auction manager key, 'totals']
     ///   15. `[]` Safety deposit config pda of ['metaplex', program id, auction manager, safety deposit]
+    ///   16. `[]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended'])
+    ///
+    /// The auction extended account is required, so v1 auction managers pass accounts 14 and 15 as
+    /// well. Payouts on second-price auctions are worked out on the price the winners paid.
     EmptyPaymentAccount(EmptyPaymentAccountArgs),
 
     /// Given a signer wallet, create a store with pda ['metaplex', wallet] (if it does not exist) and/or update it
@@ -550,6 +556,7 @@ pub enum MetaplexInstruction {
     ///   7. `[]` Store that this auction manager will belong to
     ///   8. `[]` System sysvar    
     ///   9. `[]` Rent sysvar
+    ///   10. `[]` Auction extended (pda relative to auction of ['auction', program id, vault key, 'extended']), the auction must not take lamport bids or other payment mints
     InitAuctionManagerV2(InitAuctionManagerV2Args),
 
     /// NOTE: Requires an AuctionManagerV2.
@@ -665,6 +672,17 @@ pub enum MetaplexInstruction {
     ///   7. `[]` Rent sysvar
     ///   8. `[]` Clock sysvar
     SetAuctionCache,
+    /// Cancels an auction that has not ended. Bidders withdraw their full bids and the auctioneer
+    /// takes the items back out through RedeemUnusedWinningConfigItemsAsAuctioneer and
+    /// WithdrawMasterEdition.
+    ///
+    ///   0. `[writable]` Auction manager
+    ///   1. `[writable]` Auction
+    ///   2. `[signer]` Auction manager authority
+    ///   3. `[]` Store key
+    ///   4. `[]` Auction program
+    ///   5. `[]` Clock sysvar
+    CancelAuction,
 }
 
 /// Creates an DeprecatedInitAuctionManager instruction
@@ -674,6 +692,7 @@ pub fn create_deprecated_init_auction_manager_v1_instruction(
     auction_manager: Pubkey,
     vault: Pubkey,
     auction: Pubkey,
+    auction_extended: Pubkey,
     auction_manager_authority: Pubkey,
     payer: Pubkey,
     accept_payment_account_key: Pubkey,
@@ -692,6 +711,7 @@ pub fn create_deprecated_init_auction_manager_v1_instruction(
             AccountMeta::new_readonly(store, false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(auction_extended, false),
         ],
         data: MetaplexInstruction::DeprecatedInitAuctionManagerV1(settings)
             .try_to_vec()
@@ -707,6 +727,7 @@ pub fn create_init_auction_manager_v2_instruction(
     auction_manager: Pubkey,
     vault: Pubkey,
     auction: Pubkey,
+    auction_extended: Pubkey,
     auction_manager_authority: Pubkey,
     payer: Pubkey,
     accept_payment_account_key: Pubkey,
@@ -728,6 +749,7 @@ pub fn create_init_auction_manager_v2_instruction(
             AccountMeta::new_readonly(store, false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(auction_extended, false),
         ],
         data: MetaplexInstruction::InitAuctionManagerV2(InitAuctionManagerV2Args {
             amount_type,
@@ -1539,3 +1561,25 @@ pub fn create_end_auction_instruction(
             .unwrap(),
     }
 }
+
+/// Creates an CancelAuction instruction
+pub fn create_cancel_auction_instruction(
+    program_id: Pubkey,
+    auction_manager: Pubkey,
+    auction: Pubkey,
+    auction_manager_authority: Pubkey,
+    store: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(auction_manager, false),
+            AccountMeta::new(auction, false),
+            AccountMeta::new_readonly(auction_manager_authority, true),
+            AccountMeta::new_readonly(store, false),
+            AccountMeta::new_readonly(metaplex_auction::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: MetaplexInstruction::CancelAuction.try_to_vec().unwrap(),
+    }
+}
@@ -3,6 +3,7 @@
 pub mod deprecated_state;
 pub mod entrypoint;
 pub mod error;
+pub mod event;
 pub mod instruction;
 pub mod processor;
 pub mod state;
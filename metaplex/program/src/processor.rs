@@ -1,6 +1,7 @@
 use {
     crate::instruction::MetaplexInstruction,
     borsh::BorshDeserialize,
+    cancel_auction::process_cancel_auction,
     claim_bid::process_claim_bid,
     decommission_auction_manager::process_decommission_auction_manager,
     deprecated_init_auction_manager_v1::process_deprecated_init_auction_manager_v1,
@@ -25,6 +26,7 @@ use {
     withdraw_master_edition::process_withdraw_master_edition,
 };
 
+pub mod cancel_auction;
 pub mod claim_bid;
 pub mod decommission_auction_manager;
 pub mod deprecated_init_auction_manager_v1;
@@ -161,5 +163,9 @@ pub fn process_instruction<'a>(
             msg!("Instruction: Set Auction Cache");
             process_set_auction_cache(program_id, accounts)
         }
+        MetaplexInstruction::CancelAuction => {
+            msg!("Instruction: Cancel auction");
+            process_cancel_auction(program_id, accounts)
+        }
     }
 }
@@ -22,7 +22,8 @@ use {
 pub fn issue_claim_bid<'a>(
     auction_program: AccountInfo<'a>,
     auction: AccountInfo<'a>,
-    auction_extended: Option<AccountInfo<'a>>,
+    auction_extended: AccountInfo<'a>,
+    bidder_token: Option<AccountInfo<'a>>,
     accept_payment: AccountInfo<'a>,
     authority: AccountInfo<'a>,
     bidder: AccountInfo<'a>,
@@ -45,12 +46,13 @@ pub fn issue_claim_bid<'a>(
         bidder_pot,
         accept_payment.clone(),
         token_program,
+        auction_extended,
     ];
 
-    let mut auction_extended_key: Option<Pubkey> = None;
-    if let Some(auction_extended_account) = auction_extended {
-        auction_extended_key = Some(*auction_extended_account.key);
-        account_infos.push(auction_extended_account);
+    let mut bidder_token_key: Option<Pubkey> = None;
+    if let Some(bidder_token_account) = bidder_token {
+        bidder_token_key = Some(*bidder_token_account.key);
+        account_infos.push(bidder_token_account);
     }
     invoke_signed(
         &claim_bid_instruction(
@@ -60,7 +62,8 @@ pub fn issue_claim_bid<'a>(
             *bidder.key,
             *bidder_pot_token_acct.key,
             *token_mint.key,
-            auction_extended_key,
+            bidder_token_key,
+            None,
             ClaimBidArgs { resource: vault },
         ),
         account_infos.as_ref(),
@@ -84,7 +87,8 @@ pub fn process_claim_bid(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progr
     let auction_program_info = next_account_info(account_info_iter)?;
     let clock_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
-    let auction_extended_info = next_account_info(account_info_iter).ok();
+    let auction_extended_info = next_account_info(account_info_iter)?;
+    let bidder_token_info = next_account_info(account_info_iter).ok();
 
     let mut auction_manager = get_auction_manager(auction_manager_info)?;
     let store = Store::from_account_info(store_info)?;
@@ -99,9 +103,7 @@ pub fn process_claim_bid(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progr
     assert_owned_by(token_mint_info, &spl_token::id())?;
     assert_owned_by(vault_info, &store.token_vault_program)?;
     assert_owned_by(store_info, program_id)?;
-    if let Some(auction_extended) = auction_extended_info {
-        assert_owned_by(auction_extended, &store.auction_program)?;
-    }
+    assert_owned_by(auction_extended_info, &store.auction_program)?;
 
     if auction_manager.store() != *store_info.key {
         return Err(MetaplexError::AuctionManagerStoreMismatch.into());
@@ -127,11 +129,12 @@ pub fn process_claim_bid(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progr
         return Err(MetaplexError::AuctionManagerVaultMismatch.into());
     }
 
-    let mut instant_sale_price: Option<u64> = None;
-    if let Some(auction_extended) = auction_extended_info {
-        instant_sale_price =
-            AuctionDataExtended::get_instant_sale_price(&auction_extended.data.borrow());
+    if token_pot_info.mint(&auction) != auction.token_mint {
+        return Err(MetaplexError::PaymentMintsNotSupported.into());
     }
+
+    let instant_sale_price =
+        AuctionDataExtended::get_instant_sale_price(&auction_extended_info.data.borrow());
     if !instant_sale_price.is_some() {
         if auction.state != AuctionState::Ended {
             return Err(MetaplexError::AuctionHasNotEnded.into());
@@ -161,7 +164,8 @@ pub fn process_claim_bid(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progr
     issue_claim_bid(
         auction_program_info.clone(),
         auction_info.clone(),
-        auction_extended_info.map_or(None, |acc| Some(acc.clone())),
+        auction_extended_info.clone(),
+        bidder_token_info.map_or(None, |acc| Some(acc.clone())),
         accept_payment_info.clone(),
         auction_manager_info.clone(),
         bidder_info.clone(),
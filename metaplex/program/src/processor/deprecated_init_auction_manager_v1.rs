@@ -33,11 +33,13 @@ pub fn process_deprecated_init_auction_manager_v1(
     let store_info = next_account_info(account_info_iter)?;
     let system_info = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
+    let auction_extended_info = next_account_info(account_info_iter)?;
     let (bump_seed, vault, auction) = assert_common_checks(
         program_id,
         auction_manager_info,
         vault_info,
         auction_info,
+        auction_extended_info,
         store_info,
         accept_payment_info,
         authority_info,
@@ -78,7 +80,7 @@ pub fn process_deprecated_init_auction_manager_v1(
                 safety_deposit_box_found_lookup[item.safety_deposit_box_index as usize] = true
             }
 
-            if item.safety_deposit_box_index > vault.token_type_count {
+            if item.safety_deposit_box_index as u64 > vault.token_type_count {
                 return Err(MetaplexError::InvalidSafetyDepositBox.into());
             }
 
@@ -145,11 +145,11 @@ pub fn process_deprecated_validate_participation(
     }
 
     if let Some(participation_config) = &auction_manager.settings.participation_config {
-        if participation_config.safety_deposit_box_index > vault.token_type_count {
+        if participation_config.safety_deposit_box_index as u64 > vault.token_type_count {
             return Err(MetaplexError::InvalidSafetyDepositBox.into());
         }
 
-        if participation_config.safety_deposit_box_index != safety_deposit.order {
+        if participation_config.safety_deposit_box_index as u64 != safety_deposit.order {
             return Err(MetaplexError::SafetyDepositIndexMismatch.into());
         }
 
@@ -118,7 +118,7 @@ pub fn process_deprecated_validate_safety_deposit_box_v1<'a>(
                 .checked_add(1)
                 .ok_or(MetaplexError::NumericalOverflowError)?;
 
-            if possible_item.safety_deposit_box_index == safety_deposit.order {
+            if possible_item.safety_deposit_box_index as u64 == safety_deposit.order {
                 winning_config_type = possible_item.winning_config_type;
 
                 winning_config_items_validated = winning_config_items_validated
@@ -137,7 +137,7 @@ pub fn process_deprecated_validate_safety_deposit_box_v1<'a>(
     }
 
     if let Some(participation_config) = &auction_manager.settings.participation_config {
-        if participation_config.safety_deposit_box_index == safety_deposit.order {
+        if participation_config.safety_deposit_box_index as u64 == safety_deposit.order {
             // Really it's unknown how many prints will be made
             // but we set it to 1 since that's how many master edition tokens are in there.
             total_amount_requested = total_amount_requested
@@ -3,6 +3,7 @@ use solana_program::msg;
 use {
     crate::{
         error::MetaplexError,
+        event::MetaplexEvent,
         instruction::EmptyPaymentAccountArgs,
         state::{
             get_auction_manager, AuctionManager, Key, PayoutTicket, Store, MAX_PAYOUT_TICKET_SIZE,
@@ -15,7 +16,7 @@ use {
         },
     },
     borsh::BorshSerialize,
-    metaplex_auction::processor::AuctionData,
+    metaplex_auction::processor::{AuctionData, AuctionDataExtended, SettlementMode},
     metaplex_token_metadata::state::{MasterEditionV1, Metadata},
     metaplex_token_vault::state::SafetyDepositBox,
     solana_program::{
@@ -80,6 +81,7 @@ fn calculate_owed_amount(
     safety_deposit_config_info: Option<&AccountInfo>,
     auction_manager: &Box<dyn AuctionManager>,
     auction: &AuctionData,
+    settlement_mode: Option<&SettlementMode>,
     metadata: &Metadata,
     winning_config_index: &Option<u8>,
     winning_config_item_index: &Option<u8>,
@@ -92,7 +94,8 @@ fn calculate_owed_amount(
     )?;
 
     let mut amount_available_to_split: u128 = match winning_config_index {
-        Some(index) => auction.bid_state.amount(*index as usize) as u128,
+        // Royalties are split on what the winner was actually charged, see AuctionData::settlement_price.
+        Some(index) => auction.settlement_price(*index as usize, settlement_mode) as u128,
         None => {
             // this means the amount owed is the amount collected from participation nft bids.
             auction_manager.get_collected_to_accept_payment(safety_deposit_config_info)?
@@ -234,6 +237,7 @@ pub fn process_empty_payment_account(
     let rent_info = next_account_info(account_info_iter)?;
     let auction_token_tracker_info = next_account_info(account_info_iter).ok();
     let safety_deposit_config_info = next_account_info(account_info_iter).ok();
+    let auction_extended_info = next_account_info(account_info_iter)?;
 
     if let Some(tracker_info) = auction_token_tracker_info {
         assert_derivation(
@@ -327,6 +331,22 @@ pub fn process_empty_payment_account(
         return Err(MetaplexError::AuctionManagerAuctionMismatch.into());
     }
 
+    // Winners on second-price auctions pay less than they bid, the shares are worked out on what
+    // was paid.
+    assert_owned_by(auction_extended_info, &store.auction_program)?;
+    assert_derivation(
+        &store.auction_program,
+        auction_extended_info,
+        &[
+            metaplex_auction::PREFIX.as_bytes(),
+            store.auction_program.as_ref(),
+            vault_info.key.as_ref(),
+            metaplex_auction::EXTENDED.as_bytes(),
+        ],
+    )?;
+    let settlement_mode =
+        AuctionDataExtended::from_account_info(auction_extended_info)?.settlement_mode;
+
     if safety_deposit.vault != *vault_info.key {
         return Err(MetaplexError::SafetyDepositBoxVaultMismatch.into());
     }
@@ -419,6 +439,7 @@ pub fn process_empty_payment_account(
         safety_deposit_config_info,
         &auction_manager,
         &auction,
+        settlement_mode.as_ref(),
         &metadata,
         &args.winning_config_index,
         &args.winning_config_item_index,
@@ -453,6 +474,15 @@ pub fn process_empty_payment_account(
             authority_seeds,
             token_program_info.clone(),
         )?;
+
+        MetaplexEvent::PayoutMade {
+            auction_manager: *auction_manager_info.key,
+            safety_deposit_box: *safety_deposit_info.key,
+            recipient: payout_ticket.recipient,
+            destination: *destination_info.key,
+            amount: final_amount,
+        }
+        .emit()?;
     }
 
     payout_ticket.serialize(&mut *payout_ticket_info.data.borrow_mut())?;
@@ -21,6 +21,7 @@ pub fn issue_end_auction<'a>(
     auction_program: AccountInfo<'a>,
     authority: AccountInfo<'a>,
     auction: AccountInfo<'a>,
+    auction_extended: AccountInfo<'a>,
     clock: AccountInfo<'a>,
     vault: Pubkey,
     reveal: Option<(u64, u64)>,
@@ -30,12 +31,13 @@ pub fn issue_end_auction<'a>(
         &end_auction_instruction(
             *auction_program.key,
             *authority.key,
+            Some(*auction_extended.key),
             EndAuctionArgs {
                 resource: vault,
                 reveal,
             },
         ),
-        &[auction_program, authority, auction, clock],
+        &[auction_program, authority, auction, clock, auction_extended],
         &[&signer_seeds],
     )?;
 
@@ -95,6 +97,7 @@ pub fn process_end_auction(
         auction_program_info.clone(),
         auction_manager_info.clone(),
         auction_info.clone(),
+        auction_data_extended_info.clone(),
         clock_info.clone(),
         auction_manager.vault(),
         args.reveal,
@@ -103,7 +106,11 @@ pub fn process_end_auction(
 
     if auction_data_extended.instant_sale_price.is_some() {
         match auction.bid_state {
-            BidState::EnglishAuction { .. } => {
+            BidState::EnglishAuction { .. }
+            | BidState::DutchAuction { .. }
+            | BidState::SealedBidAuction { .. }
+            | BidState::Raffle { .. }
+            | BidState::BidBook { .. } => {
                 auction_manager.set_status(AuctionManagerStatus::Disbursing);
             }
             BidState::OpenEdition { .. } => {
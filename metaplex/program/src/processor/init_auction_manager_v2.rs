@@ -1,6 +1,7 @@
 use {
     crate::{
         error::MetaplexError,
+        event::MetaplexEvent,
         state::{
             AuctionManagerStatus, AuctionManagerV2, AuctionWinnerTokenTypeTracker, Key, Store,
             TupleNumericType, MAX_AUCTION_MANAGER_V2_SIZE, PREFIX, TOTALS,
@@ -17,7 +18,7 @@ use {
         program_option::COption,
         pubkey::Pubkey,
     },
-    metaplex_auction::processor::{AuctionData, AuctionState},
+    metaplex_auction::processor::{AuctionData, AuctionDataExtended, AuctionState, BidState},
     spl_token::state::Account,
     metaplex_token_vault::state::{Vault, VaultState},
 };
@@ -27,6 +28,7 @@ pub fn assert_common_checks(
     auction_manager_info: &AccountInfo,
     vault_info: &AccountInfo,
     auction_info: &AccountInfo,
+    auction_extended_info: &AccountInfo,
     store_info: &AccountInfo,
     accept_payment_info: &AccountInfo,
     authority_info: &AccountInfo,
@@ -38,6 +40,7 @@ pub fn assert_common_checks(
     let store = Store::from_account_info(store_info)?;
     assert_owned_by(vault_info, &store.token_vault_program)?;
     assert_owned_by(auction_info, &store.auction_program)?;
+    assert_owned_by(auction_extended_info, &store.auction_program)?;
     assert_owned_by(store_info, program_id)?;
     assert_owned_by(accept_payment_info, &store.token_program)?;
 
@@ -53,6 +56,10 @@ pub fn assert_common_checks(
         return Err(MetaplexError::AuctionMustBeCreated.into());
     }
 
+    if let BidState::BidBook { .. } = auction.bid_state {
+        return Err(MetaplexError::BidBookAuctionNotSupported.into());
+    }
+
     let bump_seed = assert_derivation(
         program_id,
         auction_manager_info,
@@ -69,6 +76,28 @@ pub fn assert_common_checks(
         ],
     )?;
 
+    assert_derivation(
+        &store.auction_program,
+        auction_extended_info,
+        &[
+            metaplex_auction::PREFIX.as_bytes(),
+            &store.auction_program.as_ref(),
+            &vault_info.key.as_ref(),
+            metaplex_auction::EXTENDED.as_bytes(),
+        ],
+    )?;
+
+    // Winning bids are claimed into the accept payment account, which lamport bids and bids paid
+    // in other mints can't be.
+    let auction_extended = AuctionDataExtended::from_account_info(auction_extended_info)?;
+    if auction_extended.lamport_bids == Some(true) {
+        return Err(MetaplexError::LamportBidsNotSupported.into());
+    }
+
+    if auction_extended.payment_mints.is_some() {
+        return Err(MetaplexError::PaymentMintsNotSupported.into());
+    }
+
     if auction.token_mint != accept_payment.mint {
         return Err(MetaplexError::AuctionAcceptPaymentMintMismatch.into());
     }
@@ -115,12 +144,14 @@ pub fn process_init_auction_manager_v2(
     let store_info = next_account_info(account_info_iter)?;
     let system_info = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
+    let auction_extended_info = next_account_info(account_info_iter)?;
 
     let (bump_seed, _vault, _auction) = assert_common_checks(
         program_id,
         auction_manager_info,
         vault_info,
         auction_info,
+        auction_extended_info,
         store_info,
         accept_payment_info,
         authority_info,
@@ -194,5 +225,13 @@ pub fn process_init_auction_manager_v2(
         token_type_tracker.save(&auction_token_tracker_info);
     }
 
+    MetaplexEvent::AuctionManagerInitialized {
+        auction_manager: *auction_manager_info.key,
+        auction: *auction_info.key,
+        vault: *vault_info.key,
+        authority: *authority_info.key,
+    }
+    .emit()?;
+
     Ok(())
 }
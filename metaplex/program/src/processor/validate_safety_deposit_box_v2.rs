@@ -479,7 +479,7 @@ pub fn process_validate_safety_deposit_box_v2<'a>(
         total_amount_requested,
     })?;
 
-    if safety_deposit_config.order != safety_deposit.order as u64 {
+    if safety_deposit_config.order != safety_deposit.order {
         return Err(MetaplexError::SafetyDepositConfigOrderMismatch.into());
     }
 
@@ -508,7 +508,7 @@ pub fn process_validate_safety_deposit_box_v2<'a>(
         .checked_add(1)
         .ok_or(MetaplexError::NumericalOverflowError)?;
 
-    if auction_manager.state.safety_config_items_validated == vault.token_type_count as u64 {
+    if auction_manager.state.safety_config_items_validated == vault.token_type_count {
         auction_manager.state.status = AuctionManagerStatus::Validated
     }
 
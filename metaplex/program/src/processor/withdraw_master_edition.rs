@@ -95,7 +95,8 @@ pub fn process_withdraw_master_edition<'a>(
         return Err(MetaplexError::AuctionManagerStoreMismatch.into());
     }
 
-    if auction.state != AuctionState::Ended {
+    let cancelled = auction.state == AuctionState::Cancelled;
+    if auction.state != AuctionState::Ended && !cancelled {
         return Err(MetaplexError::AuctionHasNotEnded.into());
     }
 
@@ -117,15 +118,16 @@ pub fn process_withdraw_master_edition<'a>(
 
     if prize_tracking_ticket_info.data_is_empty() {
         // Nobody has redeemed yet, we need to figure out if SOMEONE could and if we should
-        // stop a withdrawal.
+        // stop a withdrawal. Nobody can redeem from a cancelled auction.
 
         let max_bids_allowed_before_removal_is_stopped = auction_manager
             .get_max_bids_allowed_before_removal_is_stopped(
-                safety_deposit_box.order as u64,
+                safety_deposit_box.order,
                 safety_deposit_config_info,
             )?;
-        if auction_data_extended.total_uncancelled_bids
-            > max_bids_allowed_before_removal_is_stopped as u64
+        if !cancelled
+            && auction_data_extended.total_uncancelled_bids
+                > max_bids_allowed_before_removal_is_stopped as u64
         {
             return Err(MetaplexError::NotAllBidsClaimed.into());
         }
@@ -148,7 +150,7 @@ pub fn process_withdraw_master_edition<'a>(
     }
 
     auction_manager.assert_is_valid_master_edition_v2_safety_deposit(
-        safety_deposit_box.order as u64,
+        safety_deposit_box.order,
         safety_deposit_config_info,
     )?;
 
@@ -807,7 +807,7 @@ pub struct SafetyDepositConfig {
     pub key: Key,
     /// reverse lookup
     pub auction_manager: Pubkey,
-    // only 255 safety deposits on vault right now but soon this will likely expand.
+    // only 255 safety deposits on a VaultV1, VaultV2 lifts that limit.
     /// safety deposit order
     pub order: u64,
     pub winning_config_type: WinningConfigType,
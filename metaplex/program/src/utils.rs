@@ -1,6 +1,7 @@
 use {
     crate::{
         error::MetaplexError,
+        event::MetaplexEvent,
         state::{
             get_auction_manager, AuctionManager, AuctionManagerStatus, BidRedemptionTicket, Key,
             OriginalAuthorityLookup, Store, WhitelistedCreator, PREFIX,
@@ -663,12 +664,19 @@ pub fn common_redeem_checks(
         return Err(MetaplexError::AuctionManagerTokenMetadataProgramMismatch.into());
     }
 
-    assert_auction_is_ended_or_valid_instant_sale(
-        auction_info,
-        auction_extended_info,
-        bidder_metadata_info,
-        win_index,
-    )?;
+    // A cancelled auction has no winners, only the auctioneer may take the items back out.
+    if AuctionData::get_state(auction_info)? == AuctionState::Cancelled {
+        if overwrite_win_index.is_none() {
+            return Err(MetaplexError::AuctionCancelled.into());
+        }
+    } else {
+        assert_auction_is_ended_or_valid_instant_sale(
+            auction_info,
+            auction_extended_info,
+            bidder_metadata_info,
+            win_index,
+        )?;
+    }
 
     // No-op if already set.
     auction_manager.set_status(AuctionManagerStatus::Disbursing);
@@ -757,6 +765,16 @@ pub fn common_redeem_finish(args: CommonRedeemFinishArgs) -> ProgramResult {
         )?;
     }
 
+    if bid_redeemed || participation_redeemed {
+        MetaplexEvent::PrizeRedeemed {
+            auction_manager: *auction_manager_info.key,
+            bidder_metadata: *bidder_metadata_info.key,
+            winning_index: winning_index.map(|index| index as u64),
+            participation: participation_redeemed,
+        }
+        .emit()?;
+    }
+
     msg!("About to pass through the eye of the needle");
     sol_log_compute_units();
 
@@ -932,6 +950,7 @@ pub fn end_auction<'a: 'b, 'b>(
         &end_auction_instruction(
             *auction_program.key,
             *authority.key,
+            None,
             EndAuctionArgs {
                 resource,
                 reveal: None,
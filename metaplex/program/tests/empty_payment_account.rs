@@ -0,0 +1,79 @@
+#![cfg(feature = "test-bpf")]
+mod utils;
+
+use borsh::BorshSerialize;
+use metaplex::{
+    id,
+    instruction::{EmptyPaymentAccountArgs, MetaplexInstruction},
+};
+use metaplex_auction::processor::{AuctionDataExtended, SettlementMode};
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+use utils::*;
+
+#[tokio::test]
+async fn empty_payment_account_fail_without_auction_extended() {
+    // The accept payment account only holds the second price, paying creators their share of the
+    // winning bid would take more than it has.
+    let mut program_test = ProgramTest::new("metaplex", id(), None);
+    let accounts = add_auction_accounts(
+        &mut program_test,
+        Pubkey::new_unique(),
+        AuctionDataExtended {
+            settlement_mode: Some(SettlementMode::SecondPrice),
+            ..auction_extended()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let instruction = Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(accounts.accept_payment, false),
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new(accounts.auction_manager, false),
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(accounts.store, false),
+            AccountMeta::new_readonly(accounts.vault, false),
+            AccountMeta::new_readonly(accounts.auction, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(accounts.token_tracker, false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        ],
+        data: MetaplexInstruction::EmptyPaymentAccount(EmptyPaymentAccountArgs {
+            winning_config_index: Some(0),
+            winning_config_item_index: Some(0),
+            creator_index: Some(0),
+        })
+        .try_to_vec()
+        .unwrap(),
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "test-bpf")]
+mod utils;
+
+use metaplex::{error::MetaplexError, id, instruction, state::TupleNumericType};
+use metaplex_auction::processor::{AuctionDataExtended, PaymentMint, PaymentRate};
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+    transport::TransportError,
+};
+use utils::*;
+
+async fn init_auction_manager(auction_extended: AuctionDataExtended) -> Result<(), TransportError> {
+    let authority = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new("metaplex", id(), None);
+    let accounts = add_auction_accounts(&mut program_test, authority, auction_extended);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::create_init_auction_manager_v2_instruction(
+            id(),
+            accounts.token_tracker,
+            accounts.auction_manager,
+            accounts.vault,
+            accounts.auction,
+            accounts.auction_extended,
+            authority,
+            payer.pubkey(),
+            accounts.accept_payment,
+            accounts.store,
+            TupleNumericType::U8,
+            TupleNumericType::U8,
+            1,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn init_auction_manager_v2_success() {
+    init_auction_manager(auction_extended())
+        .await
+        .expect("init_auction_manager_v2");
+}
+
+#[tokio::test]
+async fn init_auction_manager_v2_fail_lamport_bids() {
+    // Winning bids are claimed into the accept payment account, so their pots must be token
+    // accounts, which lamport bids are not.
+    let err = init_auction_manager(AuctionDataExtended {
+        lamport_bids: Some(true),
+        ..auction_extended()
+    })
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MetaplexError::LamportBidsNotSupported as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn init_auction_manager_v2_fail_payment_mints() {
+    // Bids paid in another mint would have to be claimed into an accept payment account of that
+    // mint, the auction manager only has one for the token mint.
+    let err = init_auction_manager(AuctionDataExtended {
+        payment_mints: Some(vec![PaymentMint {
+            mint: Pubkey::new_unique(),
+            rate: PaymentRate::Fixed {
+                numerator: 1,
+                denominator: 1,
+            },
+        }]),
+        ..auction_extended()
+    })
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MetaplexError::PaymentMintsNotSupported as u32)
+        )
+    );
+}
@@ -0,0 +1,179 @@
+use borsh::BorshSerialize;
+use metaplex::{
+    id,
+    state::{Key, Store, MAX_STORE_SIZE, PREFIX, TOTALS},
+};
+use metaplex_auction::processor::{
+    AuctionData, AuctionDataExtended, AuctionState, BidState, PriceFloor,
+    MAX_AUCTION_DATA_EXTENDED_SIZE,
+};
+use metaplex_token_vault::state::{Key as VaultKey, Vault, VaultState, MAX_VAULT_V2_SIZE};
+use solana_program::{program_option::COption, program_pack::Pack};
+use solana_program_test::*;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+pub struct AuctionAccounts {
+    pub store: Pubkey,
+    pub vault: Pubkey,
+    pub auction: Pubkey,
+    pub auction_extended: Pubkey,
+    pub auction_manager: Pubkey,
+    pub token_tracker: Pubkey,
+    pub accept_payment: Pubkey,
+}
+
+pub fn program_account(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Sets up a combined vault and a created auction over it, both under the given authority, as
+/// InitAuctionManagerV2 expects to find them.
+pub fn add_auction_accounts(
+    program_test: &mut ProgramTest,
+    authority: Pubkey,
+    auction_extended: AuctionDataExtended,
+) -> AuctionAccounts {
+    let auction_program = Pubkey::new_unique();
+    let vault_program = Pubkey::new_unique();
+
+    let store = Pubkey::new_unique();
+    let mut data = vec![0; MAX_STORE_SIZE];
+    Store {
+        key: Key::StoreV1,
+        public: true,
+        auction_program,
+        token_vault_program: vault_program,
+        token_metadata_program: Pubkey::new_unique(),
+        token_program: spl_token::id(),
+    }
+    .serialize(&mut data.as_mut_slice())
+    .unwrap();
+    program_test.add_account(store, program_account(id(), data));
+
+    let vault = Pubkey::new_unique();
+    let mut data = vec![0; MAX_VAULT_V2_SIZE];
+    Vault {
+        key: VaultKey::VaultV2,
+        token_program: spl_token::id(),
+        fraction_mint: Pubkey::new_unique(),
+        authority,
+        fraction_treasury: Pubkey::new_unique(),
+        redeem_treasury: Pubkey::new_unique(),
+        allow_further_share_creation: false,
+        pricing_lookup_address: Pubkey::new_unique(),
+        token_type_count: 1,
+        state: VaultState::Combined,
+        locked_price_per_share: 0,
+        original_authority: Pubkey::new_unique(),
+    }
+    .serialize(&mut data.as_mut_slice())
+    .unwrap();
+    program_test.add_account(vault, program_account(vault_program, data));
+
+    let (auction, _) = Pubkey::find_program_address(
+        &[
+            metaplex_auction::PREFIX.as_bytes(),
+            auction_program.as_ref(),
+            vault.as_ref(),
+        ],
+        &auction_program,
+    );
+    let data = AuctionData {
+        authority,
+        bid_state: BidState::new_english(1),
+        end_auction_at: None,
+        end_auction_gap: None,
+        ended_at: None,
+        last_bid: None,
+        price_floor: PriceFloor::None([0; 32]),
+        state: AuctionState::create(),
+        token_mint: spl_token::native_mint::id(),
+    }
+    .try_to_vec()
+    .unwrap();
+    program_test.add_account(auction, program_account(auction_program, data));
+
+    let (auction_extended_key, _) = Pubkey::find_program_address(
+        &[
+            metaplex_auction::PREFIX.as_bytes(),
+            auction_program.as_ref(),
+            vault.as_ref(),
+            metaplex_auction::EXTENDED.as_bytes(),
+        ],
+        &auction_program,
+    );
+    let mut data = vec![0; MAX_AUCTION_DATA_EXTENDED_SIZE];
+    auction_extended
+        .serialize(&mut data.as_mut_slice())
+        .unwrap();
+    program_test.add_account(auction_extended_key, program_account(auction_program, data));
+
+    let (auction_manager, _) =
+        Pubkey::find_program_address(&[PREFIX.as_bytes(), auction.as_ref()], &id());
+    let (token_tracker, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            id().as_ref(),
+            auction_manager.as_ref(),
+            TOTALS.as_bytes(),
+        ],
+        &id(),
+    );
+
+    let accept_payment = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: spl_token::native_mint::id(),
+        owner: auction_manager,
+        amount: 0,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    program_test.add_account(accept_payment, program_account(spl_token::id(), data));
+
+    AuctionAccounts {
+        store,
+        vault,
+        auction,
+        auction_extended: auction_extended_key,
+        auction_manager,
+        token_tracker,
+        accept_payment,
+    }
+}
+
+pub fn auction_extended() -> AuctionDataExtended {
+    AuctionDataExtended {
+        total_uncancelled_bids: 0,
+        tick_size: None,
+        gap_tick_size_percentage: None,
+        instant_sale_price: None,
+        name: None,
+        dutch_auction_schedule: None,
+        sealed_bid_config: None,
+        settlement_mode: None,
+        proxy_bidder: None,
+        bid_ledger_entries: None,
+        payer: None,
+        open_bidder_pots: Some(0),
+        start_auction_at: None,
+        bid_gate: None,
+        raffle_config: None,
+        raffle_seed: None,
+        raffle_draw_slot: None,
+        bid_book: None,
+        payment_mints: None,
+        lamport_bids: None,
+        cancellation_rules: None,
+    }
+}
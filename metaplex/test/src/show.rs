@@ -41,7 +41,7 @@ pub fn send_show(app_matches: &ArgMatches, _payer: Keypair, client: RpcClient) {
     }
 
     let auction_data = client.get_account(&auction_key).unwrap();
-    let auction: AuctionData = try_from_slice_unchecked(&auction_data.data).unwrap();
+    let auction = AuctionData::from_bytes(&auction_data.data).unwrap();
     let auction_program = metaplex_auction::id();
     let seeds = &[
         metaplex_auction::PREFIX.as_bytes(),
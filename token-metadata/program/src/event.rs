@@ -0,0 +1,49 @@
+//! Events logged by the metadata program, so indexers can follow metadata and editions without
+//! parsing its other log messages. Each event is logged as EVENT_PREFIX followed by its Borsh
+//! serialization in base64. New events and fields are only ever appended, so older logs keep
+//! decoding.
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{entrypoint::ProgramResult, msg, pubkey::Pubkey},
+};
+
+/// Marks an event among the program logs, followed by the encoded event.
+pub const EVENT_PREFIX: &str = "metaplex-token-metadata event: ";
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub enum MetadataEvent {
+    /// Metadata was created for a mint.
+    MetadataCreated {
+        metadata: Pubkey,
+        mint: Pubkey,
+        update_authority: Pubkey,
+    },
+    /// Metadata was changed by its update authority.
+    MetadataUpdated {
+        metadata: Pubkey,
+        update_authority: Pubkey,
+    },
+    /// A mint became a master edition, printing at most max_supply editions when set.
+    MasterEditionCreated {
+        master_edition: Pubkey,
+        mint: Pubkey,
+        max_supply: Option<u64>,
+    },
+    /// Edition number edition_number of a master edition was printed to mint.
+    EditionMinted {
+        master_edition: Pubkey,
+        edition: Pubkey,
+        mint: Pubkey,
+        edition_number: u64,
+    },
+}
+
+impl MetadataEvent {
+    /// Logs the event for indexers.
+    pub fn emit(&self) -> ProgramResult {
+        msg!("{}{}", EVENT_PREFIX, base64::encode(self.try_to_vec()?));
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ pub mod deprecated_instruction;
 pub mod deprecated_processor;
 pub mod entrypoint;
 pub mod error;
+pub mod event;
 pub mod instruction;
 pub mod processor;
 pub mod state;
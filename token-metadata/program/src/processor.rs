@@ -6,6 +6,7 @@ use crate::{
         process_deprecated_set_reservation_list,
     },
     error::MetadataError,
+    event::MetadataEvent,
     instruction::MetadataInstruction,
     state::{
         Data, Key, MasterEditionV1, MasterEditionV2, Metadata, EDITION, MAX_MASTER_EDITION_LEN,
@@ -215,7 +216,12 @@ pub fn process_update_metadata_accounts(
     puff_out_data_fields(&mut metadata);
 
     metadata.serialize(&mut *metadata_account_info.data.borrow_mut())?;
-    Ok(())
+
+    MetadataEvent::MetadataUpdated {
+        metadata: *metadata_account_info.key,
+        update_authority: metadata.update_authority,
+    }
+    .emit()
 }
 
 pub fn process_update_primary_sale_happened_via_token(
@@ -373,7 +379,12 @@ pub fn process_create_master_edition(
         token_program_info,
     )?;
 
-    Ok(())
+    MetadataEvent::MasterEditionCreated {
+        master_edition: *edition_account_info.key,
+        mint: *mint_info.key,
+        max_supply,
+    }
+    .emit()
 }
 
 pub fn process_mint_new_edition_from_master_edition_via_token<'a>(
@@ -419,7 +430,15 @@ pub fn process_mint_new_edition_from_master_edition_via_token<'a>(
         },
         edition,
         ignore_owner_signer,
-    )
+    )?;
+
+    MetadataEvent::EditionMinted {
+        master_edition: *master_edition_account_info.key,
+        edition: *new_edition_account_info.key,
+        mint: *mint_info.key,
+        edition_number: edition,
+    }
+    .emit()
 }
 
 pub fn process_convert_master_edition_v1_to_v2(
@@ -576,7 +595,15 @@ pub fn process_mint_new_edition_from_master_edition_via_vault_proxy<'a>(
         rent_info,
     };
 
-    process_mint_new_edition_from_master_edition_via_token_logic(program_id, args, edition, true)
+    process_mint_new_edition_from_master_edition_via_token_logic(program_id, args, edition, true)?;
+
+    MetadataEvent::EditionMinted {
+        master_edition: *master_edition_account_info.key,
+        edition: *new_edition_account_info.key,
+        mint: *mint_info.key,
+        edition_number: edition,
+    }
+    .emit()
 }
 
 /// Puff out the variable length fields to a fixed length on a metadata
@@ -1,5 +1,6 @@
 use crate::{
     error::MetadataError,
+    event::MetadataEvent,
     state::{
         get_reservation_list, Data, EditionMarker, Key, MasterEditionV1, Metadata, EDITION,
         EDITION_MARKER_BIT_SIZE, MAX_CREATOR_LIMIT, MAX_EDITION_LEN, MAX_EDITION_MARKER_SIZE,
@@ -895,6 +896,13 @@ pub fn process_create_metadata_accounts_logic(
 
     metadata.serialize(&mut *metadata_account_info.data.borrow_mut())?;
 
+    MetadataEvent::MetadataCreated {
+        metadata: *metadata_account_info.key,
+        mint: *mint_info.key,
+        update_authority: update_authority_key,
+    }
+    .emit()?;
+
     Ok(())
 }
 
@@ -232,6 +232,76 @@ pub enum VaultError {
     /// Derived key invalid
     #[error("Derived key invalid")]
     DerivedKeyInvalid,
+
+    /// Only the buyout authority of the vault can combine it for a buyout
+    #[error("Buyout authority invalid")]
+    InvalidBuyoutAuthority,
+
+    /// No circulating shares to pay the buyout out to
+    #[error("No circulating shares to pay the buyout out to")]
+    NoCirculatingShares,
+
+    /// A vault can only hold votes for so many distinct reserve prices
+    #[error("Too many distinct reserve prices voted for")]
+    TooManyReservePrices,
+
+    /// The vote being removed was never counted
+    #[error("Reserve vote not found")]
+    ReserveVoteNotFound,
+
+    /// Combining needs the reserve price share holders voted for
+    #[error("No share holder has voted on the reserve price")]
+    NoReserveVotes,
+
+    /// The vote escrow must be the one recorded on the reserve votes, an empty fraction mint
+    /// account owned by the vault pda when first used
+    #[error("Vote escrow invalid")]
+    InvalidVoteEscrow,
+
+    /// Only the holder who locked the shares can unlock them
+    #[error("Voter does not match the vote record")]
+    InvalidVoter,
+
+    /// Too few of the circulating shares are locked in reserve votes for their median to count
+    #[error("Reserve votes have not reached quorum")]
+    ReserveQuorumNotReached,
+
+    /// A reserve price has to be above zero
+    #[error("Invalid reserve price")]
+    InvalidReservePrice,
+
+    /// Removing a box needs the box holding the last order of the vault, to move into its place
+    #[error("Safety deposit box with the last order missing or invalid")]
+    LastSafetyDepositBoxInvalid,
+
+    /// Token close account failed
+    #[error("Token close account failed")]
+    TokenCloseFailed,
+
+    /// In-kind redemption needs every safety deposit box of the vault, in order
+    #[error("Safety deposit boxes missing or out of order")]
+    SafetyDepositBoxesIncomplete,
+
+    /// Shares can't be redeemed in kind while a buyout auction of the vault is running or won
+    #[error("Vault has a buyout auction")]
+    BuyoutInProgress,
+
+    /// A VaultV1 holds at most 255 safety deposit boxes, migrate it to VaultV2 for more
+    #[error("Vault needs migrating to VaultV2")]
+    VaultNeedsMigration,
+
+    /// Only VaultV1 vaults that are still inactive can be migrated
+    #[error("Vault cannot be migrated")]
+    VaultCannotBeMigrated,
+
+    /// Token set authority failed
+    #[error("Token set authority failed")]
+    TokenSetAuthorityFailed,
+
+    /// Shares can't be redeemed in kind once the vault authority was handed to someone else, like
+    /// an auction manager relying on the safety deposit boxes
+    #[error("Vault authority is not its original authority")]
+    VaultAuthorityChanged,
 }
 
 impl PrintProgramError for VaultError {
@@ -0,0 +1,97 @@
+//! Events logged by the vault program, so indexers can follow vaults without parsing its other
+//! log messages. Each event is logged as EVENT_PREFIX followed by its Borsh serialization in
+//! base64. New events and fields are only ever appended, so older logs keep decoding.
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{entrypoint::ProgramResult, msg, pubkey::Pubkey},
+};
+
+/// Marks an event among the program logs, followed by the encoded event.
+pub const EVENT_PREFIX: &str = "metaplex-token-vault event: ";
+
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub enum VaultEvent {
+    /// A vault was initialized, inactive until its tokens are added.
+    VaultInitialized {
+        vault: Pubkey,
+        authority: Pubkey,
+        fraction_mint: Pubkey,
+    },
+    /// Tokens were locked in a safety deposit box of an inactive vault.
+    TokenAdded {
+        vault: Pubkey,
+        safety_deposit_box: Pubkey,
+        token_mint: Pubkey,
+        amount: u64,
+    },
+    /// A vault was activated, minting its first shares to the fraction treasury.
+    VaultActivated {
+        vault: Pubkey,
+        number_of_shares: u64,
+    },
+    /// More shares were minted to the fraction treasury.
+    SharesMinted {
+        vault: Pubkey,
+        number_of_shares: u64,
+    },
+    /// The outstanding shares were bought out, handing the vault to new_authority.
+    VaultCombined {
+        vault: Pubkey,
+        new_authority: Pubkey,
+        price_per_share: u64,
+        paid: u64,
+    },
+    /// Shares of a combined vault were redeemed for their part of the buyout.
+    SharesRedeemed {
+        vault: Pubkey,
+        number_of_shares: u64,
+        paid: u64,
+    },
+    /// Tokens were taken out of a safety deposit box of a combined vault.
+    TokenWithdrawn {
+        vault: Pubkey,
+        safety_deposit_box: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    },
+    /// A holder locked shares to vote for a reserve price, shares is their total locked.
+    ReserveVoted {
+        vault: Pubkey,
+        voter: Pubkey,
+        shares: u64,
+        price_per_share: u64,
+        median: Option<u64>,
+    },
+    /// A holder unlocked their shares, withdrawing their reserve vote.
+    SharesUnlocked {
+        vault: Pubkey,
+        voter: Pubkey,
+        shares: u64,
+        median: Option<u64>,
+    },
+    /// Tokens were returned from an inactive vault and their safety deposit box closed.
+    TokenRemoved {
+        vault: Pubkey,
+        safety_deposit_box: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    },
+    /// Shares of an active vault were burned for their part of every safety deposit box.
+    SharesRedeemedInKind {
+        vault: Pubkey,
+        number_of_shares: u64,
+        amounts: Vec<u64>,
+    },
+    /// An inactive VaultV1 moved to a new VaultV2 account, along with its safety deposit boxes.
+    VaultMigrated { vault: Pubkey, new_vault: Pubkey },
+}
+
+impl VaultEvent {
+    /// Logs the event for indexers.
+    pub fn emit(&self) -> ProgramResult {
+        msg!("{}{}", EVENT_PREFIX, base64::encode(self.try_to_vec()?));
+        Ok(())
+    }
+}
@@ -1,5 +1,8 @@
 use {
-    crate::state::{ExternalPriceAccount, Key},
+    crate::{
+        state::{ExternalPriceAccount, Key, PREFIX, VOTE, VOTES},
+        utils::find_buyout_auction_address,
+    },
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         instruction::{AccountMeta, Instruction},
@@ -32,6 +35,15 @@ pub struct MintEditionProxyArgs {
     pub edition: u64,
 }
 
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct VoteArgs {
+    /// Shares to lock on top of any already locked, can be zero to only change the price
+    pub amount: u64,
+    /// Price per share the voter wants as the reserve
+    pub price_per_share: u64,
+}
+
 /// Instructions supported by the Fraction program.
 #[derive(BorshSerialize, BorshDeserialize, Clone)]
 pub enum VaultInstruction {
@@ -85,6 +97,9 @@ pub enum VaultInstruction {
     ///   9. `[]` PDA-based Burn authority for the fraction treasury account containing the uncirculated shares seed [PREFIX, program_id]
     ///   10. `[]` External pricing lookup address
     ///   11. `[]` Token program
+    ///   12. `[]` Reserve votes of the vault, the price per share is their share-weighted median once RESERVE_VOTE_QUORUM_BASIS_POINTS of the circulating shares are locked seed [PREFIX, program_id, vault key, 'votes']
+    ///            Required while shares are circulating, so clients passing only the first 12 accounts can no longer combine those vaults.
+    ///            The external pricing account sets the price when no shares circulate, or when nobody has voted.
     CombineVault,
 
     /// If in the combine state, shareholders can hit this endpoint to burn shares in exchange for monies from the treasury.
@@ -153,6 +168,100 @@ pub enum VaultInstruction {
     ///   1. `[signer]` Vault authority
     ///   2. `[]` New authority
     SetAuthority,
+
+    /// Combines an active vault for the winner of its buyout auction, without the vault authority
+    /// or external pricing account. The winning bid has already been paid into the redeem treasury,
+    /// and is split evenly over the circulating shares for RedeemShares. Any remainder of the split
+    /// stays in the redeem treasury.
+    ///   0. `[writable]` Initialized activated token vault
+    ///   1. `[writable]` Fraction mint
+    ///   2. `[writable]` Fraction treasury account, its uncirculated shares are burned
+    ///   3. `[]` Redeem treasury account holding the winning bid
+    ///   4. `[]` New authority on the vault, the winner of the buyout
+    ///   5. `[signer]` Buyout authority, pda of the buyout auction program with seed ['auction', auction program id, vault key, 'buyout']
+    ///   6. `[]` PDA-based Burn authority for the fraction treasury account seed [PREFIX, program_id, vault key]
+    ///   7. `[]` Token program
+    CombineVaultByBuyout(AmountArgs),
+
+    /// Locks fractional shares of an active vault into its vote escrow to vote for a reserve price.
+    /// Voting again adds to the locked shares and moves all of them to the new price. The price must
+    /// be above zero. Once MAX_RESERVE_PRICES prices hold votes, a new price evicts the one with the
+    /// fewest shares, whose voters have to vote again to count.
+    ///   0. `[writable]` Vote record of the voter (will be created if needed) seed [PREFIX, program_id, vault key, voter, 'vote']
+    ///   1. `[writable]` Reserve votes of the vault (will be created if needed) seed [PREFIX, program_id, vault key, 'votes']
+    ///   2. `[writable]` Vote escrow, fraction mint account owned by the pda [PREFIX, program_id, vault key], recorded by the first vote
+    ///   3. `[writable]` Token account holding the shares to lock
+    ///   4. `[]` Initialized active token vault
+    ///   5. `[signer]` Voter
+    ///   6. `[signer]` Transfer authority to move the shares into the vote escrow
+    ///   7. `[signer]` Payer
+    ///   8. `[]` Token program
+    ///   9. `[]` Rent sysvar
+    ///   10. `[]` System program
+    LockSharesForVote(VoteArgs),
+
+    /// Unlocks every share a holder locked to vote, removing their vote.
+    ///   0. `[writable]` Vote record of the voter seed [PREFIX, program_id, vault key, voter, 'vote']
+    ///   1. `[writable]` Reserve votes of the vault seed [PREFIX, program_id, vault key, 'votes']
+    ///   2. `[writable]` Vote escrow
+    ///   3. `[writable]` Destination account for the shares
+    ///   4. `[]` Token vault
+    ///   5. `[signer]` Voter
+    ///   6. `[]` PDA-based Transfer authority to move the shares out of the vote escrow seed [PREFIX, program_id, vault key]
+    ///   7. `[]` Token program
+    UnlockVoteShares,
+
+    /// Returns every token in a safety deposit box of an inactive vault and closes the box and its
+    /// store. The box holding the last order takes over the order of the removed one, so orders
+    /// stay contiguous.
+    ///   0. `[writable]` The safety deposit box account key for the tokens
+    ///   1. `[writable]` The store key on the safety deposit box account
+    ///   2. `[writable]` Initialized Destination account for the tokens
+    ///   3. `[writable]` Initialized inactive token vault
+    ///   4. `[signer]` Authority on the vault
+    ///   5. `[]` PDA-based Transfer authority to move the tokens from the store to the destination and close it seed [PREFIX, program_id, vault key]
+    ///   6. `[writable]` Account receiving the rent of the closed safety deposit box and store
+    ///   7. `[]` Token program
+    ///   8. `[writable]` The safety deposit box with the last order, unless it is the one being removed
+    RemoveTokenFromInactiveVault,
+
+    /// Burns fractional shares of an active vault for the same part of every safety deposit box,
+    /// number of shares / fraction supply of each store, rounded down. Refused once the vault
+    /// authority is no longer the one it was initialized with, which a VaultV1 doesn't record, and
+    /// while a buyout auction of the vault exists that wasn't cancelled or closed without bids.
+    ///   0. `[writable]` Initialized Token account containing your fractional shares
+    ///   1. `[writable]` Fraction mint
+    ///   2. `[]` Initialized active token vault
+    ///   3. `[signer]` Burn authority for the burning of your shares
+    ///   4. `[]` PDA-based Transfer authority to move the tokens from the stores seed [PREFIX, program_id, vault key]
+    ///   5. `[]` Token program
+    ///   6. `[]` Buyout auction of the vault, pda of the buyout auction program with seed ['auction', auction program id, buyout authority]
+    ///   Followed by, for every safety deposit box of the vault in order:
+    ///   `[]` The safety deposit box
+    ///   `[writable]` The store key on the safety deposit box
+    ///   `[writable]` Initialized Destination account for the tokens of the box
+    InKindRedeem(NumberOfShareArgs),
+
+    /// Moves an inactive VaultV1 onto the VaultV2 layout, which holds more than 255 safety deposit
+    /// boxes. Accounts cannot grow, so the vault moves to a new account: the fraction mint, both
+    /// treasuries and every store are handed to the pda of the new vault, and every box is
+    /// recreated as a SafetyDepositBoxV2 under the new vault. The old accounts are closed.
+    ///   0. `[writable]` Initialized inactive VaultV1
+    ///   1. `[writable]` Uninitialized new vault account, allocated at MAX_VAULT_V2_SIZE and owned by this program
+    ///   2. `[writable]` Fraction mint
+    ///   3. `[writable]` Fraction treasury
+    ///   4. `[writable]` Redeem treasury
+    ///   5. `[signer]` Authority on the vault
+    ///   6. `[]` PDA-based authority of the old vault seed [PREFIX, program_id, vault key]
+    ///   7. `[writable, signer]` Payer for the new boxes, receiving the rent of the closed accounts
+    ///   8. `[]` Token program
+    ///   9. `[]` Rent sysvar
+    ///   10. `[]` System program
+    ///   Followed by, for every safety deposit box of the vault in order:
+    ///   `[writable]` The safety deposit box
+    ///   `[writable]` The new safety deposit box seed [PREFIX, new vault key, token mint]
+    ///   `[writable]` The store key on the safety deposit box
+    MigrateVaultToV2,
 }
 
 /// Creates an InitVault instruction
@@ -286,6 +395,16 @@ pub fn create_combine_vault_instruction(
     uncirculated_burn_authority: Pubkey,
     external_pricing_account: Pubkey,
 ) -> Instruction {
+    let (reserve_votes, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault.as_ref(),
+            VOTES.as_bytes(),
+        ],
+        &program_id,
+    );
+
     Instruction {
         program_id,
         accounts: vec![
@@ -301,6 +420,7 @@ pub fn create_combine_vault_instruction(
             AccountMeta::new_readonly(uncirculated_burn_authority, false),
             AccountMeta::new_readonly(external_pricing_account, false),
             AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(reserve_votes, false),
         ],
         data: VaultInstruction::CombineVault.try_to_vec().unwrap(),
     }
@@ -462,3 +582,271 @@ pub fn create_set_authority_instruction(
         data: VaultInstruction::SetAuthority.try_to_vec().unwrap(),
     }
 }
+
+/// Creates an CombineVaultByBuyout instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_combine_vault_by_buyout_instruction(
+    program_id: Pubkey,
+    vault: Pubkey,
+    fraction_mint: Pubkey,
+    fraction_treasury: Pubkey,
+    redeem_treasury: Pubkey,
+    new_authority: Pubkey,
+    buyout_authority: Pubkey,
+    uncirculated_burn_authority: Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new(fraction_mint, false),
+            AccountMeta::new(fraction_treasury, false),
+            AccountMeta::new_readonly(redeem_treasury, false),
+            AccountMeta::new_readonly(new_authority, false),
+            AccountMeta::new_readonly(buyout_authority, true),
+            AccountMeta::new_readonly(uncirculated_burn_authority, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: VaultInstruction::CombineVaultByBuyout(AmountArgs { amount })
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates an LockSharesForVote instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_lock_shares_for_vote_instruction(
+    program_id: Pubkey,
+    vault: Pubkey,
+    vote_escrow: Pubkey,
+    shares_account: Pubkey,
+    voter: Pubkey,
+    transfer_authority: Pubkey,
+    payer: Pubkey,
+    amount: u64,
+    price_per_share: u64,
+) -> Instruction {
+    let (vote_record, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault.as_ref(),
+            voter.as_ref(),
+            VOTE.as_bytes(),
+        ],
+        &program_id,
+    );
+    let (reserve_votes, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault.as_ref(),
+            VOTES.as_bytes(),
+        ],
+        &program_id,
+    );
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(vote_record, false),
+            AccountMeta::new(reserve_votes, false),
+            AccountMeta::new(vote_escrow, false),
+            AccountMeta::new(shares_account, false),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new_readonly(voter, true),
+            AccountMeta::new_readonly(transfer_authority, true),
+            AccountMeta::new_readonly(payer, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: VaultInstruction::LockSharesForVote(VoteArgs {
+            amount,
+            price_per_share,
+        })
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+/// Creates an UnlockVoteShares instruction
+pub fn create_unlock_vote_shares_instruction(
+    program_id: Pubkey,
+    vault: Pubkey,
+    vote_escrow: Pubkey,
+    destination: Pubkey,
+    voter: Pubkey,
+) -> Instruction {
+    let (vote_record, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault.as_ref(),
+            voter.as_ref(),
+            VOTE.as_bytes(),
+        ],
+        &program_id,
+    );
+    let (reserve_votes, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault.as_ref(),
+            VOTES.as_bytes(),
+        ],
+        &program_id,
+    );
+    let (transfer_authority, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), program_id.as_ref(), vault.as_ref()],
+        &program_id,
+    );
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(vote_record, false),
+            AccountMeta::new(reserve_votes, false),
+            AccountMeta::new(vote_escrow, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new_readonly(voter, true),
+            AccountMeta::new_readonly(transfer_authority, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: VaultInstruction::UnlockVoteShares.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an RemoveTokenFromInactiveVault instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_remove_token_from_inactive_vault_instruction(
+    program_id: Pubkey,
+    safety_deposit_box: Pubkey,
+    store: Pubkey,
+    destination: Pubkey,
+    vault: Pubkey,
+    vault_authority: Pubkey,
+    rent_receiver: Pubkey,
+    last_safety_deposit_box: Option<Pubkey>,
+) -> Instruction {
+    let (transfer_authority, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), program_id.as_ref(), vault.as_ref()],
+        &program_id,
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new(safety_deposit_box, false),
+        AccountMeta::new(store, false),
+        AccountMeta::new(destination, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(vault_authority, true),
+        AccountMeta::new_readonly(transfer_authority, false),
+        AccountMeta::new(rent_receiver, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    if let Some(last_safety_deposit_box) = last_safety_deposit_box {
+        accounts.push(AccountMeta::new(last_safety_deposit_box, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: VaultInstruction::RemoveTokenFromInactiveVault
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates an InKindRedeem instruction, boxes being the safety deposit box, store and destination of
+/// every box in the vault, in order.
+pub fn create_in_kind_redeem_instruction(
+    program_id: Pubkey,
+    outstanding_shares_account: Pubkey,
+    fraction_mint: Pubkey,
+    vault: Pubkey,
+    burn_authority: Pubkey,
+    boxes: &[(Pubkey, Pubkey, Pubkey)],
+    number_of_shares: u64,
+) -> Instruction {
+    let (transfer_authority, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), program_id.as_ref(), vault.as_ref()],
+        &program_id,
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new(outstanding_shares_account, false),
+        AccountMeta::new(fraction_mint, false),
+        AccountMeta::new_readonly(vault, false),
+        AccountMeta::new_readonly(burn_authority, true),
+        AccountMeta::new_readonly(transfer_authority, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(find_buyout_auction_address(&vault), false),
+    ];
+
+    for (safety_deposit_box, store, destination) in boxes {
+        accounts.push(AccountMeta::new_readonly(*safety_deposit_box, false));
+        accounts.push(AccountMeta::new(*store, false));
+        accounts.push(AccountMeta::new(*destination, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: VaultInstruction::InKindRedeem(NumberOfShareArgs { number_of_shares })
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates an MigrateVaultToV2 instruction, boxes being the safety deposit box, token mint and
+/// store of every box in the vault, in order.
+#[allow(clippy::too_many_arguments)]
+pub fn create_migrate_vault_to_v2_instruction(
+    program_id: Pubkey,
+    vault: Pubkey,
+    new_vault: Pubkey,
+    fraction_mint: Pubkey,
+    fraction_treasury: Pubkey,
+    redeem_treasury: Pubkey,
+    vault_authority: Pubkey,
+    payer: Pubkey,
+    boxes: &[(Pubkey, Pubkey, Pubkey)],
+) -> Instruction {
+    let (authority, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), program_id.as_ref(), vault.as_ref()],
+        &program_id,
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new(vault, false),
+        AccountMeta::new(new_vault, false),
+        AccountMeta::new(fraction_mint, false),
+        AccountMeta::new(fraction_treasury, false),
+        AccountMeta::new(redeem_treasury, false),
+        AccountMeta::new_readonly(vault_authority, true),
+        AccountMeta::new_readonly(authority, false),
+        AccountMeta::new(payer, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+
+    for (safety_deposit_box, token_mint, store) in boxes {
+        let (new_safety_deposit_box, _) = Pubkey::find_program_address(
+            &[PREFIX.as_bytes(), new_vault.as_ref(), token_mint.as_ref()],
+            &program_id,
+        );
+        accounts.push(AccountMeta::new(*safety_deposit_box, false));
+        accounts.push(AccountMeta::new(new_safety_deposit_box, false));
+        accounts.push(AccountMeta::new(*store, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: VaultInstruction::MigrateVaultToV2.try_to_vec().unwrap(),
+    }
+}
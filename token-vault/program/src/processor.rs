@@ -1,16 +1,22 @@
 use {
     crate::{
         error::VaultError,
+        event::VaultEvent,
         instruction::VaultInstruction,
         state::{
-            ExternalPriceAccount, Key, SafetyDepositBox, Vault, VaultState,
-            MAX_SAFETY_DEPOSIT_SIZE, PREFIX,
+            ExternalPriceAccount, Key, ReserveVotes, SafetyDepositBox, Vault, VaultState,
+            VoteRecord, MAX_RESERVE_VOTES_SIZE, MAX_SAFETY_DEPOSIT_SIZE,
+            MAX_SAFETY_DEPOSIT_V2_SIZE, MAX_VAULT_SIZE, MAX_VAULT_V2_SIZE, MAX_VOTE_RECORD_SIZE,
+            PREFIX, VOTE, VOTES,
         },
         utils::{
-            assert_initialized, assert_owned_by, assert_rent_exempt, assert_token_matching,
+            assert_buyout_authority, assert_derivation, assert_initialized, assert_no_buyout,
+            assert_owned_by, assert_rent_exempt, assert_token_matching,
             assert_token_program_matches_package, assert_vault_authority_correct,
-            create_or_allocate_account_raw, spl_token_burn, spl_token_mint_to, spl_token_transfer,
-            TokenBurnParams, TokenMintToParams, TokenTransferParams,
+            close_program_account, create_or_allocate_account_raw, has_reserve_votes,
+            reserve_price, spl_token_burn, spl_token_close_account, spl_token_mint_to,
+            spl_token_set_authority, spl_token_transfer, TokenBurnParams, TokenCloseAccountParams,
+            TokenMintToParams, TokenSetAuthorityParams, TokenTransferParams,
         },
     },
     borsh::{BorshDeserialize, BorshSerialize},
@@ -18,12 +24,16 @@ use {
         account_info::{next_account_info, AccountInfo},
         entrypoint::ProgramResult,
         msg,
+        program_error::ProgramError,
         program_option::COption,
         pubkey::Pubkey,
         rent::Rent,
         sysvar::Sysvar,
     },
-    spl_token::state::{Account, Mint},
+    spl_token::{
+        instruction::AuthorityType,
+        state::{Account, Mint},
+    },
 };
 
 pub fn process_instruction(
@@ -88,6 +98,30 @@ pub fn process_instruction(
             msg!("Instruction: Set Authority");
             process_set_authority(program_id, accounts)
         }
+        VaultInstruction::CombineVaultByBuyout(args) => {
+            msg!("Instruction: Combine Vault By Buyout");
+            process_combine_vault_by_buyout(program_id, accounts, args.amount)
+        }
+        VaultInstruction::LockSharesForVote(args) => {
+            msg!("Instruction: Lock Shares For Vote");
+            process_lock_shares_for_vote(program_id, accounts, args.amount, args.price_per_share)
+        }
+        VaultInstruction::UnlockVoteShares => {
+            msg!("Instruction: Unlock Vote Shares");
+            process_unlock_vote_shares(program_id, accounts)
+        }
+        VaultInstruction::RemoveTokenFromInactiveVault => {
+            msg!("Instruction: Remove Token From Inactive Vault");
+            process_remove_token_from_inactive_vault(program_id, accounts)
+        }
+        VaultInstruction::InKindRedeem(args) => {
+            msg!("Instruction: In Kind Redeem");
+            process_in_kind_redeem(program_id, accounts, args.number_of_shares)
+        }
+        VaultInstruction::MigrateVaultToV2 => {
+            msg!("Instruction: Migrate Vault To V2");
+            process_migrate_vault_to_v2(program_id, accounts)
+        }
     }
 }
 
@@ -140,7 +174,7 @@ pub fn process_set_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> P
     }
 
     vault.authority = *new_authority_info.key;
-    vault.serialize(&mut *vault_info.data.borrow_mut())?;
+    vault.save(vault_info)?;
 
     Ok(())
 }
@@ -352,6 +386,12 @@ pub fn process_mint_fractional_shares(
         token_program: token_program_info.clone(),
     })?;
 
+    VaultEvent::SharesMinted {
+        vault: *vault_info.key,
+        number_of_shares,
+    }
+    .emit()?;
+
     Ok(())
 }
 
@@ -459,13 +499,143 @@ pub fn process_withdraw_token_from_safety_deposit_box(
 
                 if fraction_mint.supply == 0 && vault.token_type_count == 0 {
                     vault.state = VaultState::Deactivated;
-                    vault.serialize(&mut *vault_info.data.borrow_mut())?;
+                    vault.save(vault_info)?;
                 }
             }
         }
         None => return Err(VaultError::NumericalOverflowError.into()),
     };
 
+    VaultEvent::TokenWithdrawn {
+        vault: *vault_info.key,
+        safety_deposit_box: *safety_deposit_info.key,
+        destination: *destination_info.key,
+        amount,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+pub fn process_remove_token_from_inactive_vault(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let safety_deposit_info = next_account_info(account_info_iter)?;
+    let store_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+    let transfer_authority_info = next_account_info(account_info_iter)?;
+    let rent_receiver_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let last_safety_deposit_info = next_account_info(account_info_iter).ok();
+
+    let mut vault = Vault::from_account_info(vault_info)?;
+    let safety_deposit = SafetyDepositBox::from_account_info(safety_deposit_info)?;
+    let destination: Account = assert_initialized(destination_info)?;
+    let store: Account = assert_initialized(store_info)?;
+
+    assert_token_program_matches_package(token_program_info)?;
+    assert_owned_by(destination_info, token_program_info.key)?;
+    assert_owned_by(safety_deposit_info, program_id)?;
+    assert_owned_by(store_info, token_program_info.key)?;
+    assert_owned_by(vault_info, program_id)?;
+
+    assert_token_matching(&vault, token_program_info)?;
+    assert_vault_authority_correct(&vault, vault_authority_info)?;
+
+    if vault.state != VaultState::Inactive {
+        return Err(VaultError::VaultShouldBeInactive.into());
+    }
+
+    if safety_deposit.vault != *vault_info.key {
+        return Err(VaultError::SafetyDepositBoxVaultMismatch.into());
+    }
+
+    if *store_info.key != safety_deposit.store {
+        return Err(VaultError::StoreDoesNotMatchSafetyDepositBox.into());
+    }
+
+    if destination.mint != safety_deposit.token_mint {
+        return Err(VaultError::DestinationAccountNeedsToMatchTokenMint.into());
+    }
+
+    let last_order = match vault.token_type_count.checked_sub(1) {
+        Some(val) => val,
+        None => return Err(VaultError::NumericalOverflowError.into()),
+    };
+
+    // The box with the last order moves into the gap, keeping orders 0..token_type_count.
+    if safety_deposit.order != last_order {
+        let last_safety_deposit_info = match last_safety_deposit_info {
+            Some(val) => val,
+            None => return Err(VaultError::LastSafetyDepositBoxInvalid.into()),
+        };
+        assert_owned_by(last_safety_deposit_info, program_id)?;
+
+        let mut last_safety_deposit =
+            SafetyDepositBox::from_account_info(last_safety_deposit_info)?;
+        if last_safety_deposit.vault != *vault_info.key || last_safety_deposit.order != last_order {
+            return Err(VaultError::LastSafetyDepositBoxInvalid.into());
+        }
+
+        last_safety_deposit.order = safety_deposit.order;
+        last_safety_deposit.save(last_safety_deposit_info)?;
+    }
+
+    vault.token_type_count = last_order;
+    vault.save(vault_info)?;
+
+    let (authority, bump_seed) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+        ],
+        program_id,
+    );
+    let authority_signer_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        vault_info.key.as_ref(),
+        &[bump_seed],
+    ];
+
+    if authority != *transfer_authority_info.key {
+        return Err(VaultError::InvalidAuthority.into());
+    }
+
+    if store.amount > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: store_info.clone(),
+            destination: destination_info.clone(),
+            amount: store.amount,
+            authority: transfer_authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_info.clone(),
+        })?;
+    }
+
+    // Close the box and its emptied store, so the same mint can be added again.
+    spl_token_close_account(TokenCloseAccountParams {
+        account: store_info.clone(),
+        destination: rent_receiver_info.clone(),
+        authority: transfer_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_info.clone(),
+    })?;
+    close_program_account(safety_deposit_info, rent_receiver_info)?;
+
+    VaultEvent::TokenRemoved {
+        vault: *vault_info.key,
+        safety_deposit_box: *safety_deposit_info.key,
+        destination: *destination_info.key,
+        amount: store.amount,
+    }
+    .emit()?;
+
     Ok(())
 }
 
@@ -576,8 +746,152 @@ pub fn process_redeem_shares(program_id: &Pubkey, accounts: &[AccountInfo]) -> P
 
     if fractional_remaining == 0 && vault.token_type_count == 0 {
         vault.state = VaultState::Deactivated;
-        vault.serialize(&mut *vault_info.data.borrow_mut())?;
+        vault.save(vault_info)?;
+    }
+
+    VaultEvent::SharesRedeemed {
+        vault: *vault_info.key,
+        number_of_shares: outstanding_shares.amount,
+        paid: we_owe_you,
     }
+    .emit()?;
+
+    Ok(())
+}
+
+pub fn process_in_kind_redeem(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    number_of_shares: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let outstanding_shares_info = next_account_info(account_info_iter)?;
+    let fraction_mint_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let burn_authority_info = next_account_info(account_info_iter)?;
+    let transfer_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let buyout_auction_info = next_account_info(account_info_iter)?;
+
+    let vault = Vault::from_account_info(vault_info)?;
+    let fraction_mint: Mint = assert_initialized(fraction_mint_info)?;
+    let outstanding_shares: Account = assert_initialized(outstanding_shares_info)?;
+
+    assert_token_program_matches_package(token_program_info)?;
+    assert_owned_by(vault_info, program_id)?;
+    assert_owned_by(outstanding_shares_info, token_program_info.key)?;
+    assert_owned_by(fraction_mint_info, token_program_info.key)?;
+    assert_token_matching(&vault, token_program_info)?;
+
+    if vault.state != VaultState::Active {
+        return Err(VaultError::VaultShouldBeActive.into());
+    }
+
+    // Whoever the authority was handed to, like an auction manager selling the boxes, or a buyout
+    // that is bidding for them, counts on the boxes staying whole.
+    if vault.authority != vault.original_authority {
+        return Err(VaultError::VaultAuthorityChanged.into());
+    }
+
+    assert_no_buyout(vault_info, buyout_auction_info)?;
+
+    if fraction_mint_info.key != &vault.fraction_mint {
+        return Err(VaultError::VaultMintNeedsToMatchVault.into());
+    }
+
+    if outstanding_shares.mint != *fraction_mint_info.key {
+        return Err(VaultError::OutstandingShareAccountNeedsToMatchFractionalMint.into());
+    }
+
+    if number_of_shares == 0 || outstanding_shares.amount < number_of_shares {
+        return Err(VaultError::NotEnoughShares.into());
+    }
+
+    if fraction_mint.supply == 0 {
+        return Err(VaultError::FractionSupplyEmpty.into());
+    }
+
+    let (authority, bump_seed) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+        ],
+        program_id,
+    );
+    let authority_signer_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        vault_info.key.as_ref(),
+        &[bump_seed],
+    ];
+
+    if authority != *transfer_authority_info.key {
+        return Err(VaultError::InvalidAuthority.into());
+    }
+
+    // Every box must be given, by order, so no part of the vault is skipped.
+    let mut amounts = Vec::with_capacity(vault.token_type_count as usize);
+    for order in 0..vault.token_type_count {
+        let safety_deposit_info = next_account_info(account_info_iter)?;
+        let store_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+
+        assert_owned_by(safety_deposit_info, program_id)?;
+        assert_owned_by(store_info, token_program_info.key)?;
+        assert_owned_by(destination_info, token_program_info.key)?;
+
+        let safety_deposit = SafetyDepositBox::from_account_info(safety_deposit_info)?;
+        let store: Account = assert_initialized(store_info)?;
+        let destination: Account = assert_initialized(destination_info)?;
+
+        if safety_deposit.vault != *vault_info.key || safety_deposit.order != order {
+            return Err(VaultError::SafetyDepositBoxesIncomplete.into());
+        }
+
+        if *store_info.key != safety_deposit.store {
+            return Err(VaultError::StoreDoesNotMatchSafetyDepositBox.into());
+        }
+
+        if destination.mint != safety_deposit.token_mint {
+            return Err(VaultError::DestinationAccountNeedsToMatchTokenMint.into());
+        }
+
+        let amount = (store.amount as u128)
+            .checked_mul(number_of_shares as u128)
+            .and_then(|val| val.checked_div(fraction_mint.supply as u128))
+            .ok_or(VaultError::NumericalOverflowError)? as u64;
+
+        if amount > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: store_info.clone(),
+                destination: destination_info.clone(),
+                amount,
+                authority: transfer_authority_info.clone(),
+                authority_signer_seeds,
+                token_program: token_program_info.clone(),
+            })?;
+        }
+
+        amounts.push(amount);
+    }
+
+    spl_token_burn(TokenBurnParams {
+        mint: fraction_mint_info.clone(),
+        amount: number_of_shares,
+        authority: burn_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_info.clone(),
+        source: outstanding_shares_info.clone(),
+    })?;
+
+    VaultEvent::SharesRedeemedInKind {
+        vault: *vault_info.key,
+        number_of_shares,
+        amounts,
+    }
+    .emit()?;
 
     Ok(())
 }
@@ -597,6 +911,7 @@ pub fn process_combine_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> P
     let fraction_burn_authority_info = next_account_info(account_info_iter)?;
     let external_pricing_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
+    let reserve_votes_info = next_account_info(account_info_iter).ok();
 
     let mut vault = Vault::from_account_info(vault_info)?;
     let fraction_mint: Mint = assert_initialized(fraction_mint_info)?;
@@ -646,18 +961,30 @@ pub fn process_combine_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> P
         return Err(VaultError::NotAllowedToCombine.into());
     }
 
-    let total_market_cap = match fraction_mint
-        .supply
-        .checked_mul(external_pricing.price_per_share)
-    {
+    // While shares circulate and their holders have voted, they set the price and the external
+    // pricing account only allows combining. Vaults nobody voted on keep its price.
+    let price_per_share = if fraction_mint.supply > fraction_treasury.amount {
+        let reserve_votes_info = reserve_votes_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if has_reserve_votes(program_id, vault_info, reserve_votes_info)? {
+            reserve_price(
+                program_id,
+                vault_info,
+                reserve_votes_info,
+                fraction_mint.supply - fraction_treasury.amount,
+            )?
+        } else {
+            external_pricing.price_per_share
+        }
+    } else {
+        external_pricing.price_per_share
+    };
+
+    let total_market_cap = match fraction_mint.supply.checked_mul(price_per_share) {
         Some(val) => val,
         None => return Err(VaultError::NumericalOverflowError.into()),
     };
 
-    let stored_market_cap = match fraction_treasury
-        .amount
-        .checked_mul(external_pricing.price_per_share)
-    {
+    let stored_market_cap = match fraction_treasury.amount.checked_mul(price_per_share) {
         Some(val) => val,
         None => return Err(VaultError::NumericalOverflowError.into()),
     };
@@ -667,10 +994,7 @@ pub fn process_combine_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> P
         None => return Err(VaultError::NumericalOverflowError.into()),
     };
 
-    let your_share_value = match your_outstanding_shares
-        .amount
-        .checked_mul(external_pricing.price_per_share)
-    {
+    let your_share_value = match your_outstanding_shares.amount.checked_mul(price_per_share) {
         Some(val) => val,
         None => return Err(VaultError::NumericalOverflowError.into()),
     };
@@ -732,8 +1056,380 @@ pub fn process_combine_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> P
 
     vault.state = VaultState::Combined;
     vault.authority = *new_vault_authority_info.key;
-    vault.locked_price_per_share = external_pricing.price_per_share;
-    vault.serialize(&mut *vault_info.data.borrow_mut())?;
+    vault.locked_price_per_share = price_per_share;
+    vault.save(vault_info)?;
+
+    VaultEvent::VaultCombined {
+        vault: *vault_info.key,
+        new_authority: vault.authority,
+        price_per_share: vault.locked_price_per_share,
+        paid: what_you_owe,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+pub fn process_combine_vault_by_buyout(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_info = next_account_info(account_info_iter)?;
+    let fraction_mint_info = next_account_info(account_info_iter)?;
+    let fraction_treasury_info = next_account_info(account_info_iter)?;
+    let redeem_treasury_info = next_account_info(account_info_iter)?;
+    let new_vault_authority_info = next_account_info(account_info_iter)?;
+    let buyout_authority_info = next_account_info(account_info_iter)?;
+    let fraction_burn_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    let mut vault = Vault::from_account_info(vault_info)?;
+    let fraction_mint: Mint = assert_initialized(fraction_mint_info)?;
+    let fraction_treasury: Account = assert_initialized(fraction_treasury_info)?;
+
+    assert_token_program_matches_package(token_program_info)?;
+    assert_token_matching(&vault, token_program_info)?;
+    assert_owned_by(vault_info, program_id)?;
+    assert_owned_by(fraction_mint_info, token_program_info.key)?;
+    assert_owned_by(fraction_treasury_info, token_program_info.key)?;
+    assert_buyout_authority(vault_info, buyout_authority_info)?;
+
+    if vault.state != VaultState::Active {
+        return Err(VaultError::VaultShouldBeActive.into());
+    }
+
+    if fraction_mint_info.key != &vault.fraction_mint {
+        return Err(VaultError::VaultMintNeedsToMatchVault.into());
+    }
+
+    if fraction_treasury_info.key != &vault.fraction_treasury {
+        return Err(VaultError::FractionTreasuryNeedsToMatchVault.into());
+    }
+
+    if redeem_treasury_info.key != &vault.redeem_treasury {
+        return Err(VaultError::RedeemTreasuryNeedsToMatchVault.into());
+    }
+
+    // The uncirculated shares are burned, so the circulating shares split the whole bid.
+    let circulating_shares = match fraction_mint.supply.checked_sub(fraction_treasury.amount) {
+        Some(val) => val,
+        None => return Err(VaultError::NumericalOverflowError.into()),
+    };
+
+    if circulating_shares == 0 {
+        return Err(VaultError::NoCirculatingShares.into());
+    }
+
+    let (authority, bump_seed) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+        ],
+        program_id,
+    );
+    let authority_signer_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        vault_info.key.as_ref(),
+        &[bump_seed],
+    ];
+
+    if authority != *fraction_burn_authority_info.key {
+        return Err(VaultError::InvalidAuthority.into());
+    }
+
+    spl_token_burn(TokenBurnParams {
+        mint: fraction_mint_info.clone(),
+        amount: fraction_treasury.amount,
+        authority: fraction_burn_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_info.clone(),
+        source: fraction_treasury_info.clone(),
+    })?;
+
+    vault.state = VaultState::Combined;
+    vault.authority = *new_vault_authority_info.key;
+    vault.locked_price_per_share = amount / circulating_shares;
+    vault.save(vault_info)?;
+
+    VaultEvent::VaultCombined {
+        vault: *vault_info.key,
+        new_authority: vault.authority,
+        price_per_share: vault.locked_price_per_share,
+        paid: amount,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+pub fn process_lock_shares_for_vote(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    price_per_share: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vote_record_info = next_account_info(account_info_iter)?;
+    let reserve_votes_info = next_account_info(account_info_iter)?;
+    let vote_escrow_info = next_account_info(account_info_iter)?;
+    let shares_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let voter_info = next_account_info(account_info_iter)?;
+    let transfer_authority_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    assert_token_program_matches_package(token_program_info)?;
+    assert_owned_by(vault_info, program_id)?;
+    assert_owned_by(vote_escrow_info, token_program_info.key)?;
+    assert_owned_by(shares_info, token_program_info.key)?;
+
+    let vault = Vault::from_account_info(vault_info)?;
+    let vote_escrow: Account = assert_initialized(vote_escrow_info)?;
+    let shares: Account = assert_initialized(shares_info)?;
+    assert_token_matching(&vault, token_program_info)?;
+
+    if !voter_info.is_signer {
+        return Err(VaultError::InvalidVoter.into());
+    }
+
+    if vault.state != VaultState::Active {
+        return Err(VaultError::VaultShouldBeActive.into());
+    }
+
+    if shares.mint != vault.fraction_mint {
+        return Err(VaultError::ShareMintShouldMatchFractionalMint.into());
+    }
+
+    if shares.amount < amount {
+        return Err(VaultError::TokenAccountAmountLessThanAmountSpecified.into());
+    }
+
+    if price_per_share == 0 {
+        return Err(VaultError::InvalidReservePrice.into());
+    }
+
+    let reserve_votes_bump = assert_derivation(
+        program_id,
+        reserve_votes_info,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+            VOTES.as_bytes(),
+        ],
+    )?;
+    let vote_record_bump = assert_derivation(
+        program_id,
+        vote_record_info,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+            voter_info.key.as_ref(),
+            VOTE.as_bytes(),
+        ],
+    )?;
+
+    let (authority, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+        ],
+        program_id,
+    );
+
+    if reserve_votes_info.data_is_empty() {
+        // The first vote sets up the escrow every locked share of the vault is held in.
+        if vote_escrow.mint != vault.fraction_mint
+            || vote_escrow.owner != authority
+            || vote_escrow.amount != 0
+            || vote_escrow.delegate != COption::None
+            || vote_escrow.close_authority != COption::None
+        {
+            return Err(VaultError::InvalidVoteEscrow.into());
+        }
+
+        create_or_allocate_account_raw(
+            *program_id,
+            reserve_votes_info,
+            rent_info,
+            system_account_info,
+            payer_info,
+            MAX_RESERVE_VOTES_SIZE,
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                vault_info.key.as_ref(),
+                VOTES.as_bytes(),
+                &[reserve_votes_bump],
+            ],
+        )?;
+    }
+
+    let mut reserve_votes = ReserveVotes::from_account_info(reserve_votes_info)?;
+    if reserve_votes.key == Key::Uninitialized {
+        reserve_votes.key = Key::ReserveVotesV1;
+        reserve_votes.vault = *vault_info.key;
+        reserve_votes.escrow = *vote_escrow_info.key;
+    } else if reserve_votes.escrow != *vote_escrow_info.key {
+        return Err(VaultError::InvalidVoteEscrow.into());
+    }
+
+    if vote_record_info.data_is_empty() {
+        create_or_allocate_account_raw(
+            *program_id,
+            vote_record_info,
+            rent_info,
+            system_account_info,
+            payer_info,
+            MAX_VOTE_RECORD_SIZE,
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                vault_info.key.as_ref(),
+                voter_info.key.as_ref(),
+                VOTE.as_bytes(),
+                &[vote_record_bump],
+            ],
+        )?;
+    }
+
+    let mut vote_record = VoteRecord::from_account_info(vote_record_info)?;
+    if vote_record.key == Key::Uninitialized {
+        vote_record.key = Key::VoteRecordV1;
+        vote_record.vault = *vault_info.key;
+        vote_record.voter = *voter_info.key;
+    }
+
+    // Every locked share moves to the new price.
+    reserve_votes.remove(
+        vote_record.price_per_share,
+        vote_record.shares,
+        vote_record.vote_id,
+    )?;
+    vote_record.shares = match vote_record.shares.checked_add(amount) {
+        Some(val) => val,
+        None => return Err(VaultError::NumericalOverflowError.into()),
+    };
+    vote_record.price_per_share = price_per_share;
+    vote_record.vote_id = reserve_votes.add(vote_record.price_per_share, vote_record.shares)?;
+
+    vote_record.serialize(&mut *vote_record_info.data.borrow_mut())?;
+    reserve_votes.serialize(&mut *reserve_votes_info.data.borrow_mut())?;
+
+    if amount > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: shares_info.clone(),
+            destination: vote_escrow_info.clone(),
+            amount,
+            authority: transfer_authority_info.clone(),
+            authority_signer_seeds: &[],
+            token_program: token_program_info.clone(),
+        })?;
+    }
+
+    VaultEvent::ReserveVoted {
+        vault: *vault_info.key,
+        voter: *voter_info.key,
+        shares: vote_record.shares,
+        price_per_share,
+        median: reserve_votes.median(),
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+pub fn process_unlock_vote_shares(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vote_record_info = next_account_info(account_info_iter)?;
+    let reserve_votes_info = next_account_info(account_info_iter)?;
+    let vote_escrow_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let voter_info = next_account_info(account_info_iter)?;
+    let transfer_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_token_program_matches_package(token_program_info)?;
+    assert_owned_by(vault_info, program_id)?;
+    assert_owned_by(vote_record_info, program_id)?;
+    assert_owned_by(reserve_votes_info, program_id)?;
+    assert_owned_by(destination_info, token_program_info.key)?;
+
+    let vault = Vault::from_account_info(vault_info)?;
+    let mut vote_record = VoteRecord::from_account_info(vote_record_info)?;
+    let mut reserve_votes = ReserveVotes::from_account_info(reserve_votes_info)?;
+    let destination: Account = assert_initialized(destination_info)?;
+    assert_token_matching(&vault, token_program_info)?;
+
+    if !voter_info.is_signer || vote_record.voter != *voter_info.key {
+        return Err(VaultError::InvalidVoter.into());
+    }
+
+    if vote_record.vault != *vault_info.key || reserve_votes.vault != *vault_info.key {
+        return Err(VaultError::DerivedKeyInvalid.into());
+    }
+
+    if reserve_votes.escrow != *vote_escrow_info.key {
+        return Err(VaultError::InvalidVoteEscrow.into());
+    }
+
+    if destination.mint != vault.fraction_mint {
+        return Err(VaultError::ShareMintShouldMatchFractionalMint.into());
+    }
+
+    let (authority, bump_seed) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+        ],
+        program_id,
+    );
+    let authority_signer_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        vault_info.key.as_ref(),
+        &[bump_seed],
+    ];
+
+    if authority != *transfer_authority_info.key {
+        return Err(VaultError::InvalidAuthority.into());
+    }
+
+    let shares = vote_record.shares;
+    reserve_votes.remove(vote_record.price_per_share, shares, vote_record.vote_id)?;
+    vote_record.shares = 0;
+
+    vote_record.serialize(&mut *vote_record_info.data.borrow_mut())?;
+    reserve_votes.serialize(&mut *reserve_votes_info.data.borrow_mut())?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: vote_escrow_info.clone(),
+        destination: destination_info.clone(),
+        amount: shares,
+        authority: transfer_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_info.clone(),
+    })?;
+
+    VaultEvent::SharesUnlocked {
+        vault: *vault_info.key,
+        voter: *voter_info.key,
+        shares,
+        median: reserve_votes.median(),
+    }
+    .emit()?;
 
     Ok(())
 }
@@ -792,7 +1488,13 @@ pub fn process_activate_vault(
     })?;
 
     vault.state = VaultState::Active;
-    vault.serialize(&mut *vault_info.data.borrow_mut())?;
+    vault.save(vault_info)?;
+
+    VaultEvent::VaultActivated {
+        vault: *vault_info.key,
+        number_of_shares,
+    }
+    .emit()?;
 
     Ok(())
 }
@@ -882,32 +1584,38 @@ pub fn process_add_token_to_inactivated_vault(
         token_account.mint.as_ref(),
         &[bump_seed],
     ];
+    // Boxes take the layout of their vault, only a VaultV2 holds orders past 255.
+    let (safety_deposit_key, safety_deposit_size) = if vault.key == Key::VaultV1 {
+        (Key::SafetyDepositBoxV1, MAX_SAFETY_DEPOSIT_SIZE)
+    } else {
+        (Key::SafetyDepositBoxV2, MAX_SAFETY_DEPOSIT_V2_SIZE)
+    };
     create_or_allocate_account_raw(
         *program_id,
         safety_deposit_account_info,
         rent_info,
         system_account_info,
         payer_info,
-        MAX_SAFETY_DEPOSIT_SIZE,
+        safety_deposit_size,
         authority_signer_seeds,
     )?;
 
     let mut safety_deposit_account =
         SafetyDepositBox::from_account_info(safety_deposit_account_info)?;
-    safety_deposit_account.key = Key::SafetyDepositBoxV1;
+    safety_deposit_account.key = safety_deposit_key;
     safety_deposit_account.vault = *vault_info.key;
     safety_deposit_account.token_mint = token_account.mint;
     safety_deposit_account.store = *store_info.key;
     safety_deposit_account.order = vault.token_type_count;
 
-    safety_deposit_account.serialize(&mut *safety_deposit_account_info.data.borrow_mut())?;
+    safety_deposit_account.save(safety_deposit_account_info)?;
 
     vault.token_type_count = match vault.token_type_count.checked_add(1) {
         Some(val) => val,
         None => return Err(VaultError::NumericalOverflowError.into()),
     };
 
-    vault.serialize(&mut *vault_info.data.borrow_mut())?;
+    vault.save(vault_info)?;
 
     spl_token_transfer(TokenTransferParams {
         source: token_account_info.clone(),
@@ -918,6 +1626,185 @@ pub fn process_add_token_to_inactivated_vault(
         token_program: token_program_info.clone(),
     })?;
 
+    VaultEvent::TokenAdded {
+        vault: *vault_info.key,
+        safety_deposit_box: *safety_deposit_account_info.key,
+        token_mint: token_account.mint,
+        amount,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+pub fn process_migrate_vault_to_v2(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_info = next_account_info(account_info_iter)?;
+    let new_vault_info = next_account_info(account_info_iter)?;
+    let fraction_mint_info = next_account_info(account_info_iter)?;
+    let fraction_treasury_info = next_account_info(account_info_iter)?;
+    let redeem_treasury_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+    assert_token_program_matches_package(token_program_info)?;
+    assert_owned_by(vault_info, program_id)?;
+    assert_owned_by(new_vault_info, program_id)?;
+    assert_rent_exempt(rent, new_vault_info)?;
+
+    let vault = Vault::from_account_info(vault_info)?;
+    let mut new_vault = Vault::from_account_info(new_vault_info)?;
+    assert_token_matching(&vault, token_program_info)?;
+    assert_vault_authority_correct(&vault, vault_authority_info)?;
+
+    // Only an inactive vault still needs room for more boxes, and nothing outside the vault
+    // refers to it yet.
+    if vault.key != Key::VaultV1 || vault.state != VaultState::Inactive {
+        return Err(VaultError::VaultCannotBeMigrated.into());
+    }
+
+    if new_vault.key != Key::Uninitialized || new_vault_info.data_len() != MAX_VAULT_V2_SIZE {
+        return Err(VaultError::AlreadyInitialized.into());
+    }
+
+    if fraction_mint_info.key != &vault.fraction_mint {
+        return Err(VaultError::VaultMintNeedsToMatchVault.into());
+    }
+
+    if fraction_treasury_info.key != &vault.fraction_treasury {
+        return Err(VaultError::FractionTreasuryNeedsToMatchVault.into());
+    }
+
+    if redeem_treasury_info.key != &vault.redeem_treasury {
+        return Err(VaultError::RedeemTreasuryNeedsToMatchVault.into());
+    }
+
+    let (authority, bump_seed) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+        ],
+        program_id,
+    );
+    let authority_signer_seeds = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        vault_info.key.as_ref(),
+        &[bump_seed],
+    ];
+
+    if authority != *authority_info.key {
+        return Err(VaultError::InvalidAuthority.into());
+    }
+
+    let (new_authority, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            new_vault_info.key.as_ref(),
+        ],
+        program_id,
+    );
+
+    for authority_type in [AuthorityType::MintTokens, AuthorityType::FreezeAccount].iter() {
+        spl_token_set_authority(TokenSetAuthorityParams {
+            account: fraction_mint_info.clone(),
+            new_authority: &new_authority,
+            authority_type: authority_type.clone(),
+            authority: authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_info.clone(),
+        })?;
+    }
+
+    for treasury_info in [fraction_treasury_info, redeem_treasury_info].iter() {
+        spl_token_set_authority(TokenSetAuthorityParams {
+            account: (*treasury_info).clone(),
+            new_authority: &new_authority,
+            authority_type: AuthorityType::AccountOwner,
+            authority: authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_info.clone(),
+        })?;
+    }
+
+    for order in 0..vault.token_type_count {
+        let safety_deposit_info = next_account_info(account_info_iter)?;
+        let new_safety_deposit_info = next_account_info(account_info_iter)?;
+        let store_info = next_account_info(account_info_iter)?;
+
+        assert_owned_by(safety_deposit_info, program_id)?;
+        let mut safety_deposit = SafetyDepositBox::from_account_info(safety_deposit_info)?;
+
+        if safety_deposit.vault != *vault_info.key || safety_deposit.order != order {
+            return Err(VaultError::SafetyDepositBoxesIncomplete.into());
+        }
+
+        if *store_info.key != safety_deposit.store {
+            return Err(VaultError::StoreDoesNotMatchSafetyDepositBox.into());
+        }
+
+        let bump_seed = assert_derivation(
+            program_id,
+            new_safety_deposit_info,
+            &[
+                PREFIX.as_bytes(),
+                new_vault_info.key.as_ref(),
+                safety_deposit.token_mint.as_ref(),
+            ],
+        )?;
+        create_or_allocate_account_raw(
+            *program_id,
+            new_safety_deposit_info,
+            rent_info,
+            system_account_info,
+            payer_info,
+            MAX_SAFETY_DEPOSIT_V2_SIZE,
+            &[
+                PREFIX.as_bytes(),
+                new_vault_info.key.as_ref(),
+                safety_deposit.token_mint.as_ref(),
+                &[bump_seed],
+            ],
+        )?;
+
+        spl_token_set_authority(TokenSetAuthorityParams {
+            account: store_info.clone(),
+            new_authority: &new_authority,
+            authority_type: AuthorityType::AccountOwner,
+            authority: authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_info.clone(),
+        })?;
+
+        safety_deposit.key = Key::SafetyDepositBoxV2;
+        safety_deposit.vault = *new_vault_info.key;
+        safety_deposit.save(new_safety_deposit_info)?;
+
+        close_program_account(safety_deposit_info, payer_info)?;
+    }
+
+    new_vault = Vault {
+        key: Key::VaultV2,
+        original_authority: vault.authority,
+        ..vault
+    };
+    new_vault.save(new_vault_info)?;
+
+    close_program_account(vault_info, payer_info)?;
+
+    VaultEvent::VaultMigrated {
+        vault: *vault_info.key,
+        new_vault: *new_vault_info.key,
+    }
+    .emit()?;
+
     Ok(())
 }
 
@@ -1034,7 +1921,12 @@ pub fn process_init_vault(
         return Err(VaultError::VaultTreasuryMintDoesNotMatchVaultMint.into());
     }
 
-    vault.key = Key::VaultV1;
+    // Vaults allocated at MAX_VAULT_V2_SIZE are VaultV2, older clients still get VaultV1.
+    vault.key = if vault_info.data_len() == MAX_VAULT_SIZE {
+        Key::VaultV1
+    } else {
+        Key::VaultV2
+    };
     vault.token_program = *token_program_info.key;
     vault.redeem_treasury = *redeem_treasury_info.key;
     vault.fraction_treasury = *fraction_treasury_info.key;
@@ -1042,10 +1934,18 @@ pub fn process_init_vault(
     vault.pricing_lookup_address = *pricing_lookup_address.key;
     vault.allow_further_share_creation = allow_further_share_creation;
     vault.authority = *authority_info.key;
+    vault.original_authority = vault.authority;
     vault.token_type_count = 0;
     vault.state = VaultState::Inactive;
 
-    vault.serialize(&mut *vault_info.data.borrow_mut())?;
+    vault.save(vault_info)?;
+
+    VaultEvent::VaultInitialized {
+        vault: *vault_info.key,
+        authority: vault.authority,
+        fraction_mint: vault.fraction_mint,
+    }
+    .emit()?;
 
     Ok(())
 }
@@ -1,11 +1,26 @@
 use {
-    crate::utils::try_from_slice_checked,
+    crate::{error::VaultError, utils::try_from_slice_checked},
     borsh::{BorshDeserialize, BorshSerialize},
-    solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey},
+    solana_program::{
+        account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+    std::convert::{TryFrom, TryInto},
 };
 /// prefix used for PDAs to avoid certain collision attacks (https://en.wikipedia.org/wiki/Collision_attack#Chosen-prefix_collision_attack)
 pub const PREFIX: &str = "vault";
 
+/// Auction program that runs buyout auctions, whose buyout authority can combine a vault.
+pub const BUYOUT_AUCTION_PROGRAM: &str = "auctxRXPeJoc4817jDhf4HbjnhEcr1cCXenosMhK5R8";
+/// Seed of the buyout authority of a vault, pda of the buyout auction program with seed
+/// ['auction', auction program id, vault key, 'buyout'].
+pub const BUYOUT: &str = "buyout";
+/// Seed of the reserve votes of a vault, pda with seed [PREFIX, program id, vault key, 'votes'].
+pub const VOTES: &str = "votes";
+/// Seed of the vote record of a share holder, pda with seed
+/// [PREFIX, program id, vault key, voter, 'vote'].
+pub const VOTE: &str = "vote";
+
 #[repr(C)]
 #[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
 pub enum Key {
@@ -13,11 +28,30 @@ pub enum Key {
     SafetyDepositBoxV1,
     ExternalAccountKeyV1,
     VaultV1,
+    VoteRecordV1,
+    ReserveVotesV1,
+    VaultV2,
+    SafetyDepositBoxV2,
 }
 
 pub const MAX_SAFETY_DEPOSIT_SIZE: usize = 1 + 32 + 32 + 32 + 1;
+pub const MAX_SAFETY_DEPOSIT_V2_SIZE: usize = 1 + 32 + 32 + 32 + 8;
 pub const MAX_VAULT_SIZE: usize = 1 + 32 + 32 + 32 + 32 + 1 + 32 + 1 + 32 + 1 + 1 + 8;
+pub const MAX_VAULT_V2_SIZE: usize = MAX_VAULT_SIZE + 7 + 32;
+/// Where token_type_count starts in both vault layouts, see Vault::get_token_type_count.
+pub const TOKEN_TYPE_COUNT_POSITION: usize = 194;
+/// Where order starts in both safety deposit box layouts, see SafetyDepositBox::get_order.
+pub const ORDER_POSITION: usize = 97;
 pub const MAX_EXTERNAL_ACCOUNT_SIZE: usize = 1 + 8 + 32 + 1;
+pub const MAX_VOTE_RECORD_SIZE: usize = 1 + 32 + 32 + 8 + 8 + 8;
+/// Most distinct reserve prices a vault can hold votes for at once. Past that, the price with the
+/// fewest shares makes way for one with more.
+pub const MAX_RESERVE_PRICES: usize = 64;
+pub const MAX_RESERVE_VOTES_SIZE: usize =
+    1 + 32 + 32 + 8 + 8 + 4 + MAX_RESERVE_PRICES * (8 + 8 + 8);
+/// Share of the circulating shares, in basis points, that has to be locked in reserve votes before
+/// their median sets the reserve price.
+pub const RESERVE_VOTE_QUORUM_BASIS_POINTS: u64 = 2_000;
 #[repr(C)]
 #[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq)]
 pub enum VaultState {
@@ -52,23 +86,104 @@ pub struct Vault {
     /// The authority of the vault withdrawals a Safety Deposit contents to count down how many
     /// are left to be opened and closed down. Once this hits zero, and the fraction mint has zero shares,
     /// then we can deactivate the vault.
-    pub token_type_count: u8,
+    /// Stored as a u8 by VaultV1, which therefore holds at most 255 safety deposit boxes.
+    pub token_type_count: u64,
     pub state: VaultState,
 
     /// Once combination happens, we copy price per share to vault so that if something nefarious happens
     /// to external price account, like price change, we still have the math 'saved' for use in our calcs
     pub locked_price_per_share: u64,
+    /// Authority the vault was initialized with. Only VaultV2 records it, VaultV1 reads as the
+    /// default pubkey.
+    pub original_authority: Pubkey,
+}
+
+/// Vault as VaultV1 lays it out, with a u8 token_type_count.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+struct VaultV1 {
+    key: Key,
+    token_program: Pubkey,
+    fraction_mint: Pubkey,
+    authority: Pubkey,
+    fraction_treasury: Pubkey,
+    redeem_treasury: Pubkey,
+    allow_further_share_creation: bool,
+    pricing_lookup_address: Pubkey,
+    token_type_count: u8,
+    state: VaultState,
+    locked_price_per_share: u64,
 }
 
 impl Vault {
+    /// Reads either layout, VaultV2 being the Borsh layout of Vault itself. Uninitialized accounts
+    /// take the layout their size was allocated for.
     pub fn from_account_info(a: &AccountInfo) -> Result<Vault, ProgramError> {
-        let vt: Vault = try_from_slice_checked(&a.data.borrow_mut(), Key::VaultV1, MAX_VAULT_SIZE)?;
+        let data = a.data.borrow_mut();
+        if data[0] == Key::VaultV1 as u8
+            || (data[0] == Key::Uninitialized as u8 && data.len() == MAX_VAULT_SIZE)
+        {
+            let vt: VaultV1 = try_from_slice_checked(&data, Key::VaultV1, MAX_VAULT_SIZE)?;
+
+            return Ok(Vault {
+                key: vt.key,
+                token_program: vt.token_program,
+                fraction_mint: vt.fraction_mint,
+                authority: vt.authority,
+                fraction_treasury: vt.fraction_treasury,
+                redeem_treasury: vt.redeem_treasury,
+                allow_further_share_creation: vt.allow_further_share_creation,
+                pricing_lookup_address: vt.pricing_lookup_address,
+                token_type_count: vt.token_type_count as u64,
+                state: vt.state,
+                locked_price_per_share: vt.locked_price_per_share,
+                original_authority: Pubkey::default(),
+            });
+        }
+
+        let vt: Vault = try_from_slice_checked(&data, Key::VaultV2, MAX_VAULT_V2_SIZE)?;
 
         Ok(vt)
     }
 
-    pub fn get_token_type_count(a: &AccountInfo) -> u8 {
-        return a.data.borrow()[194];
+    /// Writes the vault back in the layout of its key.
+    pub fn save(&self, a: &AccountInfo) -> ProgramResult {
+        if self.key != Key::VaultV1 {
+            self.serialize(&mut *a.data.borrow_mut())?;
+            return Ok(());
+        }
+
+        let token_type_count = match u8::try_from(self.token_type_count) {
+            Ok(val) => val,
+            Err(_) => return Err(VaultError::VaultNeedsMigration.into()),
+        };
+
+        VaultV1 {
+            key: self.key.clone(),
+            token_program: self.token_program,
+            fraction_mint: self.fraction_mint,
+            authority: self.authority,
+            fraction_treasury: self.fraction_treasury,
+            redeem_treasury: self.redeem_treasury,
+            allow_further_share_creation: self.allow_further_share_creation,
+            pricing_lookup_address: self.pricing_lookup_address,
+            token_type_count,
+            state: self.state.clone(),
+            locked_price_per_share: self.locked_price_per_share,
+        }
+        .serialize(&mut *a.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub fn get_token_type_count(a: &AccountInfo) -> u64 {
+        let data = a.data.borrow();
+        if data[0] == Key::VaultV2 as u8 {
+            let bytes = &data[TOKEN_TYPE_COUNT_POSITION..TOKEN_TYPE_COUNT_POSITION + 8];
+            return u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        data[TOKEN_TYPE_COUNT_POSITION] as u64
     }
 }
 
@@ -86,22 +201,79 @@ pub struct SafetyDepositBox {
     /// Account that stores the tokens under management
     pub store: Pubkey,
     /// the order in the array of registries
-    pub order: u8,
+    /// Stored as a u8 by SafetyDepositBoxV1.
+    pub order: u64,
+}
+
+/// SafetyDepositBox as SafetyDepositBoxV1 lays it out, with a u8 order.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+struct SafetyDepositBoxV1 {
+    key: Key,
+    vault: Pubkey,
+    token_mint: Pubkey,
+    store: Pubkey,
+    order: u8,
 }
 
 impl SafetyDepositBox {
+    /// Reads either layout, SafetyDepositBoxV2 being the Borsh layout of SafetyDepositBox itself.
+    /// Uninitialized accounts take the layout their size was allocated for.
     pub fn from_account_info(a: &AccountInfo) -> Result<SafetyDepositBox, ProgramError> {
-        let sd: SafetyDepositBox = try_from_slice_checked(
-            &a.data.borrow_mut(),
-            Key::SafetyDepositBoxV1,
-            MAX_SAFETY_DEPOSIT_SIZE,
-        )?;
+        let data = a.data.borrow_mut();
+        if data[0] == Key::SafetyDepositBoxV1 as u8
+            || (data[0] == Key::Uninitialized as u8 && data.len() == MAX_SAFETY_DEPOSIT_SIZE)
+        {
+            let sd: SafetyDepositBoxV1 =
+                try_from_slice_checked(&data, Key::SafetyDepositBoxV1, MAX_SAFETY_DEPOSIT_SIZE)?;
+
+            return Ok(SafetyDepositBox {
+                key: sd.key,
+                vault: sd.vault,
+                token_mint: sd.token_mint,
+                store: sd.store,
+                order: sd.order as u64,
+            });
+        }
+
+        let sd: SafetyDepositBox =
+            try_from_slice_checked(&data, Key::SafetyDepositBoxV2, MAX_SAFETY_DEPOSIT_V2_SIZE)?;
 
         Ok(sd)
     }
 
-    pub fn get_order(a: &AccountInfo) -> u8 {
-        a.data.borrow()[97]
+    /// Writes the box back in the layout of its key.
+    pub fn save(&self, a: &AccountInfo) -> ProgramResult {
+        if self.key != Key::SafetyDepositBoxV1 {
+            self.serialize(&mut *a.data.borrow_mut())?;
+            return Ok(());
+        }
+
+        let order = match u8::try_from(self.order) {
+            Ok(val) => val,
+            Err(_) => return Err(VaultError::VaultNeedsMigration.into()),
+        };
+
+        SafetyDepositBoxV1 {
+            key: self.key.clone(),
+            vault: self.vault,
+            token_mint: self.token_mint,
+            store: self.store,
+            order,
+        }
+        .serialize(&mut *a.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub fn get_order(a: &AccountInfo) -> u64 {
+        let data = a.data.borrow();
+        if data[0] == Key::SafetyDepositBoxV2 as u8 {
+            let bytes = &data[ORDER_POSITION..ORDER_POSITION + 8];
+            return u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        data[ORDER_POSITION] as u64
     }
 }
 
@@ -128,3 +300,208 @@ impl ExternalPriceAccount {
         Ok(sd)
     }
 }
+
+/// Shares a holder has locked to vote on the reserve price of a vault. Unlocking the shares
+/// removes the vote.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+pub struct VoteRecord {
+    pub key: Key,
+    /// Vault the vote is for
+    pub vault: Pubkey,
+    /// Holder who locked the shares, and the only one who can unlock them
+    pub voter: Pubkey,
+    /// Shares locked in the vote escrow of the vault
+    pub shares: u64,
+    /// Price per share the holder voted for
+    pub price_per_share: u64,
+    /// Id of the reserve vote counting the shares. When that price was evicted from the reserve
+    /// votes, the shares no longer count until the holder votes again.
+    pub vote_id: u64,
+}
+
+impl VoteRecord {
+    pub fn from_account_info(a: &AccountInfo) -> Result<VoteRecord, ProgramError> {
+        let vr: VoteRecord = try_from_slice_checked(
+            &a.data.borrow_mut(),
+            Key::VoteRecordV1,
+            MAX_VOTE_RECORD_SIZE,
+        )?;
+
+        Ok(vr)
+    }
+}
+
+/// Locked shares voting for one reserve price.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct ReserveVote {
+    pub price_per_share: u64,
+    pub shares: u64,
+    /// Tells the vote records counted here apart from ones counted before the price was evicted
+    pub id: u64,
+}
+
+/// Tally of every vote on the reserve price of a vault, which is the share-weighted median of the
+/// votes.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+pub struct ReserveVotes {
+    pub key: Key,
+    /// Vault the votes are for
+    pub vault: Pubkey,
+    /// Token account of the fraction mint holding every locked share, owned by the vault pda
+    pub escrow: Pubkey,
+    /// Shares locked over all counted votes
+    pub total_shares: u64,
+    /// Id of the last price added to the votes
+    pub last_vote_id: u64,
+    /// Shares voting for each price, sorted by price
+    pub votes: Vec<ReserveVote>,
+}
+
+impl ReserveVotes {
+    pub fn from_account_info(a: &AccountInfo) -> Result<ReserveVotes, ProgramError> {
+        let rv: ReserveVotes = try_from_slice_checked(
+            &a.data.borrow_mut(),
+            Key::ReserveVotesV1,
+            MAX_RESERVE_VOTES_SIZE,
+        )?;
+
+        Ok(rv)
+    }
+
+    /// Adds shares voting for a price, returning the id of the vote counting them. Once every
+    /// price is taken, a new one evicts the price with the fewest shares if it has more, so small
+    /// votes can't crowd out larger ones.
+    pub fn add(&mut self, price_per_share: u64, shares: u64) -> Result<u64, ProgramError> {
+        if shares == 0 {
+            return Ok(0);
+        }
+
+        let id = match self
+            .votes
+            .binary_search_by_key(&price_per_share, |vote| vote.price_per_share)
+        {
+            Ok(index) => {
+                self.votes[index].shares = self.votes[index]
+                    .shares
+                    .checked_add(shares)
+                    .ok_or(VaultError::NumericalOverflowError)?;
+                self.votes[index].id
+            }
+            Err(_) => {
+                if self.votes.len() >= MAX_RESERVE_PRICES {
+                    self.evict_smallest(shares)?;
+                }
+                self.last_vote_id = self
+                    .last_vote_id
+                    .checked_add(1)
+                    .ok_or(VaultError::NumericalOverflowError)?;
+                let index = match self
+                    .votes
+                    .binary_search_by_key(&price_per_share, |vote| vote.price_per_share)
+                {
+                    Ok(index) | Err(index) => index,
+                };
+                self.votes.insert(
+                    index,
+                    ReserveVote {
+                        price_per_share,
+                        shares,
+                        id: self.last_vote_id,
+                    },
+                );
+                self.last_vote_id
+            }
+        };
+
+        self.total_shares = self
+            .total_shares
+            .checked_add(shares)
+            .ok_or(VaultError::NumericalOverflowError)?;
+
+        Ok(id)
+    }
+
+    /// Drops the price with the fewest shares, which must be fewer than `shares`, to make room.
+    fn evict_smallest(&mut self, shares: u64) -> Result<(), ProgramError> {
+        let (index, smallest) = match self
+            .votes
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, vote)| vote.shares)
+        {
+            Some((index, vote)) => (index, vote.shares),
+            None => return Err(VaultError::TooManyReservePrices.into()),
+        };
+        if smallest >= shares {
+            return Err(VaultError::TooManyReservePrices.into());
+        }
+
+        self.votes.remove(index);
+        self.total_shares = self
+            .total_shares
+            .checked_sub(smallest)
+            .ok_or(VaultError::NumericalOverflowError)?;
+
+        Ok(())
+    }
+
+    /// Takes shares counted by the vote `id` back out, dropping the price once nobody votes for
+    /// it. Shares whose price was evicted are no longer counted, so there is nothing to take out.
+    pub fn remove(
+        &mut self,
+        price_per_share: u64,
+        shares: u64,
+        id: u64,
+    ) -> Result<(), ProgramError> {
+        if shares == 0 {
+            return Ok(());
+        }
+
+        let index = match self
+            .votes
+            .binary_search_by_key(&price_per_share, |vote| vote.price_per_share)
+        {
+            Ok(index) if self.votes[index].id == id => index,
+            _ => return Ok(()),
+        };
+
+        self.total_shares = self
+            .total_shares
+            .checked_sub(shares)
+            .ok_or(VaultError::NumericalOverflowError)?;
+        self.votes[index].shares = self.votes[index]
+            .shares
+            .checked_sub(shares)
+            .ok_or(VaultError::NumericalOverflowError)?;
+        if self.votes[index].shares == 0 {
+            self.votes.remove(index);
+        }
+
+        Ok(())
+    }
+
+    /// Whether enough of the `circulating_shares` are locked in votes for their median to count,
+    /// see RESERVE_VOTE_QUORUM_BASIS_POINTS.
+    pub fn has_quorum(&self, circulating_shares: u64) -> bool {
+        (self.total_shares as u128) * 10_000
+            >= (circulating_shares as u128) * (RESERVE_VOTE_QUORUM_BASIS_POINTS as u128)
+    }
+
+    /// Share-weighted median of the votes, the lowest price at least half of the locked shares
+    /// voted for or under. None when nobody has voted.
+    pub fn median(&self) -> Option<u64> {
+        let half = self.total_shares / 2 + self.total_shares % 2;
+        let mut counted: u64 = 0;
+        for vote in &self.votes {
+            counted = counted.saturating_add(vote.shares);
+            if counted >= half {
+                return Some(vote.price_per_share);
+            }
+        }
+
+        None
+    }
+}
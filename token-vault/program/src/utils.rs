@@ -1,7 +1,7 @@
 use {
     crate::{
         error::VaultError,
-        state::{Key, Vault},
+        state::{Key, ReserveVotes, Vault, BUYOUT, BUYOUT_AUCTION_PROGRAM, PREFIX, VOTES},
     },
     borsh::BorshDeserialize,
     solana_program::{
@@ -14,9 +14,10 @@ use {
         program_pack::{IsInitialized, Pack},
         pubkey::Pubkey,
         system_instruction,
-        sysvar::{rent::Rent, Sysvar},
+        sysvar::{clock::Clock, rent::Rent, Sysvar},
     },
-    std::convert::TryInto,
+    spl_token::instruction::AuthorityType,
+    std::{convert::TryInto, str::FromStr},
 };
 
 /// assert initialized account
@@ -68,6 +69,216 @@ pub fn assert_vault_authority_correct(
     Ok(())
 }
 
+/// Whether any shares are locked voting on the reserve price of the vault.
+pub fn has_reserve_votes(
+    program_id: &Pubkey,
+    vault_info: &AccountInfo,
+    reserve_votes_info: &AccountInfo,
+) -> Result<bool, ProgramError> {
+    assert_derivation(
+        program_id,
+        reserve_votes_info,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+            VOTES.as_bytes(),
+        ],
+    )?;
+    if reserve_votes_info.data_is_empty() {
+        return Ok(false);
+    }
+    assert_owned_by(reserve_votes_info, program_id)?;
+
+    Ok(ReserveVotes::from_account_info(reserve_votes_info)?.total_shares > 0)
+}
+
+/// Reserve price per share of the vault, the share-weighted median of its reserve votes. The votes
+/// need a quorum of the circulating shares, see RESERVE_VOTE_QUORUM_BASIS_POINTS.
+pub fn reserve_price(
+    program_id: &Pubkey,
+    vault_info: &AccountInfo,
+    reserve_votes_info: &AccountInfo,
+    circulating_shares: u64,
+) -> Result<u64, ProgramError> {
+    assert_derivation(
+        program_id,
+        reserve_votes_info,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            vault_info.key.as_ref(),
+            VOTES.as_bytes(),
+        ],
+    )?;
+    if reserve_votes_info.data_is_empty() {
+        return Err(VaultError::NoReserveVotes.into());
+    }
+    assert_owned_by(reserve_votes_info, program_id)?;
+
+    let reserve_votes = ReserveVotes::from_account_info(reserve_votes_info)?;
+    if !reserve_votes.has_quorum(circulating_shares) {
+        return Err(VaultError::ReserveQuorumNotReached.into());
+    }
+    reserve_votes
+        .median()
+        .ok_or_else(|| VaultError::NoReserveVotes.into())
+}
+
+/// Asserts the account is the signing buyout authority of the vault, see BUYOUT.
+pub fn assert_buyout_authority(
+    vault_info: &AccountInfo,
+    buyout_authority_info: &AccountInfo,
+) -> ProgramResult {
+    let auction_program = Pubkey::from_str(BUYOUT_AUCTION_PROGRAM).unwrap();
+    let (buyout_authority, _) = Pubkey::find_program_address(
+        &[
+            "auction".as_bytes(),
+            auction_program.as_ref(),
+            vault_info.key.as_ref(),
+            BUYOUT.as_bytes(),
+        ],
+        &auction_program,
+    );
+
+    if buyout_authority != *buyout_authority_info.key {
+        return Err(VaultError::InvalidBuyoutAuthority.into());
+    }
+
+    if !buyout_authority_info.is_signer {
+        return Err(VaultError::AuthorityIsNotSigner.into());
+    }
+
+    Ok(())
+}
+
+/// Buyout auction of the vault, pda of the buyout auction program with seed
+/// ['auction', auction program id, buyout authority].
+pub fn find_buyout_auction_address(vault: &Pubkey) -> Pubkey {
+    let auction_program = Pubkey::from_str(BUYOUT_AUCTION_PROGRAM).unwrap();
+    let (buyout_authority, _) = Pubkey::find_program_address(
+        &[
+            "auction".as_bytes(),
+            auction_program.as_ref(),
+            vault.as_ref(),
+            BUYOUT.as_bytes(),
+        ],
+        &auction_program,
+    );
+
+    Pubkey::find_program_address(
+        &[
+            "auction".as_bytes(),
+            auction_program.as_ref(),
+            buyout_authority.as_ref(),
+        ],
+        &auction_program,
+    )
+    .0
+}
+
+/// The parts of a buyout auction assert_no_buyout looks at.
+struct BuyoutAuction {
+    state: u8,
+    last_bid: Option<i64>,
+    ended_at: Option<i64>,
+    end_auction_gap: Option<i64>,
+    num_bids: u64,
+}
+
+impl BuyoutAuction {
+    /// Reads the auction in either layout, None if the data is cut short. AuctionDataV2 keeps its
+    /// layout key, state and which timestamps are set at 64, 65 and 68, the timestamps from 72 and
+    /// the number of bids at 144. The Borsh layout has the four optional timestamps from 64, then
+    /// the price floor, the state and the tag and length of the bids.
+    fn from_data(data: &[u8]) -> Option<BuyoutAuction> {
+        let read_i64 = |offset: usize| -> Option<i64> {
+            Some(i64::from_le_bytes(
+                data.get(offset..offset + 8)?.try_into().ok()?,
+            ))
+        };
+
+        if data.len() > 64 && data[64] == 2 {
+            let timestamps_set = *data.get(68)?;
+            let timestamp = |bit: usize| -> Option<Option<i64>> {
+                let value = read_i64(72 + bit * 8)?;
+                Some(if timestamps_set & (1 << bit) != 0 {
+                    Some(value)
+                } else {
+                    None
+                })
+            };
+            return Some(BuyoutAuction {
+                state: data[65],
+                last_bid: timestamp(0)?,
+                ended_at: timestamp(1)?,
+                end_auction_gap: timestamp(3)?,
+                num_bids: u64::from_le_bytes(data.get(144..152)?.try_into().ok()?),
+            });
+        }
+
+        let mut offset = 64;
+        let mut timestamps = [None; 4];
+        for timestamp in timestamps.iter_mut() {
+            if *data.get(offset)? == 1 {
+                *timestamp = Some(read_i64(offset + 1)?);
+                offset += 9;
+            } else {
+                offset += 1;
+            }
+        }
+        // Past the price floor, the state and the bid state tag sits the length of the bids.
+        let num_bids = u32::from_le_bytes(data.get(offset + 35..offset + 39)?.try_into().ok()?);
+        Some(BuyoutAuction {
+            state: *data.get(offset + 33)?,
+            last_bid: timestamps[0],
+            ended_at: timestamps[1],
+            end_auction_gap: timestamps[3],
+            num_bids: num_bids as u64,
+        })
+    }
+
+    /// Whether nothing can be bought out of the auction: it was cancelled, or it is over and
+    /// nobody bid, the same end as AuctionData::ended in the auction program.
+    fn is_closed(&self, now: i64) -> bool {
+        // Cancelled and Ended are the last two AuctionStates.
+        if self.state == 3 {
+            return true;
+        }
+
+        let ended = self.state == 2
+            || match (self.ended_at, self.end_auction_gap, self.last_bid) {
+                (Some(end), Some(gap), Some(last)) => now > end && now > last.saturating_add(gap),
+                (Some(end), _, _) => now > end,
+                _ => false,
+            };
+        ended && self.num_bids == 0
+    }
+}
+
+/// Asserts the account is the buyout auction of the vault and that no buyout is running or won.
+/// A buyout that was cancelled, or that closed without a single bid, never took the boxes. The
+/// auction program depends on this one, so its account is read in place, see BuyoutAuction.
+pub fn assert_no_buyout(
+    vault_info: &AccountInfo,
+    buyout_auction_info: &AccountInfo,
+) -> ProgramResult {
+    if *buyout_auction_info.key != find_buyout_auction_address(vault_info.key) {
+        return Err(VaultError::InvalidBuyoutAuthority.into());
+    }
+
+    let auction_program = Pubkey::from_str(BUYOUT_AUCTION_PROGRAM).unwrap();
+    if buyout_auction_info.data_is_empty() || *buyout_auction_info.owner != auction_program {
+        return Ok(());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    match BuyoutAuction::from_data(&buyout_auction_info.data.borrow()) {
+        Some(auction) if auction.is_closed(now) => Ok(()),
+        _ => Err(VaultError::BuyoutInProgress.into()),
+    }
+}
+
 pub fn assert_token_program_matches_package(token_program_info: &AccountInfo) -> ProgramResult {
     if *token_program_info.key != spl_token::id() {
         return Err(VaultError::InvalidTokenProgram.into());
@@ -201,6 +412,72 @@ pub fn spl_token_burn(params: TokenBurnParams<'_, '_>) -> ProgramResult {
     result.map_err(|_| VaultError::TokenBurnFailed.into())
 }
 
+/// Issue a spl_token `SetAuthority` instruction.
+pub fn spl_token_set_authority(params: TokenSetAuthorityParams<'_, '_>) -> ProgramResult {
+    let TokenSetAuthorityParams {
+        account,
+        new_authority,
+        authority_type,
+        authority,
+        token_program,
+        authority_signer_seeds,
+    } = params;
+    let result = invoke_signed(
+        &spl_token::instruction::set_authority(
+            token_program.key,
+            account.key,
+            Some(new_authority),
+            authority_type,
+            authority.key,
+            &[],
+        )?,
+        &[account, authority, token_program],
+        &[authority_signer_seeds],
+    );
+    result.map_err(|_| VaultError::TokenSetAuthorityFailed.into())
+}
+
+/// Issue a spl_token `CloseAccount` instruction.
+pub fn spl_token_close_account(params: TokenCloseAccountParams<'_, '_>) -> ProgramResult {
+    let TokenCloseAccountParams {
+        account,
+        destination,
+        authority,
+        token_program,
+        authority_signer_seeds,
+    } = params;
+    let result = invoke_signed(
+        &spl_token::instruction::close_account(
+            token_program.key,
+            account.key,
+            destination.key,
+            authority.key,
+            &[],
+        )?,
+        &[account, destination, authority, token_program],
+        &[authority_signer_seeds],
+    );
+    result.map_err(|_| VaultError::TokenCloseFailed.into())
+}
+
+/// Closes an account owned by this program, handing its lamports to receiver.
+pub fn close_program_account(
+    account_info: &AccountInfo,
+    receiver_info: &AccountInfo,
+) -> ProgramResult {
+    let lamports = account_info.lamports();
+    **receiver_info.lamports.borrow_mut() = match receiver_info.lamports().checked_add(lamports) {
+        Some(val) => val,
+        None => return Err(VaultError::NumericalOverflowError.into()),
+    };
+    **account_info.lamports.borrow_mut() = 0;
+    for byte in account_info.data.borrow_mut().iter_mut() {
+        *byte = 0;
+    }
+
+    Ok(())
+}
+
 ///TokenTransferParams
 pub struct TokenTransferParams<'a: 'b, 'b> {
     /// source
@@ -247,6 +524,36 @@ pub struct TokenBurnParams<'a: 'b, 'b> {
     pub token_program: AccountInfo<'a>,
 }
 
+/// TokenSetAuthorityParams
+pub struct TokenSetAuthorityParams<'a: 'b, 'b> {
+    /// account, a mint or token account
+    pub account: AccountInfo<'a>,
+    /// new_authority
+    pub new_authority: &'b Pubkey,
+    /// authority_type
+    pub authority_type: AuthorityType,
+    /// authority
+    pub authority: AccountInfo<'a>,
+    /// authority_signer_seeds
+    pub authority_signer_seeds: &'b [&'b [u8]],
+    /// token_program
+    pub token_program: AccountInfo<'a>,
+}
+
+/// TokenCloseAccountParams
+pub struct TokenCloseAccountParams<'a: 'b, 'b> {
+    /// account, an empty token account
+    pub account: AccountInfo<'a>,
+    /// destination of the rent
+    pub destination: AccountInfo<'a>,
+    /// authority
+    pub authority: AccountInfo<'a>,
+    /// authority_signer_seeds
+    pub authority_signer_seeds: &'b [&'b [u8]],
+    /// token_program
+    pub token_program: AccountInfo<'a>,
+}
+
 pub fn try_from_slice_checked<T: BorshDeserialize>(
     data: &[u8],
     data_type: Key,
@@ -0,0 +1,281 @@
+#![cfg(feature = "test-bpf")]
+mod utils;
+
+use borsh::BorshSerialize;
+use metaplex_token_vault::{
+    error::VaultError,
+    id, instruction,
+    state::{
+        Key, SafetyDepositBox, Vault, VaultState, BUYOUT_AUCTION_PROGRAM,
+        MAX_SAFETY_DEPOSIT_V2_SIZE, MAX_VAULT_V2_SIZE, PREFIX,
+    },
+    utils::find_buyout_auction_address,
+};
+use solana_program::{program_option::COption, program_pack::Pack};
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    transport::TransportError,
+};
+use std::str::FromStr;
+use utils::*;
+
+const SUPPLY: u64 = 10;
+const STORED: u64 = 100;
+
+struct VaultAccounts {
+    vault: Pubkey,
+    fraction_mint: Pubkey,
+    shares: Pubkey,
+    safety_deposit_box: Pubkey,
+    store: Pubkey,
+    destination: Pubkey,
+}
+
+/// Sets up an active vault holding a single box of STORED tokens, with SUPPLY shares of which the
+/// holder has half.
+fn add_vault_accounts(
+    program_test: &mut ProgramTest,
+    holder: Pubkey,
+    authority: Pubkey,
+    original_authority: Pubkey,
+) -> VaultAccounts {
+    let vault = Pubkey::new_unique();
+    let (vault_pda, _) =
+        Pubkey::find_program_address(&[PREFIX.as_bytes(), id().as_ref(), vault.as_ref()], &id());
+
+    let fraction_mint = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(vault_pda),
+        supply: SUPPLY,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    program_test.add_account(fraction_mint, account(spl_token::id(), data));
+
+    let shares = Pubkey::new_unique();
+    program_test.add_account(shares, token_account(fraction_mint, holder, SUPPLY / 2));
+
+    let mut data = vec![0; MAX_VAULT_V2_SIZE];
+    Vault {
+        key: Key::VaultV2,
+        token_program: spl_token::id(),
+        fraction_mint,
+        authority,
+        fraction_treasury: Pubkey::new_unique(),
+        redeem_treasury: Pubkey::new_unique(),
+        allow_further_share_creation: false,
+        pricing_lookup_address: Pubkey::new_unique(),
+        token_type_count: 1,
+        state: VaultState::Active,
+        locked_price_per_share: 0,
+        original_authority,
+    }
+    .serialize(&mut data.as_mut_slice())
+    .unwrap();
+    program_test.add_account(vault, account(id(), data));
+
+    let token_mint = Pubkey::new_unique();
+    let store = Pubkey::new_unique();
+    program_test.add_account(store, token_account(token_mint, vault_pda, STORED));
+    let destination = Pubkey::new_unique();
+    program_test.add_account(destination, token_account(token_mint, holder, 0));
+
+    let safety_deposit_box = Pubkey::new_unique();
+    let mut data = vec![0; MAX_SAFETY_DEPOSIT_V2_SIZE];
+    SafetyDepositBox {
+        key: Key::SafetyDepositBoxV2,
+        vault,
+        token_mint,
+        store,
+        order: 0,
+    }
+    .serialize(&mut data.as_mut_slice())
+    .unwrap();
+    program_test.add_account(safety_deposit_box, account(id(), data));
+
+    VaultAccounts {
+        vault,
+        fraction_mint,
+        shares,
+        safety_deposit_box,
+        store,
+        destination,
+    }
+}
+
+/// Adds a buyout auction of the vault in the AuctionDataV2 layout, with the bid count but not the
+/// bids themselves.
+fn add_buyout_auction(
+    program_test: &mut ProgramTest,
+    vault: &Pubkey,
+    state: u8,
+    ended_at: Option<i64>,
+    num_bids: u64,
+) {
+    let mut data = vec![0; 200];
+    data[64] = 2;
+    data[65] = state;
+    if let Some(ended_at) = ended_at {
+        data[68] = 1 << 1;
+        data[80..88].copy_from_slice(&ended_at.to_le_bytes());
+    }
+    data[144..152].copy_from_slice(&num_bids.to_le_bytes());
+    program_test.add_account(
+        find_buyout_auction_address(vault),
+        account(Pubkey::from_str(BUYOUT_AUCTION_PROGRAM).unwrap(), data),
+    );
+}
+
+async fn in_kind_redeem(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    holder: &Keypair,
+    accounts: &VaultAccounts,
+) -> Result<(), TransportError> {
+    let recent_blockhash = banks_client.get_recent_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::create_in_kind_redeem_instruction(
+            id(),
+            accounts.shares,
+            accounts.fraction_mint,
+            accounts.vault,
+            holder.pubkey(),
+            &[(
+                accounts.safety_deposit_box,
+                accounts.store,
+                accounts.destination,
+            )],
+            SUPPLY / 2,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, holder],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn in_kind_redeem_success() {
+    let holder = Keypair::new();
+    let authority = Pubkey::new_unique();
+    let mut program_test = program_test();
+    let accounts = add_vault_accounts(&mut program_test, holder.pubkey(), authority, authority);
+    // A cancelled buyout leaves the boxes to the holders.
+    add_buyout_auction(&mut program_test, &accounts.vault, 3, None, 0);
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    in_kind_redeem(&mut banks_client, &payer, &holder, &accounts)
+        .await
+        .expect("in_kind_redeem");
+
+    assert_eq!(
+        token_amount(&mut banks_client, accounts.destination).await,
+        STORED / 2
+    );
+    assert_eq!(
+        token_amount(&mut banks_client, accounts.store).await,
+        STORED / 2
+    );
+    assert_eq!(token_amount(&mut banks_client, accounts.shares).await, 0);
+}
+
+#[tokio::test]
+async fn in_kind_redeem_fail_authority_changed() {
+    // Once the vault is handed to an auction manager, the boxes it sells have to stay whole.
+    let holder = Keypair::new();
+    let mut program_test = program_test();
+    let accounts = add_vault_accounts(
+        &mut program_test,
+        holder.pubkey(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    let err = in_kind_redeem(&mut banks_client, &payer, &holder, &accounts)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::VaultAuthorityChanged as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn in_kind_redeem_fail_buyout_started() {
+    let holder = Keypair::new();
+    let authority = Pubkey::new_unique();
+    let mut program_test = program_test();
+    let accounts = add_vault_accounts(&mut program_test, holder.pubkey(), authority, authority);
+    add_buyout_auction(&mut program_test, &accounts.vault, 1, None, 0);
+
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    let err = in_kind_redeem(&mut banks_client, &payer, &holder, &accounts)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::BuyoutInProgress as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn in_kind_redeem_success_buyout_ended_without_bids() {
+    // Nobody bid before the buyout ended, it stays started but will never take the boxes.
+    let holder = Keypair::new();
+    let authority = Pubkey::new_unique();
+    let mut program_test = program_test();
+    let accounts = add_vault_accounts(&mut program_test, holder.pubkey(), authority, authority);
+    add_buyout_auction(&mut program_test, &accounts.vault, 1, Some(1), 0);
+
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    in_kind_redeem(&mut banks_client, &payer, &holder, &accounts)
+        .await
+        .expect("in_kind_redeem");
+    assert_eq!(
+        token_amount(&mut banks_client, accounts.destination).await,
+        STORED / 2
+    );
+}
+
+#[tokio::test]
+async fn in_kind_redeem_fail_buyout_ended_with_bids() {
+    // The winner of an ended buyout is yet to claim the boxes.
+    let holder = Keypair::new();
+    let authority = Pubkey::new_unique();
+    let mut program_test = program_test();
+    let accounts = add_vault_accounts(&mut program_test, holder.pubkey(), authority, authority);
+    add_buyout_auction(&mut program_test, &accounts.vault, 1, Some(1), 1);
+
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    let err = in_kind_redeem(&mut banks_client, &payer, &holder, &accounts)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::BuyoutInProgress as u32)
+        )
+    );
+}
@@ -0,0 +1,262 @@
+#![cfg(feature = "test-bpf")]
+mod utils;
+
+use borsh::BorshSerialize;
+use metaplex_token_vault::{
+    error::VaultError,
+    id, instruction,
+    state::{
+        Key, SafetyDepositBox, Vault, VaultState, MAX_SAFETY_DEPOSIT_SIZE,
+        MAX_SAFETY_DEPOSIT_V2_SIZE, MAX_VAULT_SIZE, MAX_VAULT_V2_SIZE, PREFIX,
+    },
+};
+use solana_program::{
+    account_info::AccountInfo, borsh::try_from_slice_unchecked, program_error::ProgramError,
+    program_option::COption, program_pack::Pack,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use utils::*;
+
+fn vault(key: Key, token_type_count: u64) -> Vault {
+    Vault {
+        key,
+        token_program: spl_token::id(),
+        fraction_mint: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        fraction_treasury: Pubkey::new_unique(),
+        redeem_treasury: Pubkey::new_unique(),
+        allow_further_share_creation: true,
+        pricing_lookup_address: Pubkey::new_unique(),
+        token_type_count,
+        state: VaultState::Inactive,
+        locked_price_per_share: 7,
+        original_authority: Pubkey::default(),
+    }
+}
+
+fn safety_deposit_box(key: Key, order: u64) -> SafetyDepositBox {
+    SafetyDepositBox {
+        key,
+        vault: Pubkey::new_unique(),
+        token_mint: Pubkey::new_unique(),
+        store: Pubkey::new_unique(),
+        order,
+    }
+}
+
+/// Runs `f` on an account of this program holding `data`.
+fn with_account<T>(data: &mut [u8], f: impl FnOnce(&AccountInfo) -> T) -> T {
+    let key = Pubkey::new_unique();
+    let owner = id();
+    let mut lamports = 0;
+    f(&AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        data,
+        &owner,
+        false,
+        0,
+    ))
+}
+
+/// Saves the vault into an account of `size` and reads it back.
+fn save_vault(vault: &Vault, size: usize) -> Result<Vault, ProgramError> {
+    with_account(&mut vec![0; size], |account_info| {
+        vault.save(account_info)?;
+        assert_eq!(
+            Vault::get_token_type_count(account_info),
+            vault.token_type_count
+        );
+        Vault::from_account_info(account_info)
+    })
+}
+
+/// Saves the box into an account of `size` and reads it back.
+fn save_safety_deposit_box(
+    safety_deposit_box: &SafetyDepositBox,
+    size: usize,
+) -> Result<SafetyDepositBox, ProgramError> {
+    with_account(&mut vec![0; size], |account_info| {
+        safety_deposit_box.save(account_info)?;
+        assert_eq!(
+            SafetyDepositBox::get_order(account_info),
+            safety_deposit_box.order
+        );
+        SafetyDepositBox::from_account_info(account_info)
+    })
+}
+
+#[test]
+fn vault_round_trips_both_layouts() {
+    let v1 = vault(Key::VaultV1, 255);
+    let saved = save_vault(&v1, MAX_VAULT_SIZE).unwrap();
+    assert_eq!(saved.try_to_vec().unwrap(), v1.try_to_vec().unwrap());
+
+    let v2 = Vault {
+        original_authority: Pubkey::new_unique(),
+        ..vault(Key::VaultV2, 1_000)
+    };
+    let saved = save_vault(&v2, MAX_VAULT_V2_SIZE).unwrap();
+    assert_eq!(saved.try_to_vec().unwrap(), v2.try_to_vec().unwrap());
+}
+
+#[test]
+fn vault_v1_fail_save_past_255_boxes() {
+    let err = save_vault(&vault(Key::VaultV1, 256), MAX_VAULT_SIZE)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        ProgramError::Custom(VaultError::VaultNeedsMigration as u32)
+    );
+}
+
+#[test]
+fn safety_deposit_box_round_trips_both_layouts() {
+    let v1 = safety_deposit_box(Key::SafetyDepositBoxV1, 255);
+    let saved = save_safety_deposit_box(&v1, MAX_SAFETY_DEPOSIT_SIZE).unwrap();
+    assert_eq!(saved.try_to_vec().unwrap(), v1.try_to_vec().unwrap());
+
+    let v2 = safety_deposit_box(Key::SafetyDepositBoxV2, 1_000);
+    let saved = save_safety_deposit_box(&v2, MAX_SAFETY_DEPOSIT_V2_SIZE).unwrap();
+    assert_eq!(saved.try_to_vec().unwrap(), v2.try_to_vec().unwrap());
+
+    let err = save_safety_deposit_box(
+        &safety_deposit_box(Key::SafetyDepositBoxV1, 256),
+        MAX_SAFETY_DEPOSIT_SIZE,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        err,
+        ProgramError::Custom(VaultError::VaultNeedsMigration as u32)
+    );
+}
+
+#[tokio::test]
+async fn migrate_vault_to_v2_success() {
+    let authority = Keypair::new();
+    let old_vault = Pubkey::new_unique();
+    let (old_pda, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), id().as_ref(), old_vault.as_ref()],
+        &id(),
+    );
+    let new_vault = Pubkey::new_unique();
+    let (new_pda, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), id().as_ref(), new_vault.as_ref()],
+        &id(),
+    );
+    let mut program_test = program_test();
+
+    let fraction_mint = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(old_pda),
+        supply: 0,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::Some(old_pda),
+    }
+    .pack_into_slice(&mut data);
+    program_test.add_account(fraction_mint, account(spl_token::id(), data));
+
+    let fraction_treasury = Pubkey::new_unique();
+    program_test.add_account(fraction_treasury, token_account(fraction_mint, old_pda, 0));
+    let redeem_treasury = Pubkey::new_unique();
+    program_test.add_account(
+        redeem_treasury,
+        token_account(Pubkey::new_unique(), old_pda, 0),
+    );
+
+    let vault_v1 = Vault {
+        fraction_mint,
+        authority: authority.pubkey(),
+        fraction_treasury,
+        redeem_treasury,
+        ..vault(Key::VaultV1, 1)
+    };
+    let mut data = vec![0; MAX_VAULT_SIZE];
+    with_account(&mut data, |account_info| vault_v1.save(account_info)).unwrap();
+    program_test.add_account(old_vault, account(id(), data));
+    program_test.add_account(new_vault, account(id(), vec![0; MAX_VAULT_V2_SIZE]));
+
+    let token_mint = Pubkey::new_unique();
+    let store = Pubkey::new_unique();
+    program_test.add_account(store, token_account(token_mint, old_pda, 1));
+    let old_box = Pubkey::new_unique();
+    let box_v1 = SafetyDepositBox {
+        key: Key::SafetyDepositBoxV1,
+        vault: old_vault,
+        token_mint,
+        store,
+        order: 0,
+    };
+    let mut data = vec![0; MAX_SAFETY_DEPOSIT_SIZE];
+    with_account(&mut data, |account_info| box_v1.save(account_info)).unwrap();
+    program_test.add_account(old_box, account(id(), data));
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::create_migrate_vault_to_v2_instruction(
+            id(),
+            old_vault,
+            new_vault,
+            fraction_mint,
+            fraction_treasury,
+            redeem_treasury,
+            authority.pubkey(),
+            payer.pubkey(),
+            &[(old_box, token_mint, store)],
+        )],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("migrate_vault_to_v2");
+
+    let new_vault_account = banks_client.get_account(new_vault).await.unwrap().unwrap();
+    let migrated: Vault = try_from_slice_unchecked(&new_vault_account.data).unwrap();
+    assert!(migrated.key == Key::VaultV2);
+    assert_eq!(migrated.token_type_count, 1);
+    assert_eq!(migrated.original_authority, authority.pubkey());
+    assert_eq!(migrated.fraction_mint, fraction_mint);
+
+    let (new_box, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), new_vault.as_ref(), token_mint.as_ref()],
+        &id(),
+    );
+    let new_box_account = banks_client.get_account(new_box).await.unwrap().unwrap();
+    let migrated: SafetyDepositBox = try_from_slice_unchecked(&new_box_account.data).unwrap();
+    assert!(migrated.key == Key::SafetyDepositBoxV2);
+    assert_eq!(migrated.vault, new_vault);
+    assert_eq!(migrated.store, store);
+    assert_eq!(migrated.order, 0);
+
+    // Everything the vault holds is handed to the pda of the new vault.
+    for key in [fraction_treasury, redeem_treasury, store].iter() {
+        let account = banks_client.get_account(*key).await.unwrap().unwrap();
+        let account = spl_token::state::Account::unpack(&account.data).unwrap();
+        assert_eq!(account.owner, new_pda);
+    }
+    assert_eq!(token_amount(&mut banks_client, store).await, 1);
+    let account = banks_client
+        .get_account(fraction_mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mint = spl_token::state::Mint::unpack(&account.data).unwrap();
+    assert_eq!(mint.mint_authority, COption::Some(new_pda));
+
+    assert!(banks_client.get_account(old_vault).await.unwrap().is_none());
+    assert!(banks_client.get_account(old_box).await.unwrap().is_none());
+}
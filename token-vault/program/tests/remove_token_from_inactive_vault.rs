@@ -0,0 +1,198 @@
+#![cfg(feature = "test-bpf")]
+mod utils;
+
+use borsh::BorshSerialize;
+use metaplex_token_vault::{
+    error::VaultError,
+    id, instruction,
+    state::{
+        Key, SafetyDepositBox, Vault, VaultState, MAX_SAFETY_DEPOSIT_V2_SIZE, MAX_VAULT_V2_SIZE,
+        PREFIX,
+    },
+};
+use solana_program::borsh::try_from_slice_unchecked;
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    transport::TransportError,
+};
+use utils::*;
+
+const STORED: u64 = 100;
+
+struct SafetyDepositAccounts {
+    safety_deposit_box: Pubkey,
+    store: Pubkey,
+    token_mint: Pubkey,
+}
+
+/// Sets up an inactive vault under the authority holding one box per order, each with STORED
+/// tokens.
+fn add_vault_accounts(
+    program_test: &mut ProgramTest,
+    authority: Pubkey,
+    boxes: u64,
+) -> (Pubkey, Vec<SafetyDepositAccounts>) {
+    let vault = Pubkey::new_unique();
+    let (vault_pda, _) =
+        Pubkey::find_program_address(&[PREFIX.as_bytes(), id().as_ref(), vault.as_ref()], &id());
+
+    let mut data = vec![0; MAX_VAULT_V2_SIZE];
+    Vault {
+        key: Key::VaultV2,
+        token_program: spl_token::id(),
+        fraction_mint: Pubkey::new_unique(),
+        authority,
+        fraction_treasury: Pubkey::new_unique(),
+        redeem_treasury: Pubkey::new_unique(),
+        allow_further_share_creation: false,
+        pricing_lookup_address: Pubkey::new_unique(),
+        token_type_count: boxes,
+        state: VaultState::Inactive,
+        locked_price_per_share: 0,
+        original_authority: authority,
+    }
+    .serialize(&mut data.as_mut_slice())
+    .unwrap();
+    program_test.add_account(vault, account(id(), data));
+
+    let safety_deposits = (0..boxes)
+        .map(|order| {
+            let token_mint = Pubkey::new_unique();
+            let store = Pubkey::new_unique();
+            program_test.add_account(store, token_account(token_mint, vault_pda, STORED));
+
+            let safety_deposit_box = Pubkey::new_unique();
+            let mut data = vec![0; MAX_SAFETY_DEPOSIT_V2_SIZE];
+            SafetyDepositBox {
+                key: Key::SafetyDepositBoxV2,
+                vault,
+                token_mint,
+                store,
+                order,
+            }
+            .serialize(&mut data.as_mut_slice())
+            .unwrap();
+            program_test.add_account(safety_deposit_box, account(id(), data));
+
+            SafetyDepositAccounts {
+                safety_deposit_box,
+                store,
+                token_mint,
+            }
+        })
+        .collect();
+
+    (vault, safety_deposits)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn remove_token(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    authority: &Keypair,
+    vault: Pubkey,
+    removed: &SafetyDepositAccounts,
+    destination: Pubkey,
+    rent_receiver: Pubkey,
+    last_safety_deposit_box: Option<Pubkey>,
+) -> Result<(), TransportError> {
+    let recent_blockhash = banks_client.get_recent_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::create_remove_token_from_inactive_vault_instruction(
+                id(),
+                removed.safety_deposit_box,
+                removed.store,
+                destination,
+                vault,
+                authority.pubkey(),
+                rent_receiver,
+                last_safety_deposit_box,
+            ),
+        ],
+        Some(&payer.pubkey()),
+        &[payer, authority],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn remove_token_moves_last_order_into_the_gap() {
+    let authority = Keypair::new();
+    let rent_receiver = Pubkey::new_unique();
+    let mut program_test = program_test();
+    let (vault, boxes) = add_vault_accounts(&mut program_test, authority.pubkey(), 3);
+    let destination = Pubkey::new_unique();
+    program_test.add_account(
+        destination,
+        token_account(boxes[0].token_mint, authority.pubkey(), 0),
+    );
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    // Removing anything but the last order needs the box holding it.
+    let err = remove_token(
+        &mut banks_client,
+        &payer,
+        &authority,
+        vault,
+        &boxes[0],
+        destination,
+        rent_receiver,
+        None,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::LastSafetyDepositBoxInvalid as u32)
+        )
+    );
+
+    remove_token(
+        &mut banks_client,
+        &payer,
+        &authority,
+        vault,
+        &boxes[0],
+        destination,
+        rent_receiver,
+        Some(boxes[2].safety_deposit_box),
+    )
+    .await
+    .expect("remove_token_from_inactive_vault");
+
+    assert_eq!(token_amount(&mut banks_client, destination).await, STORED);
+
+    // The box and its store are gone, their rent paid to the receiver.
+    for closed in [boxes[0].safety_deposit_box, boxes[0].store].iter() {
+        let account = banks_client.get_account(*closed).await.unwrap();
+        assert!(account.is_none());
+    }
+    let rent_receiver = banks_client
+        .get_account(rent_receiver)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rent_receiver.lamports, 2_000_000_000);
+
+    // The last box took the order of the removed one.
+    let last = banks_client
+        .get_account(boxes[2].safety_deposit_box)
+        .await
+        .unwrap()
+        .unwrap();
+    let last: SafetyDepositBox = try_from_slice_unchecked(&last.data).unwrap();
+    assert_eq!(last.order, 0);
+
+    let vault = banks_client.get_account(vault).await.unwrap().unwrap();
+    let vault: Vault = try_from_slice_unchecked(&vault.data).unwrap();
+    assert_eq!(vault.token_type_count, 2);
+}
@@ -0,0 +1,394 @@
+#![cfg(feature = "test-bpf")]
+mod utils;
+
+use borsh::BorshSerialize;
+use metaplex_token_vault::{
+    error::VaultError,
+    id, instruction,
+    state::{
+        ExternalPriceAccount, Key, ReserveVote, ReserveVotes, Vault, VaultState,
+        MAX_EXTERNAL_ACCOUNT_SIZE, MAX_RESERVE_PRICES, MAX_RESERVE_VOTES_SIZE, MAX_VAULT_V2_SIZE,
+        PREFIX, VOTES,
+    },
+};
+use solana_program::{program_error::ProgramError, program_option::COption, program_pack::Pack};
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    transport::TransportError,
+};
+use utils::*;
+
+const SUPPLY: u64 = 10;
+const EXTERNAL_PRICE: u64 = 10;
+
+fn reserve_votes() -> ReserveVotes {
+    ReserveVotes {
+        key: Key::ReserveVotesV1,
+        vault: Pubkey::new_unique(),
+        escrow: Pubkey::new_unique(),
+        total_shares: 0,
+        last_vote_id: 0,
+        votes: vec![],
+    }
+}
+
+#[test]
+fn reserve_votes_median_is_share_weighted() {
+    let mut votes = reserve_votes();
+    assert_eq!(votes.median(), None);
+
+    votes.add(30, 2).unwrap();
+    votes.add(10, 3).unwrap();
+    votes.add(20, 4).unwrap();
+    assert_eq!(votes.total_shares, 9);
+    let prices: Vec<u64> = votes
+        .votes
+        .iter()
+        .map(|vote| vote.price_per_share)
+        .collect();
+    assert_eq!(prices, vec![10, 20, 30]);
+    // Half of the 9 locked shares, rounded up, voted for 20 or under.
+    assert_eq!(votes.median(), Some(20));
+
+    // More shares on a price already voted for are counted with the same id.
+    let id = votes.votes[0].id;
+    assert_eq!(votes.add(10, 4).unwrap(), id);
+    assert_eq!(votes.median(), Some(10));
+}
+
+#[test]
+fn reserve_votes_has_quorum() {
+    let mut votes = reserve_votes();
+    votes.add(10, 19).unwrap();
+    assert!(!votes.has_quorum(100));
+
+    votes.add(10, 1).unwrap();
+    assert!(votes.has_quorum(100));
+    assert!(votes.has_quorum(0));
+}
+
+#[test]
+fn reserve_votes_remove() {
+    let mut votes = reserve_votes();
+    let id = votes.add(10, 5).unwrap();
+    votes.add(20, 5).unwrap();
+
+    votes.remove(10, 2, id).unwrap();
+    assert_eq!(votes.total_shares, 8);
+    assert_eq!(votes.votes[0].shares, 3);
+
+    // Nobody left voting for the price drops it.
+    votes.remove(10, 3, id).unwrap();
+    assert_eq!(votes.total_shares, 5);
+    assert_eq!(votes.votes.len(), 1);
+    assert_eq!(votes.median(), Some(20));
+
+    // A vote counted under an id that is gone has nothing left to take out.
+    votes.remove(20, 5, id).unwrap();
+    assert_eq!(votes.total_shares, 5);
+}
+
+#[test]
+fn reserve_votes_evict_smallest() {
+    let mut votes = reserve_votes();
+    let evicted_id = votes.add(1, 1).unwrap();
+    for price in 2..=MAX_RESERVE_PRICES as u64 {
+        votes.add(price, 10).unwrap();
+    }
+
+    // Once every price is taken, a new one needs more shares than the smallest to get in.
+    assert_eq!(
+        votes.add(1_000, 1),
+        Err(ProgramError::Custom(
+            VaultError::TooManyReservePrices as u32
+        ))
+    );
+
+    let id = votes.add(1_000, 5).unwrap();
+    assert_eq!(votes.votes.len(), MAX_RESERVE_PRICES);
+    assert_eq!(votes.votes[0].price_per_share, 2);
+    assert_eq!(votes.votes.last().unwrap().id, id);
+    assert_eq!(votes.total_shares, 10 * (MAX_RESERVE_PRICES as u64 - 1) + 5);
+
+    // Shares counted under the evicted price were dropped with it.
+    votes.remove(1, 1, evicted_id).unwrap();
+    assert_eq!(votes.total_shares, 10 * (MAX_RESERVE_PRICES as u64 - 1) + 5);
+}
+
+struct VaultAccounts {
+    vault: Pubkey,
+    fraction_mint: Pubkey,
+    fraction_treasury: Pubkey,
+    redeem_treasury: Pubkey,
+    external_pricing: Pubkey,
+    shares: Pubkey,
+    payment: Pubkey,
+    reserve_votes: Pubkey,
+}
+
+/// Sets up an active vault whose SUPPLY shares all circulate, half of them with the holder, who
+/// has enough of the price mint to buy out the other half.
+fn add_vault_accounts(
+    program_test: &mut ProgramTest,
+    holder: Pubkey,
+    authority: Pubkey,
+) -> VaultAccounts {
+    let vault = Pubkey::new_unique();
+    let (vault_pda, _) =
+        Pubkey::find_program_address(&[PREFIX.as_bytes(), id().as_ref(), vault.as_ref()], &id());
+
+    let fraction_mint = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(vault_pda),
+        supply: SUPPLY,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    program_test.add_account(fraction_mint, account(spl_token::id(), data));
+
+    let shares = Pubkey::new_unique();
+    program_test.add_account(shares, token_account(fraction_mint, holder, SUPPLY / 2));
+    let fraction_treasury = Pubkey::new_unique();
+    program_test.add_account(
+        fraction_treasury,
+        token_account(fraction_mint, vault_pda, 0),
+    );
+
+    let price_mint = Pubkey::new_unique();
+    let redeem_treasury = Pubkey::new_unique();
+    program_test.add_account(redeem_treasury, token_account(price_mint, vault_pda, 0));
+    let payment = Pubkey::new_unique();
+    program_test.add_account(payment, token_account(price_mint, holder, 1_000));
+
+    let external_pricing = Pubkey::new_unique();
+    let mut data = vec![0; MAX_EXTERNAL_ACCOUNT_SIZE];
+    ExternalPriceAccount {
+        key: Key::ExternalAccountKeyV1,
+        price_per_share: EXTERNAL_PRICE,
+        price_mint,
+        allowed_to_combine: true,
+    }
+    .serialize(&mut data.as_mut_slice())
+    .unwrap();
+    program_test.add_account(external_pricing, account(id(), data));
+
+    let mut data = vec![0; MAX_VAULT_V2_SIZE];
+    Vault {
+        key: Key::VaultV2,
+        token_program: spl_token::id(),
+        fraction_mint,
+        authority,
+        fraction_treasury,
+        redeem_treasury,
+        allow_further_share_creation: false,
+        pricing_lookup_address: external_pricing,
+        token_type_count: 0,
+        state: VaultState::Active,
+        locked_price_per_share: 0,
+        original_authority: authority,
+    }
+    .serialize(&mut data.as_mut_slice())
+    .unwrap();
+    program_test.add_account(vault, account(id(), data));
+
+    let (reserve_votes, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            id().as_ref(),
+            vault.as_ref(),
+            VOTES.as_bytes(),
+        ],
+        &id(),
+    );
+
+    VaultAccounts {
+        vault,
+        fraction_mint,
+        fraction_treasury,
+        redeem_treasury,
+        external_pricing,
+        shares,
+        payment,
+        reserve_votes,
+    }
+}
+
+/// Adds the reserve votes of the vault, `shares` of them voting for `price_per_share`.
+fn add_reserve_votes(
+    program_test: &mut ProgramTest,
+    accounts: &VaultAccounts,
+    price_per_share: u64,
+    shares: u64,
+) {
+    let mut data = vec![0; MAX_RESERVE_VOTES_SIZE];
+    ReserveVotes {
+        key: Key::ReserveVotesV1,
+        vault: accounts.vault,
+        escrow: Pubkey::new_unique(),
+        total_shares: shares,
+        last_vote_id: 1,
+        votes: vec![ReserveVote {
+            price_per_share,
+            shares,
+            id: 1,
+        }],
+    }
+    .serialize(&mut data.as_mut_slice())
+    .unwrap();
+    program_test.add_account(accounts.reserve_votes, account(id(), data));
+}
+
+async fn combine_vault(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    holder: &Keypair,
+    authority: &Keypair,
+    accounts: &VaultAccounts,
+    with_reserve_votes: bool,
+) -> Result<(), TransportError> {
+    let (burn_authority, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), id().as_ref(), accounts.vault.as_ref()],
+        &id(),
+    );
+    let mut instruction = instruction::create_combine_vault_instruction(
+        id(),
+        accounts.vault,
+        accounts.shares,
+        accounts.payment,
+        accounts.fraction_mint,
+        accounts.fraction_treasury,
+        accounts.redeem_treasury,
+        holder.pubkey(),
+        authority.pubkey(),
+        holder.pubkey(),
+        burn_authority,
+        accounts.external_pricing,
+    );
+    if !with_reserve_votes {
+        instruction.accounts.pop();
+    }
+
+    let recent_blockhash = banks_client.get_recent_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer, holder, authority],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn combine_vault_without_votes_uses_external_price() {
+    // Vaults nobody voted on keep combining at the price of the external pricing account.
+    let holder = Keypair::new();
+    let authority = Keypair::new();
+    let mut program_test = program_test();
+    let accounts = add_vault_accounts(&mut program_test, holder.pubkey(), authority.pubkey());
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    combine_vault(
+        &mut banks_client,
+        &payer,
+        &holder,
+        &authority,
+        &accounts,
+        true,
+    )
+    .await
+    .expect("combine_vault");
+
+    assert_eq!(
+        token_amount(&mut banks_client, accounts.redeem_treasury).await,
+        SUPPLY / 2 * EXTERNAL_PRICE
+    );
+}
+
+#[tokio::test]
+async fn combine_vault_with_votes_uses_their_median() {
+    let holder = Keypair::new();
+    let authority = Keypair::new();
+    let mut program_test = program_test();
+    let accounts = add_vault_accounts(&mut program_test, holder.pubkey(), authority.pubkey());
+    add_reserve_votes(&mut program_test, &accounts, 2 * EXTERNAL_PRICE, SUPPLY / 2);
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    combine_vault(
+        &mut banks_client,
+        &payer,
+        &holder,
+        &authority,
+        &accounts,
+        true,
+    )
+    .await
+    .expect("combine_vault");
+
+    assert_eq!(
+        token_amount(&mut banks_client, accounts.redeem_treasury).await,
+        SUPPLY / 2 * 2 * EXTERNAL_PRICE
+    );
+}
+
+#[tokio::test]
+async fn combine_vault_fail_votes_without_quorum() {
+    let holder = Keypair::new();
+    let authority = Keypair::new();
+    let mut program_test = program_test();
+    let accounts = add_vault_accounts(&mut program_test, holder.pubkey(), authority.pubkey());
+    add_reserve_votes(&mut program_test, &accounts, 2 * EXTERNAL_PRICE, 1);
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    let err = combine_vault(
+        &mut banks_client,
+        &payer,
+        &holder,
+        &authority,
+        &accounts,
+        true,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::ReserveQuorumNotReached as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn combine_vault_fail_without_reserve_votes_account() {
+    // While shares circulate, the votes can't be skipped by leaving their account out.
+    let holder = Keypair::new();
+    let authority = Keypair::new();
+    let mut program_test = program_test();
+    let accounts = add_vault_accounts(&mut program_test, holder.pubkey(), authority.pubkey());
+    add_reserve_votes(&mut program_test, &accounts, 2 * EXTERNAL_PRICE, SUPPLY / 2);
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    let err = combine_vault(
+        &mut banks_client,
+        &payer,
+        &holder,
+        &authority,
+        &accounts,
+        false,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+}
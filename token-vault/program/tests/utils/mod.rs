@@ -0,0 +1,45 @@
+use metaplex_token_vault::{id, processor::process_instruction};
+use solana_program::{program_option::COption, program_pack::Pack};
+use solana_program_test::*;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new(
+        "metaplex_token_vault",
+        id(),
+        processor!(process_instruction),
+    )
+}
+
+pub fn account(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+pub fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    account(spl_token::id(), data)
+}
+
+pub async fn token_amount(banks_client: &mut BanksClient, key: Pubkey) -> u64 {
+    let account = banks_client.get_account(key).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}